@@ -0,0 +1,96 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+struct WaitState {
+    // Clients blocked on each key, in arrival order
+    queues: HashMap<String, VecDeque<u64>>,
+    // Bumped whenever a watched key may have become ready
+    version: u64,
+    next_id: u64,
+}
+
+// Parks clients blocked on keys until a write wakes them up
+pub struct BlockingManager {
+    state: Mutex<WaitState>,
+    changed: Condvar,
+}
+
+pub struct Waiter {
+    id: u64,
+    keys: Vec<String>,
+    seen: u64,
+}
+
+impl BlockingManager {
+    pub fn new() -> Self {
+        BlockingManager {
+            state: Mutex::new(WaitState {
+                queues: HashMap::new(),
+                version: 0,
+                next_id: 0,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    // Must be called with the keyspace locked so no write slips in unnoticed
+    pub fn register(&self, keys: &[String]) -> Waiter {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        for key in keys {
+            state.queues.entry(key.clone()).or_default().push_back(id);
+        }
+        Waiter {
+            id,
+            keys: keys.to_vec(),
+            seen: state.version,
+        }
+    }
+
+    pub fn unregister(&self, waiter: &Waiter) {
+        let mut state = self.state.lock().unwrap();
+        for key in &waiter.keys {
+            if let Some(queue) = state.queues.get_mut(key) {
+                queue.retain(|&id| id != waiter.id);
+                if queue.is_empty() {
+                    state.queues.remove(key);
+                }
+            }
+        }
+        // The next client in line may now be entitled to the data
+        state.version += 1;
+        self.changed.notify_all();
+    }
+
+    // Whether the waiter is at the head of the queue for `key`
+    pub fn is_first(&self, waiter: &Waiter, key: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .queues
+            .get(key)
+            .is_some_and(|queue| queue.front() == Some(&waiter.id))
+    }
+
+    // Wakes clients blocked on `key`; called by writes that add data to it
+    pub fn signal(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.queues.contains_key(key) {
+            state.version += 1;
+            self.changed.notify_all();
+        }
+    }
+
+    // Sleeps until a signal arrives or `timeout` passes
+    pub fn wait(&self, waiter: &mut Waiter, timeout: Duration) {
+        let state = self.state.lock().unwrap();
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.version == waiter.seen)
+            .unwrap();
+        waiter.seen = state.version;
+    }
+}
@@ -0,0 +1,418 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+        send_error, send_resp, send_wrongtype, Command, CommandContext,
+    },
+    memory::Value,
+    resp::Resp,
+    types::bitmap::{
+        count_bits, find_bit, get_bit, parse_offset, set_bit, BitfieldType, Overflow,
+        INVALID_OFFSET, MAX_BIT_OFFSET,
+    },
+};
+
+fn parse_bit(input: &str) -> Result<u8, &'static str> {
+    match input {
+        "0" => Ok(0),
+        "1" => Ok(1),
+        _ => Err("bit is not an integer or out of range"),
+    }
+}
+
+// Resolves a `start end` pair in bytes or bits, Redis style with negative indexes
+// counting from the end, into an inclusive bit range. None if the range is empty.
+fn bit_range(len: usize, start: i64, end: i64, unit_is_bit: bool) -> Option<(u64, u64)> {
+    let total = if unit_is_bit {
+        len as i64 * 8
+    } else {
+        len as i64
+    };
+    let start = if start < 0 { start + total } else { start }.max(0);
+    let end = if end < 0 { end + total } else { end }
+        .max(0)
+        .min(total - 1);
+    if start > end {
+        return None;
+    }
+    if unit_is_bit {
+        Some((start as u64, end as u64))
+    } else {
+        Some((start as u64 * 8, end as u64 * 8 + 7))
+    }
+}
+
+fn parse_unit(input: Option<&String>) -> Result<bool, &'static str> {
+    match input.map(|unit| unit.to_ascii_uppercase()).as_deref() {
+        None | Some("BYTE") => Ok(false),
+        Some("BIT") => Ok(true),
+        Some(_) => Err(errors::SYNTAX),
+    }
+}
+
+pub struct SetbitCommand;
+
+impl Command for SetbitCommand {
+    fn name(&self) -> &'static str {
+        "SETBIT"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let offset_and_bit =
+            parse_offset(&args[1]).and_then(|offset| Ok((offset, parse_bit(&args[2])?)));
+        let (offset, bit) = match offset_and_bit {
+            Ok(parsed) => parsed,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_or_create_bytes(&args[0]) {
+            Ok(bytes) => {
+                let previous = set_bit(bytes, offset, bit);
+                send_resp(&mut ctx.stream, Resp::Integer(previous as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct GetbitCommand;
+
+impl Command for GetbitCommand {
+    fn name(&self) -> &'static str {
+        "GETBIT"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let offset = match parse_offset(&args[1]) {
+            Ok(offset) => offset,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_bytes(&args[0]) {
+            Ok(bytes) => {
+                let bit = bytes.map_or(0, |bytes| get_bit(&bytes, offset));
+                send_resp(&mut ctx.stream, Resp::Integer(bit as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct BitcountCommand;
+
+impl Command for BitcountCommand {
+    fn name(&self) -> &'static str {
+        "BITCOUNT"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let range = match &args[1..] {
+            [] => None,
+            [start, end] | [start, end, _] => {
+                let parsed = parse_int_arg(start)
+                    .and_then(|start| Ok((start, parse_int_arg(end)?, parse_unit(args.get(3))?)));
+                match parsed {
+                    Ok(range) => Some(range),
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                }
+            }
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let bytes = match memory.get_bytes(&args[0]) {
+            Ok(bytes) => bytes.unwrap_or_default(),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let (start, end, unit_is_bit) = range.unwrap_or((0, -1, false));
+        let count = bit_range(bytes.len(), start, end, unit_is_bit)
+            .map_or(0, |(start, end)| count_bits(&bytes, start, end));
+        send_resp(&mut ctx.stream, Resp::Integer(count as i64));
+    }
+}
+
+pub struct BitposCommand;
+
+impl Command for BitposCommand {
+    fn name(&self) -> &'static str {
+        "BITPOS"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if (2..=5).contains(&args.len()) => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let parsed = parse_bit(&args[1]).and_then(|bit| {
+            let start = args.get(2).map(|start| parse_int_arg(start)).transpose()?;
+            let end = args.get(3).map(|end| parse_int_arg(end)).transpose()?;
+            Ok((bit, start.unwrap_or(0), end, parse_unit(args.get(4))?))
+        });
+        let (bit, start, end, unit_is_bit) = match parsed {
+            Ok(parsed) => parsed,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let bytes = match memory.get_bytes(&args[0]) {
+            Ok(Some(bytes)) => bytes,
+            // A missing key is an empty string: no set bits, and clear bits from offset 0
+            Ok(None) => {
+                let position = if bit == 1 { -1 } else { 0 };
+                return send_resp(&mut ctx.stream, Resp::Integer(position));
+            }
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let position = match bit_range(bytes.len(), start, end.unwrap_or(-1), unit_is_bit) {
+            Some((first, last)) => match find_bit(&bytes, bit, first, last) {
+                Some(position) => position as i64,
+                // Without an explicit end the string counts as zero-padded to the right
+                None if bit == 0 && end.is_none() => last as i64 + 1,
+                None => -1,
+            },
+            None => -1,
+        };
+        send_resp(&mut ctx.stream, Resp::Integer(position));
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BitOp {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+pub struct BitopCommand;
+
+impl Command for BitopCommand {
+    fn name(&self) -> &'static str {
+        "BITOP"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let op = match args[0].to_ascii_uppercase().as_str() {
+            "AND" => BitOp::And,
+            "OR" => BitOp::Or,
+            "XOR" => BitOp::Xor,
+            "NOT" => BitOp::Not,
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+        let (destination, keys) = (&args[1], &args[2..]);
+        if matches!(op, BitOp::Not) && keys.len() != 1 {
+            return send_error(
+                &mut ctx.stream,
+                "BITOP NOT must be called with a single source key.",
+            );
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in keys {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+        let mut sources = Vec::with_capacity(keys.len());
+        for key in keys {
+            match memory.get_bytes(key) {
+                Ok(bytes) => sources.push(bytes.unwrap_or_default()),
+                Err(_) => return send_wrongtype(&mut ctx.stream),
+            }
+        }
+
+        // Shorter inputs are zero-padded to the longest one
+        let len = sources.iter().map(|bytes| bytes.len()).max().unwrap_or(0);
+        let byte = |source: &[u8], i: usize| source.get(i).copied().unwrap_or(0);
+        let result: Vec<u8> = (0..len)
+            .map(|i| {
+                let mut values = sources.iter().map(|source| byte(source, i));
+                let first = values.next().unwrap_or(0);
+                match op {
+                    BitOp::And => values.fold(first, |acc, value| acc & value),
+                    BitOp::Or => values.fold(first, |acc, value| acc | value),
+                    BitOp::Xor => values.fold(first, |acc, value| acc ^ value),
+                    BitOp::Not => !first,
+                }
+            })
+            .collect();
+
+        // The destination is overwritten, dropping its TTL; an empty result deletes it
+        expiry_manager.remove_expiry(destination);
+        if result.is_empty() {
+            memory.delete(destination);
+        } else {
+            memory.insert(destination.clone(), Value::String(result));
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+    }
+}
+
+enum BitfieldOp {
+    Get(BitfieldType, u64),
+    Set(BitfieldType, u64, i64, Overflow),
+    Incrby(BitfieldType, u64, i64, Overflow),
+}
+
+// Offsets are absolute bits, or `#n` to address the n-th field of the type's width
+fn parse_field_offset(input: &str, field: BitfieldType) -> Result<u64, &'static str> {
+    match input.strip_prefix('#') {
+        Some(index) => index
+            .parse::<u64>()
+            .ok()
+            .and_then(|index| index.checked_mul(field.bits as u64))
+            .filter(|&offset| offset <= MAX_BIT_OFFSET)
+            .ok_or(INVALID_OFFSET),
+        None => parse_offset(input),
+    }
+}
+
+fn parse_bitfield_ops(args: &[String], read_only: bool) -> Result<Vec<BitfieldOp>, &'static str> {
+    let mut ops = Vec::new();
+    let mut overflow = Overflow::Wrap;
+    let mut index = 0;
+
+    while index < args.len() {
+        let subcommand = args[index].to_ascii_uppercase();
+        if read_only && subcommand != "GET" {
+            return Err("BITFIELD_RO only supports the GET subcommand");
+        }
+        let operands = match subcommand.as_str() {
+            "OVERFLOW" => 1,
+            "GET" => 2,
+            "SET" | "INCRBY" => 3,
+            _ => return Err(errors::SYNTAX),
+        };
+        let operands = args
+            .get(index + 1..=index + operands)
+            .ok_or(errors::SYNTAX)?;
+        index += operands.len() + 1;
+
+        if subcommand == "OVERFLOW" {
+            overflow = Overflow::parse(&operands[0])?;
+            continue;
+        }
+        let field = BitfieldType::parse(&operands[0])?;
+        let offset = parse_field_offset(&operands[1], field)?;
+        ops.push(match subcommand.as_str() {
+            "GET" => BitfieldOp::Get(field, offset),
+            "SET" => BitfieldOp::Set(field, offset, parse_int_arg(&operands[2])?, overflow),
+            _ => BitfieldOp::Incrby(field, offset, parse_int_arg(&operands[2])?, overflow),
+        });
+    }
+
+    Ok(ops)
+}
+
+pub struct BitfieldCommand {
+    pub read_only: bool,
+}
+
+impl Command for BitfieldCommand {
+    fn name(&self) -> &'static str {
+        if self.read_only {
+            "BITFIELD_RO"
+        } else {
+            "BITFIELD"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let ops = match parse_bitfield_ops(&args[1..], self.read_only) {
+            Ok(ops) => ops,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        // Reads alone never create the key
+        let writes = ops.iter().any(|op| !matches!(op, BitfieldOp::Get(..)));
+        if !writes {
+            let bytes = match memory.get_bytes(&args[0]) {
+                Ok(bytes) => bytes.unwrap_or_default(),
+                Err(_) => return send_wrongtype(&mut ctx.stream),
+            };
+            let replies = ops
+                .iter()
+                .map(|op| match op {
+                    BitfieldOp::Get(field, offset) => Resp::Integer(field.read(&bytes, *offset)),
+                    _ => unreachable!(),
+                })
+                .collect();
+            return send_resp(&mut ctx.stream, Resp::Array(replies));
+        }
+
+        let bytes = match memory.get_or_create_bytes(&args[0]) {
+            Ok(bytes) => bytes,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let replies = ops
+            .into_iter()
+            .map(|op| match op {
+                BitfieldOp::Get(field, offset) => Resp::Integer(field.read(bytes, offset)),
+                BitfieldOp::Set(field, offset, value, overflow) => {
+                    let previous = field.read(bytes, offset);
+                    match field.fit(value as i128, overflow) {
+                        Some(value) => {
+                            field.write(bytes, offset, value);
+                            Resp::Integer(previous)
+                        }
+                        None => Resp::BulkString(None),
+                    }
+                }
+                BitfieldOp::Incrby(field, offset, increment, overflow) => {
+                    let previous = field.read(bytes, offset) as i128;
+                    match field.fit(previous + increment as i128, overflow) {
+                        Some(value) => {
+                            field.write(bytes, offset, value);
+                            Resp::Integer(value)
+                        }
+                        None => Resp::BulkString(None),
+                    }
+                }
+            })
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
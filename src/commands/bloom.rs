@@ -0,0 +1,255 @@
+use crate::{
+    commands::command::{
+        error_resp, errors, expire_if_needed, parse_bulk_string_args, send_arity_error, send_error,
+        send_resp, send_wrongtype, Command, CommandContext,
+    },
+    memory::Value,
+    resp::Resp,
+    types::bloom::{BloomFilter, Full, DEFAULT_EXPANSION, MAX_CAPACITY, MAX_EXPANSION},
+};
+
+const TOO_LARGE: &str = "Insufficient memory to create filter";
+
+fn added_resp(added: Result<bool, Full>) -> Resp {
+    match added {
+        Ok(added) => Resp::Integer(added as i64),
+        Err(Full::NonScaling) => error_resp("non scaling filter is full"),
+        Err(Full::MaxExpansions) => error_resp("Maximum expansions reached"),
+    }
+}
+
+pub struct BfReserveCommand;
+
+impl Command for BfReserveCommand {
+    fn name(&self) -> &'static str {
+        "BF.RESERVE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let error_rate = match args[1].parse::<f64>() {
+            Ok(rate) if rate > 0.0 && rate < 1.0 => rate,
+            Ok(_) => return send_error(&mut ctx.stream, "(0 < error rate range < 1)"),
+            Err(_) => return send_error(&mut ctx.stream, "bad error rate"),
+        };
+        let capacity = match args[2].parse::<i64>() {
+            Ok(capacity) if capacity > 0 && capacity as u64 <= MAX_CAPACITY => capacity as u64,
+            Ok(capacity) if capacity > 0 => return send_error(&mut ctx.stream, TOO_LARGE),
+            Ok(_) => return send_error(&mut ctx.stream, "(capacity should be larger than 0)"),
+            Err(_) => return send_error(&mut ctx.stream, "bad capacity"),
+        };
+
+        let mut expansion = None;
+        let mut nonscaling = false;
+        let mut index = 3;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "NONSCALING" => nonscaling = true,
+                "EXPANSION" if index + 1 < args.len() => {
+                    index += 1;
+                    expansion = match args[index].parse::<i64>() {
+                        Ok(expansion) if (1..=MAX_EXPANSION as i64).contains(&expansion) => {
+                            Some(expansion as u64)
+                        }
+                        Ok(expansion) if expansion >= 1 => {
+                            return send_error(
+                                &mut ctx.stream,
+                                "expansion should be less than or equal to 32768",
+                            )
+                        }
+                        Ok(_) => {
+                            return send_error(
+                                &mut ctx.stream,
+                                "expansion should be greater or equal to 1",
+                            )
+                        }
+                        Err(_) => return send_error(&mut ctx.stream, "bad expansion"),
+                    };
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+        if nonscaling && expansion.is_some() {
+            return send_error(&mut ctx.stream, "nonscaling filters cannot expand");
+        }
+        let expansion = match nonscaling {
+            true => None,
+            false => Some(expansion.unwrap_or(DEFAULT_EXPANSION)),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        if memory.data.contains_key(key) {
+            return send_error(&mut ctx.stream, "item exists");
+        }
+        let Some(filter) = BloomFilter::new(error_rate, capacity, expansion) else {
+            return send_error(&mut ctx.stream, TOO_LARGE);
+        };
+        memory.insert(key.clone(), Value::Bloom(filter));
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
+
+// BF.ADD and BF.MADD: adding to a missing key creates a filter with default parameters
+pub struct BfAddCommand {
+    pub multi: bool,
+}
+
+impl Command for BfAddCommand {
+    fn name(&self) -> &'static str {
+        if self.multi {
+            "BF.MADD"
+        } else {
+            "BF.ADD"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 || (self.multi && args.len() > 2) => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let filter = match memory.get_or_create_bloom(&args[0]) {
+            Ok(filter) => filter,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let mut replies: Vec<Resp> = args[1..]
+            .iter()
+            .map(|item| added_resp(filter.add(item.as_bytes())))
+            .collect();
+
+        let reply = if self.multi {
+            Resp::Array(replies)
+        } else {
+            replies.remove(0)
+        };
+        send_resp(&mut ctx.stream, reply);
+    }
+}
+
+// BF.EXISTS and BF.MEXISTS; a missing key contains nothing
+pub struct BfExistsCommand {
+    pub multi: bool,
+}
+
+impl Command for BfExistsCommand {
+    fn name(&self) -> &'static str {
+        if self.multi {
+            "BF.MEXISTS"
+        } else {
+            "BF.EXISTS"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 || (self.multi && args.len() > 2) => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let filter = match memory.get_bloom(&args[0]) {
+            Ok(filter) => filter,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let mut replies: Vec<Resp> = args[1..]
+            .iter()
+            .map(|item| {
+                let found = filter.is_some_and(|filter| filter.contains(item.as_bytes()));
+                Resp::Integer(found as i64)
+            })
+            .collect();
+
+        let reply = if self.multi {
+            Resp::Array(replies)
+        } else {
+            replies.remove(0)
+        };
+        send_resp(&mut ctx.stream, reply);
+    }
+}
+
+pub struct BfInfoCommand;
+
+impl Command for BfInfoCommand {
+    fn name(&self) -> &'static str {
+        "BF.INFO"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let filter = match memory.get_bloom(&args[0]) {
+            Ok(Some(filter)) => filter,
+            Ok(None) => return send_error(&mut ctx.stream, "not found"),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let expansion = match filter.expansion {
+            Some(expansion) => Resp::Integer(expansion as i64),
+            None => Resp::BulkString(None),
+        };
+        let fields = [
+            (
+                "CAPACITY",
+                "Capacity",
+                Resp::Integer(filter.capacity() as i64),
+            ),
+            ("SIZE", "Size", Resp::Integer(filter.size() as i64)),
+            (
+                "FILTERS",
+                "Number of filters",
+                Resp::Integer(filter.layers() as i64),
+            ),
+            (
+                "ITEMS",
+                "Number of items inserted",
+                Resp::Integer(filter.len() as i64),
+            ),
+            ("EXPANSION", "Expansion rate", expansion),
+        ];
+
+        let reply = match args.get(1) {
+            Some(wanted) => match fields
+                .into_iter()
+                .find(|(option, _, _)| wanted.eq_ignore_ascii_case(option))
+            {
+                Some((_, _, value)) => Resp::Array(vec![value]),
+                None => return send_error(&mut ctx.stream, "Invalid information value"),
+            },
+            None => Resp::Array(
+                fields
+                    .into_iter()
+                    .flat_map(|(_, label, value)| [Resp::SimpleString(label.to_string()), value])
+                    .collect(),
+            ),
+        };
+        send_resp(&mut ctx.stream, reply);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    commands::{
+        command::{
+            block_on_keys, errors, expire_if_needed, parse_bulk_string_args, parse_int_arg,
+            parse_timeout_arg, send_arity_error, send_error, send_resp, send_wrongtype, Command,
+            CommandContext,
+        },
+        zsets::drop_if_empty,
+    },
+    resp::Resp,
+    types::zset::format_score,
+};
+
+// Rejects keys that exist but are not sorted sets before the client is parked
+fn check_types(ctx: &mut CommandContext, keys: &[String]) -> bool {
+    let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+    let mut memory = ctx.state.memory.lock().unwrap();
+    keys.iter().all(|key| {
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+        memory.get_zset(key).is_ok()
+    })
+}
+
+pub struct BzpopCommand {
+    pub highest: bool,
+}
+
+impl Command for BzpopCommand {
+    fn name(&self) -> &'static str {
+        if self.highest {
+            "BZPOPMAX"
+        } else {
+            "BZPOPMIN"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (keys, timeout) = args.split_at(args.len() - 1);
+        let timeout = match parse_timeout_arg(&timeout[0]) {
+            Ok(timeout) => timeout,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        if !check_types(ctx, keys) {
+            return send_wrongtype(&mut ctx.stream);
+        }
+
+        let reply = block_on_keys(ctx, keys, timeout, |memory, expiry_manager, key| {
+            let (member, score) = memory
+                .get_zset_mut(key)
+                .ok()
+                .flatten()?
+                .pop(1, self.highest)
+                .pop()?;
+            drop_if_empty(memory, expiry_manager, key);
+            Some(Resp::Array(vec![
+                Resp::BulkString(Some(key.into())),
+                Resp::BulkString(Some(member.into())),
+                Resp::BulkString(Some(format_score(score).into())),
+            ]))
+        });
+        send_resp(&mut ctx.stream, reply.unwrap_or(Resp::NullArray));
+    }
+}
+
+pub struct BzmpopCommand;
+
+impl Command for BzmpopCommand {
+    fn name(&self) -> &'static str {
+        "BZMPOP"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let timeout = match parse_timeout_arg(&args[0]) {
+            Ok(timeout) => timeout,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let numkeys = match parse_int_arg(&args[1]) {
+            Ok(numkeys) if numkeys > 0 => numkeys as usize,
+            Ok(_) => return send_error(&mut ctx.stream, "numkeys should be greater than 0"),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        if args.len() < numkeys + 3 {
+            return send_error(&mut ctx.stream, errors::SYNTAX);
+        }
+
+        let keys = &args[2..numkeys + 2];
+        let highest = match args[numkeys + 2].to_ascii_uppercase().as_str() {
+            "MIN" => false,
+            "MAX" => true,
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+        let count = match &args[numkeys + 3..] {
+            [] => 1,
+            [option, count] if option.eq_ignore_ascii_case("COUNT") => match parse_int_arg(count) {
+                Ok(count) if count > 0 => count as usize,
+                Ok(_) => return send_error(&mut ctx.stream, "count should be greater than 0"),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            },
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+        if !check_types(ctx, keys) {
+            return send_wrongtype(&mut ctx.stream);
+        }
+
+        let reply = block_on_keys(ctx, keys, timeout, |memory, expiry_manager, key| {
+            let popped = memory.get_zset_mut(key).ok().flatten()?.pop(count, highest);
+            if popped.is_empty() {
+                return None;
+            }
+            drop_if_empty(memory, expiry_manager, key);
+            let elements = popped
+                .into_iter()
+                .map(|(member, score)| {
+                    Resp::Array(vec![
+                        Resp::BulkString(Some(member.into())),
+                        Resp::BulkString(Some(format_score(score).into())),
+                    ])
+                })
+                .collect();
+            Some(Resp::Array(vec![
+                Resp::BulkString(Some(key.into())),
+                Resp::Array(elements),
+            ]))
+        });
+        send_resp(&mut ctx.stream, reply.unwrap_or(Resp::NullArray));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_resp,
+        send_wrongtype, Command, CommandContext,
+    },
+    memory::Value,
+    resp::Resp,
+    types::cms::CountMinSketch,
+};
+
+const MISSING: &str = "CMS: key does not exist";
+
+// CMS.INITBYDIM key width depth, or CMS.INITBYPROB key error probability
+pub struct CmsInitCommand {
+    pub by_probability: bool,
+}
+
+impl Command for CmsInitCommand {
+    fn name(&self) -> &'static str {
+        if self.by_probability {
+            "CMS.INITBYPROB"
+        } else {
+            "CMS.INITBYDIM"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let (width, depth) = if self.by_probability {
+            let fraction = |input: &str| input.parse::<f64>().ok().filter(|v| *v > 0.0 && *v < 1.0);
+            let Some(error) = fraction(&args[1]) else {
+                return send_error(&mut ctx.stream, "CMS: invalid overestimation value");
+            };
+            let Some(probability) = fraction(&args[2]) else {
+                return send_error(&mut ctx.stream, "CMS: invalid prob value");
+            };
+            CountMinSketch::dimensions_for(error, probability)
+        } else {
+            let dimension = |input: &str| input.parse::<usize>().ok().filter(|v| *v > 0);
+            let Some(width) = dimension(&args[1]) else {
+                return send_error(&mut ctx.stream, "CMS: invalid width");
+            };
+            let Some(depth) = dimension(&args[2]) else {
+                return send_error(&mut ctx.stream, "CMS: invalid depth");
+            };
+            (width, depth)
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if memory.data.contains_key(&args[0]) {
+            return send_error(&mut ctx.stream, "CMS: key already exists");
+        }
+        let Some(sketch) = CountMinSketch::new(width, depth) else {
+            return send_error(&mut ctx.stream, "CMS: width * depth is too large");
+        };
+        memory.insert(args[0].clone(), Value::Cms(sketch));
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
+
+pub struct CmsIncrbyCommand;
+
+impl Command for CmsIncrbyCommand {
+    fn name(&self) -> &'static str {
+        "CMS.INCRBY"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 && args.len() % 2 == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let mut increments = Vec::with_capacity(args.len() / 2);
+        for pair in args[1..].chunks(2) {
+            match pair[1].parse::<u64>() {
+                Ok(increment) => increments.push((&pair[0], increment)),
+                Err(_) => return send_error(&mut ctx.stream, "CMS: Cannot parse number"),
+            }
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let sketch = match memory.get_cms_mut(&args[0]) {
+            Ok(Some(sketch)) => sketch,
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let counts = increments
+            .into_iter()
+            .map(|(item, increment)| {
+                Resp::Integer(sketch.increment(item.as_bytes(), increment) as i64)
+            })
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(counts));
+    }
+}
+
+pub struct CmsQueryCommand;
+
+impl Command for CmsQueryCommand {
+    fn name(&self) -> &'static str {
+        "CMS.QUERY"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let sketch = match memory.get_cms(&args[0]) {
+            Ok(Some(sketch)) => sketch,
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let counts = args[1..]
+            .iter()
+            .map(|item| Resp::Integer(sketch.query(item.as_bytes()) as i64))
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(counts));
+    }
+}
+
+// CMS.MERGE destination numkeys source [source ...] [WEIGHTS weight [weight ...]]
+pub struct CmsMergeCommand;
+
+impl Command for CmsMergeCommand {
+    fn name(&self) -> &'static str {
+        "CMS.MERGE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let destination = &args[0];
+        let numkeys = match args[1].parse::<usize>() {
+            Ok(numkeys) if numkeys > 0 && 2 + numkeys <= args.len() => numkeys,
+            _ => return send_error(&mut ctx.stream, "CMS: invalid numkeys"),
+        };
+        let sources = &args[2..2 + numkeys];
+        let weights = match &args[2 + numkeys..] {
+            [] => vec![1; numkeys],
+            [option, weights @ ..]
+                if option.eq_ignore_ascii_case("WEIGHTS") && weights.len() == numkeys =>
+            {
+                match weights.iter().map(|weight| weight.parse::<i64>()).collect() {
+                    Ok(weights) => weights,
+                    Err(_) => return send_error(&mut ctx.stream, "CMS: invalid weight value"),
+                }
+            }
+            _ => return send_error(&mut ctx.stream, "CMS: wrong number of keys/weights"),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in std::iter::once(destination).chain(sources) {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        // Sources are copied so the destination may also be one of them
+        let (width, depth) = match memory.get_cms(destination) {
+            Ok(Some(sketch)) => (sketch.width, sketch.depth),
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let mut copies = Vec::with_capacity(numkeys);
+        for key in sources {
+            match memory.get_cms(key) {
+                Ok(Some(sketch)) if sketch.width == width && sketch.depth == depth => {
+                    copies.push(sketch.clone())
+                }
+                Ok(Some(_)) => return send_error(&mut ctx.stream, "CMS: width/depth is not equal"),
+                Ok(None) => return send_error(&mut ctx.stream, MISSING),
+                Err(_) => return send_wrongtype(&mut ctx.stream),
+            }
+        }
+
+        let weighted: Vec<(&CountMinSketch, i64)> = copies.iter().zip(weights).collect();
+        if let Ok(Some(sketch)) = memory.get_cms_mut(destination) {
+            sketch.merge(&weighted);
+        }
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
@@ -1,15 +1,24 @@
-use crate::{expiry_manager::ExpiryManager, memory::Memory, resp::Resp};
+use crate::{
+    blocking::{BlockingManager, Waiter},
+    expiry_manager::ExpiryManager,
+    memory::Memory,
+    resp::Resp,
+};
 use std::{
-    collections::HashMap,
-    io::Write,
+    io::{ErrorKind, Write},
     net::TcpStream,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
+// Blocked clients wake at least this often to notice timeouts and disconnects
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 // Centralized shared state
 pub struct SharedState {
     pub memory: Arc<Mutex<Memory>>,
     pub expiry_manager: Arc<Mutex<ExpiryManager>>,
+    pub blocking: Arc<BlockingManager>,
 }
 
 pub struct CommandContext {
@@ -22,14 +31,26 @@ pub trait Command {
     fn execute(&self, args: &[Resp], ctx: &mut CommandContext);
 }
 
+// Negative SRANDMEMBER and ZRANDMEMBER counts may repeat members, so the key doesn't
+// bound the reply; they are capped at this many picks
+pub const MAX_RANDOM_REPLY: u64 = 1 << 20;
+
 // Centralized error messages
 pub mod errors {
     pub const INVALID_GET_KEY: &str = "invalid GET key";
-    pub const INVALID_SET_KEY: &str = "invalid SET key";
+    pub const NOT_AN_INTEGER: &str = "value is not an integer or out of range";
+    pub const SYNTAX: &str = "syntax error";
+    pub const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
 }
 
-// Helper function for parsing BulkString arguments
+// Helper function for parsing BulkString arguments, for keys, names and options; bytes
+// that aren't valid UTF-8 are replaced
 pub fn parse_bulk_string_arg(arg: &Resp) -> Result<String, &'static str> {
+    parse_bytes_arg(arg).map(|value| String::from_utf8_lossy(&value).into_owned())
+}
+
+// Helper function for BulkString arguments stored as values, which are binary safe
+pub fn parse_bytes_arg(arg: &Resp) -> Result<Vec<u8>, &'static str> {
     if let Resp::BulkString(Some(value)) = arg {
         Ok(value.clone())
     } else {
@@ -37,35 +58,225 @@ pub fn parse_bulk_string_arg(arg: &Resp) -> Result<String, &'static str> {
     }
 }
 
-// Utility function to parse optional flags
-pub fn parse_optional_flags(args: &[Resp]) -> Result<HashMap<String, u64>, &'static str> {
-    let mut flags = HashMap::new();
+// Helper function for commands taking values. Bulk strings are binary safe, and string
+// values, hash values and stream entries are stored byte for byte. Such commands parse
+// their arguments twice: as bytes here for the values, and through
+// parse_bulk_string_args for keys, options, members and field names, which are text.
+pub fn parse_bytes_args(args: &[Resp]) -> Result<Vec<Vec<u8>>, &'static str> {
+    args.iter().map(parse_bytes_arg).collect()
+}
 
-    for chunk in args.chunks(2) {
-        if let [Resp::BulkString(Some(flag)), Resp::BulkString(Some(value))] = chunk {
-            match value.parse::<u64>() {
-                Ok(parsed_value) => {
-                    flags.insert(flag.to_ascii_uppercase(), parsed_value);
-                }
-                Err(_) => return Err("invalid flag value"),
-            }
-        } else {
-            return Err("invalid flag format");
-        }
+// Helper function for commands whose arguments are all BulkStrings
+pub fn parse_bulk_string_args(args: &[Resp]) -> Result<Vec<String>, &'static str> {
+    args.iter().map(parse_bulk_string_arg).collect()
+}
+
+// Helper function for parsing integer arguments
+pub fn parse_int_arg(arg: &str) -> Result<i64, &'static str> {
+    arg.parse::<i64>().map_err(|_| errors::NOT_AN_INTEGER)
+}
+
+// Helper function for parsing blocking timeouts given in (fractional) seconds; 0 blocks forever
+pub fn parse_timeout_arg(arg: &str) -> Result<Option<Duration>, &'static str> {
+    match arg.parse::<f64>() {
+        Ok(seconds) if seconds < 0.0 => Err("timeout is negative"),
+        Ok(0.0) => Ok(None),
+        Ok(seconds) if seconds.is_finite() => Ok(Some(Duration::from_secs_f64(seconds))),
+        _ => Err("timeout is not a float or out of range"),
     }
+}
 
-    Ok(flags)
+// Helper function for parsing BLOCK options given in milliseconds; 0 blocks forever
+pub fn parse_block_arg(arg: &str) -> Result<Option<Duration>, &'static str> {
+    match arg.parse::<i64>() {
+        Ok(ms) if ms < 0 => Err("timeout is negative"),
+        Ok(0) => Ok(None),
+        Ok(ms) => Ok(Some(Duration::from_millis(ms as u64))),
+        Err(_) => Err("timeout is not an integer or out of range"),
+    }
 }
 
 pub fn send_resp(stream: &mut TcpStream, response: Resp) {
-    let encoded_response = response.to_string();
-    let _ = stream.write_all(encoded_response.as_bytes());
+    let mut encoded_response = Vec::new();
+    response.encode(&mut encoded_response);
+    let _ = stream.write_all(&encoded_response);
+}
+
+// Error replies as values, for commands that assemble a reply before sending it or
+// report errors per element. `code` is the leading word clients match on, like NOGROUP.
+pub fn coded_error_resp(code: &str, message: &str) -> Resp {
+    Resp::SimpleError(format!("{} {}", code, message))
+}
+
+pub fn error_resp(message: &str) -> Resp {
+    coded_error_resp("ERR", message)
+}
+
+pub fn wrongtype_resp() -> Resp {
+    Resp::SimpleError(errors::WRONGTYPE.to_owned())
 }
 
 pub fn send_error(stream: &mut TcpStream, message: &str) {
-    let _ = stream.write_all(format!("-ERR {}\r\n", message).as_bytes());
+    send_resp(stream, error_resp(message));
+}
+
+pub fn send_wrongtype(stream: &mut TcpStream) {
+    send_resp(stream, wrongtype_resp());
+}
+
+pub fn send_arity_error(stream: &mut TcpStream, command: &str) {
+    send_error(
+        stream,
+        &format!(
+            "wrong number of arguments for '{}' command",
+            command.to_lowercase()
+        ),
+    );
 }
 
 pub fn send_ok(stream: &mut TcpStream) {
     send_resp(stream, Resp::SimpleString("OK".to_owned()));
 }
+
+// An expiry option as accepted by SET and GETEX
+pub enum Expiry {
+    // Milliseconds from now
+    In(u64),
+    // Milliseconds since the epoch
+    At(u64),
+    Keep,
+    Persist,
+}
+
+// Parses the amount following EX, PX, EXAT or PXAT; amounts that aren't positive or
+// overflow once converted to milliseconds are rejected on behalf of `command`
+pub fn parse_expiry(unit: &str, amount: &str, command: &str) -> Result<Expiry, String> {
+    let invalid = || {
+        format!(
+            "invalid expire time in '{}' command",
+            command.to_lowercase()
+        )
+    };
+    let amount = match parse_int_arg(amount) {
+        Ok(amount) if amount > 0 => amount as u64,
+        Ok(_) => return Err(invalid()),
+        Err(err) => return Err(err.to_owned()),
+    };
+    let ms = match unit {
+        "EX" | "EXAT" => amount.checked_mul(1000).ok_or_else(invalid)?,
+        _ => amount,
+    };
+    Ok(match unit {
+        "EX" | "PX" => Expiry::In(ms),
+        _ => Expiry::At(ms),
+    })
+}
+
+pub fn apply_expiry(expiry_manager: &mut ExpiryManager, key: &str, expiry: Expiry) {
+    match expiry {
+        Expiry::In(ms) => expiry_manager.set_expiry(key, ms),
+        Expiry::At(deadline) => expiry_manager.set_expiry_at(key, deadline),
+        Expiry::Persist => expiry_manager.remove_expiry(key),
+        Expiry::Keep => {}
+    }
+}
+
+// Passive expiry: drop the key if its TTL has elapsed so callers see it as missing, or
+// the parts of its value that have expired
+pub fn expire_if_needed(memory: &mut Memory, expiry_manager: &mut ExpiryManager, key: &str) {
+    if expiry_manager.is_expired(key) {
+        expiry_manager.remove_expiry(key);
+        expiry_manager.set_parts_expiry(key, None);
+        memory.delete(key);
+    } else if expiry_manager.parts_expired(key) {
+        expire_parts(memory, expiry_manager, key);
+    }
+}
+
+// Drops the expired parts of the value at `key`, which may delete it, and schedules the
+// next check
+pub fn expire_parts(memory: &mut Memory, expiry_manager: &mut ExpiryManager, key: &str) {
+    let next = memory.expire_parts(key);
+    if !memory.data.contains_key(key) {
+        expiry_manager.remove_expiry(key);
+    }
+    expiry_manager.set_parts_expiry(key, next);
+}
+
+// Peeks at the socket without blocking to detect clients that hung up while parked
+pub fn client_connected(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let connected = match stream.peek(&mut [0; 1]) {
+        Ok(0) => false,
+        Ok(_) => true,
+        Err(e) => e.kind() == ErrorKind::WouldBlock,
+    };
+    let _ = stream.set_nonblocking(false);
+    connected
+}
+
+// Parks the client until `poll` produces a reply, or returns None once the timeout
+// passes or the client disconnects. `poll` runs under the keyspace lock and is retried
+// whenever a write signals one of `keys`.
+pub fn park_on_keys<F>(
+    ctx: &mut CommandContext,
+    keys: &[String],
+    timeout: Option<Duration>,
+    mut poll: F,
+) -> Option<Resp>
+where
+    F: FnMut(&mut Memory, &mut ExpiryManager, &Waiter) -> Option<Resp>,
+{
+    let state = Arc::clone(&ctx.state);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut waiter = None;
+
+    loop {
+        {
+            let mut expiry_manager = state.expiry_manager.lock().unwrap();
+            let mut memory = state.memory.lock().unwrap();
+            let waiter = waiter.get_or_insert_with(|| state.blocking.register(keys));
+            for key in keys {
+                expire_if_needed(&mut memory, &mut expiry_manager, key);
+            }
+            if let Some(reply) = poll(&mut memory, &mut expiry_manager, waiter) {
+                state.blocking.unregister(waiter);
+                return Some(reply);
+            }
+        }
+
+        let waiter = waiter.as_mut().unwrap();
+        let remaining = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => BLOCK_POLL_INTERVAL,
+        };
+        if remaining.is_zero() || !client_connected(&ctx.stream) {
+            state.blocking.unregister(waiter);
+            return None;
+        }
+        state
+            .blocking
+            .wait(waiter, remaining.min(BLOCK_POLL_INTERVAL));
+    }
+}
+
+// Parks the client until `serve` produces a reply for one of `keys`. `serve` only runs
+// for keys where this client is first in line, so clients blocked on a key are served FIFO.
+pub fn block_on_keys<F>(
+    ctx: &mut CommandContext,
+    keys: &[String],
+    timeout: Option<Duration>,
+    mut serve: F,
+) -> Option<Resp>
+where
+    F: FnMut(&mut Memory, &mut ExpiryManager, &str) -> Option<Resp>,
+{
+    let blocking = Arc::clone(&ctx.state.blocking);
+    park_on_keys(ctx, keys, timeout, |memory, expiry_manager, waiter| {
+        keys.iter()
+            .filter(|key| blocking.is_first(waiter, key))
+            .find_map(|key| serve(memory, expiry_manager, key))
+    })
+}
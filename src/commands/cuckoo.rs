@@ -0,0 +1,101 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_resp,
+        send_wrongtype, Command, CommandContext,
+    },
+    resp::Resp,
+};
+
+// CF.ADD creates a filter with the default capacity when the key is missing
+pub struct CfAddCommand;
+
+impl Command for CfAddCommand {
+    fn name(&self) -> &'static str {
+        "CF.ADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let filter = match memory.get_or_create_cuckoo(&args[0]) {
+            Ok(filter) => filter,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        match filter.add(args[1].as_bytes()) {
+            Ok(()) => send_resp(&mut ctx.stream, Resp::Integer(1)),
+            Err(_) => send_error(&mut ctx.stream, "Filter is full"),
+        }
+    }
+}
+
+// CF.EXISTS and CF.COUNT; a missing key contains nothing
+pub struct CfCountCommand {
+    pub exists: bool,
+}
+
+impl Command for CfCountCommand {
+    fn name(&self) -> &'static str {
+        if self.exists {
+            "CF.EXISTS"
+        } else {
+            "CF.COUNT"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let count = match memory.get_cuckoo(&args[0]) {
+            Ok(Some(filter)) if self.exists => filter.contains(args[1].as_bytes()) as usize,
+            Ok(Some(filter)) => filter.count(args[1].as_bytes()),
+            Ok(None) => 0,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        send_resp(&mut ctx.stream, Resp::Integer(count as i64));
+    }
+}
+
+pub struct CfDelCommand;
+
+impl Command for CfDelCommand {
+    fn name(&self) -> &'static str {
+        "CF.DEL"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_cuckoo_mut(&args[0]) {
+            Ok(Some(filter)) => {
+                let removed = filter.remove(args[1].as_bytes());
+                send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+            }
+            Ok(None) => send_error(&mut ctx.stream, "Not found"),
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
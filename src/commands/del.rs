@@ -1,33 +1,48 @@
 use crate::{
     commands::command::{
-        errors, parse_bulk_string_arg, send_error, send_resp, Command, CommandContext,
+        errors, expire_if_needed, parse_bulk_string_arg, send_error, send_resp, Command,
+        CommandContext,
     },
     resp::Resp,
 };
 
-pub struct DelCommand;
+// DEL key [key ...] and UNLINK, which is the same here since values are always freed
+// inline. Keys that have already expired don't count as deleted.
+pub struct DelCommand {
+    pub unlink: bool,
+}
 
 impl Command for DelCommand {
     fn name(&self) -> &'static str {
-        "DEL"
+        if self.unlink {
+            "UNLINK"
+        } else {
+            "DEL"
+        }
     }
 
     fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
         if args.is_empty() {
-            send_error(&mut ctx.stream, "DEL requires at least one argument");
+            send_error(
+                &mut ctx.stream,
+                &format!("{} requires at least one argument", self.name()),
+            );
             return;
         }
 
         let mut deleted_count = 0;
 
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+
         for arg in args {
             match parse_bulk_string_arg(arg) {
                 Ok(key) => {
-                    let mut memory = ctx.state.memory.lock().unwrap();
+                    expire_if_needed(&mut memory, &mut expiry_manager, &key);
                     if memory.delete(&key).is_some() {
                         deleted_count += 1;
                     }
-                    ctx.state.expiry_manager.lock().unwrap().remove_expiry(&key);
+                    expiry_manager.remove_expiry(&key);
                 }
                 Err(_) => {
                     send_error(&mut ctx.stream, errors::INVALID_GET_KEY);
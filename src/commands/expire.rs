@@ -0,0 +1,177 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error, send_error,
+        send_resp, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    resp::Resp,
+};
+
+// EXPIRE key seconds, PEXPIRE key ms, EXPIREAT key timestamp and PEXPIREAT key
+// ms-timestamp, each with [NX | XX | GT | LT]. A key without a TTL counts as never
+// expiring for GT and LT, and a deadline that has already passed deletes the key.
+pub struct ExpireCommand {
+    pub millis: bool,
+    pub at: bool,
+}
+
+impl Command for ExpireCommand {
+    fn name(&self) -> &'static str {
+        match (self.millis, self.at) {
+            (false, false) => "EXPIRE",
+            (true, false) => "PEXPIRE",
+            (false, true) => "EXPIREAT",
+            (true, true) => "PEXPIREAT",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let (mut nx, mut xx, mut gt, mut lt) = (false, false, false, false);
+        for option in &args[2..] {
+            match option.to_ascii_uppercase().as_str() {
+                "NX" => nx = true,
+                "XX" => xx = true,
+                "GT" => gt = true,
+                "LT" => lt = true,
+                _ => return send_error(&mut ctx.stream, &format!("Unsupported option {}", option)),
+            }
+        }
+        if nx && (xx || gt || lt) {
+            return send_error(
+                &mut ctx.stream,
+                "NX and XX, GT or LT options at the same time are not compatible",
+            );
+        }
+        if gt && lt {
+            return send_error(
+                &mut ctx.stream,
+                "GT and LT options at the same time are not compatible",
+            );
+        }
+
+        let amount = match parse_int_arg(&args[1]) {
+            Ok(amount) => amount,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let now = ExpiryManager::get_now_ms() as i64;
+        let ms = if self.millis {
+            Some(amount)
+        } else {
+            amount.checked_mul(1000)
+        };
+        let base = if self.at { 0 } else { now };
+        let Some(deadline) = ms.and_then(|ms| ms.checked_add(base)) else {
+            return send_error(
+                &mut ctx.stream,
+                &format!(
+                    "invalid expire time in '{}' command",
+                    self.name().to_lowercase()
+                ),
+            );
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        if !memory.data.contains_key(key) {
+            return send_resp(&mut ctx.stream, Resp::Integer(0));
+        }
+        let current = expiry_manager.get_expiry(key).map(|current| current as i64);
+        let allowed = match current {
+            Some(current) => !nx && (!gt || deadline > current) && (!lt || deadline < current),
+            None => !xx && !gt,
+        };
+        if !allowed {
+            return send_resp(&mut ctx.stream, Resp::Integer(0));
+        }
+
+        if deadline <= now {
+            memory.delete(key);
+            expiry_manager.remove_expiry(key);
+        } else {
+            expiry_manager.set_expiry_at(key, deadline as u64);
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(1));
+    }
+}
+
+// TTL and PTTL report the time left, EXPIRETIME and PEXPIRETIME the absolute deadline;
+// all reply -2 for a missing key and -1 for a key without a TTL
+pub struct TtlCommand {
+    pub millis: bool,
+    pub absolute: bool,
+}
+
+impl Command for TtlCommand {
+    fn name(&self) -> &'static str {
+        match (self.millis, self.absolute) {
+            (false, false) => "TTL",
+            (true, false) => "PTTL",
+            (false, true) => "EXPIRETIME",
+            (true, true) => "PEXPIRETIME",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if !memory.data.contains_key(&args[0]) {
+            return send_resp(&mut ctx.stream, Resp::Integer(-2));
+        }
+        let Some(deadline) = expiry_manager.get_expiry(&args[0]) else {
+            return send_resp(&mut ctx.stream, Resp::Integer(-1));
+        };
+        let ms = if self.absolute {
+            deadline
+        } else {
+            deadline.saturating_sub(ExpiryManager::get_now_ms())
+        };
+        // Seconds are rounded to the nearest one, as Redis does
+        let reply = if self.millis { ms } else { (ms + 500) / 1000 };
+        send_resp(&mut ctx.stream, Resp::Integer(reply as i64));
+    }
+}
+
+// PERSIST key, replying 1 when a TTL was removed
+pub struct PersistCommand;
+
+impl Command for PersistCommand {
+    fn name(&self) -> &'static str {
+        "PERSIST"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let persisted =
+            memory.data.contains_key(&args[0]) && expiry_manager.get_expiry(&args[0]).is_some();
+        if persisted {
+            expiry_manager.remove_expiry(&args[0]);
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(persisted as i64));
+    }
+}
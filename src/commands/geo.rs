@@ -0,0 +1,489 @@
+use crate::{
+    commands::{
+        command::{
+            errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+            send_error, send_resp, send_wrongtype, Command, CommandContext,
+        },
+        zsetops::store,
+        zsets::drop_if_empty,
+    },
+    resp::Resp,
+    types::{
+        geo::{self, Shape},
+        zset::{ScoreRange, SortedSet, INVALID_FLOAT},
+    },
+};
+
+// Meters per unit
+fn parse_unit(input: &str) -> Result<f64, &'static str> {
+    match input.to_ascii_lowercase().as_str() {
+        "m" => Ok(1.0),
+        "km" => Ok(1000.0),
+        "ft" => Ok(0.3048),
+        "mi" => Ok(1609.34),
+        _ => Err("unsupported unit provided. please use M, KM, FT, MI"),
+    }
+}
+
+fn parse_float(input: &str) -> Result<f64, &'static str> {
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or(INVALID_FLOAT)
+}
+
+fn parse_coordinates(long: &str, lat: &str) -> Result<(f64, f64), String> {
+    let long = parse_float(long).map_err(str::to_string)?;
+    let lat = parse_float(lat).map_err(str::to_string)?;
+    if !geo::valid_coordinates(long, lat) {
+        return Err(format!(
+            "invalid longitude,latitude pair {:.6},{:.6}",
+            long, lat
+        ));
+    }
+    Ok((long, lat))
+}
+
+fn format_distance(meters: f64, unit: f64) -> Resp {
+    Resp::BulkString(Some(format!("{:.4}", meters / unit).into()))
+}
+
+// Coordinates print with 17 decimals and no trailing zeros, like Redis' "%.17Lf"
+fn format_coordinate(value: f64) -> String {
+    let formatted = format!("{:.17}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+fn coordinates_resp((long, lat): (f64, f64)) -> Resp {
+    Resp::Array(vec![
+        Resp::BulkString(Some(format_coordinate(long).into())),
+        Resp::BulkString(Some(format_coordinate(lat).into())),
+    ])
+}
+
+pub struct GeoaddCommand;
+
+impl Command for GeoaddCommand {
+    fn name(&self) -> &'static str {
+        "GEOADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let (mut nx, mut xx, mut ch) = (false, false, false);
+        let mut index = 1;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "NX" => nx = true,
+                "XX" => xx = true,
+                "CH" => ch = true,
+                _ => break,
+            }
+            index += 1;
+        }
+
+        let triples = &args[index..];
+        if triples.is_empty() || triples.len() % 3 != 0 {
+            return send_error(&mut ctx.stream, errors::SYNTAX);
+        }
+        if nx && xx {
+            return send_error(
+                &mut ctx.stream,
+                "XX and NX options at the same time are not compatible",
+            );
+        }
+
+        let mut elements = Vec::with_capacity(triples.len() / 3);
+        for triple in triples.chunks(3) {
+            match parse_coordinates(&triple[0], &triple[1]) {
+                Ok((long, lat)) => elements.push((geo::score(long, lat), &triple[2])),
+                Err(err) => return send_error(&mut ctx.stream, &err),
+            }
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        let zset = match memory.get_or_create_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let (mut added, mut updated) = (0, 0);
+        for (score, member) in elements {
+            match zset.score(member) {
+                Some(_) if nx => {}
+                Some(current) => {
+                    if current != score {
+                        zset.insert(member.clone(), score);
+                        updated += 1;
+                    }
+                }
+                None if xx => {}
+                None => {
+                    zset.insert(member.clone(), score);
+                    added += 1;
+                }
+            }
+        }
+
+        drop_if_empty(&mut memory, &mut expiry_manager, key);
+        if added > 0 {
+            ctx.state.blocking.signal(key);
+        }
+        let changed = if ch { added + updated } else { added };
+        send_resp(&mut ctx.stream, Resp::Integer(changed));
+    }
+}
+
+pub struct GeodistCommand;
+
+impl Command for GeodistCommand {
+    fn name(&self) -> &'static str {
+        "GEODIST"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 || args.len() == 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let unit = match args.get(3).map_or(Ok(1.0), |unit| parse_unit(unit)) {
+            Ok(unit) => unit,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let zset = match memory.get_zset(&args[0]) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let position = |member: &str| zset?.score(member).map(geo::position);
+        let reply = match (position(&args[1]), position(&args[2])) {
+            (Some(from), Some(to)) => {
+                format_distance(geo::distance(from.0, from.1, to.0, to.1), unit)
+            }
+            _ => Resp::BulkString(None),
+        };
+        send_resp(&mut ctx.stream, reply);
+    }
+}
+
+// GEOPOS and GEOHASH: one reply per member, nil for missing ones
+pub struct GeoLookupCommand {
+    pub hash: bool,
+}
+
+impl Command for GeoLookupCommand {
+    fn name(&self) -> &'static str {
+        if self.hash {
+            "GEOHASH"
+        } else {
+            "GEOPOS"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let zset = match memory.get_zset(&args[0]) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let replies = args[1..]
+            .iter()
+            .map(|member| match zset.and_then(|zset| zset.score(member)) {
+                Some(score) if self.hash => {
+                    Resp::BulkString(Some(geo::geohash_string(score).into()))
+                }
+                Some(score) => coordinates_resp(geo::position(score)),
+                None => Resp::BulkString(None),
+            })
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
+
+enum Origin {
+    Member(String),
+    Point(f64, f64),
+}
+
+struct SearchArgs {
+    origin: Origin,
+    shape: Shape,
+    // Meters per unit of the BY* distances, also used for replies
+    unit: f64,
+    descending: Option<bool>,
+    count: Option<usize>,
+    any: bool,
+    withcoord: bool,
+    withdist: bool,
+    withhash: bool,
+    storedist: bool,
+}
+
+impl SearchArgs {
+    fn parse(args: &[String], store: bool) -> Result<Self, String> {
+        let mut origin = None;
+        let mut shape = None;
+        let mut unit = 1.0;
+        let mut parsed = SearchArgs {
+            origin: Origin::Point(0.0, 0.0),
+            shape: Shape::Radius(0.0),
+            unit,
+            descending: None,
+            count: None,
+            any: false,
+            withcoord: false,
+            withdist: false,
+            withhash: false,
+            storedist: false,
+        };
+        let operands = |index: usize, n: usize| {
+            args.get(index + 1..=index + n)
+                .ok_or_else(|| errors::SYNTAX.to_string())
+        };
+
+        let mut index = 0;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "FROMMEMBER" if origin.is_none() => {
+                    origin = Some(Origin::Member(operands(index, 1)?[0].clone()));
+                    index += 1;
+                }
+                "FROMLONLAT" if origin.is_none() => {
+                    let values = operands(index, 2)?;
+                    let (long, lat) = parse_coordinates(&values[0], &values[1])?;
+                    origin = Some(Origin::Point(long, lat));
+                    index += 2;
+                }
+                "FROMMEMBER" | "FROMLONLAT" => {
+                    return Err(
+                        "exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH"
+                            .to_string(),
+                    )
+                }
+                "BYRADIUS" if shape.is_none() => {
+                    let values = operands(index, 2)?;
+                    let radius = parse_float(&values[0])?;
+                    if radius < 0.0 {
+                        return Err("radius cannot be negative".to_string());
+                    }
+                    unit = parse_unit(&values[1])?;
+                    shape = Some(Shape::Radius(radius * unit));
+                    index += 2;
+                }
+                "BYBOX" if shape.is_none() => {
+                    let values = operands(index, 3)?;
+                    let (width, height) = (parse_float(&values[0])?, parse_float(&values[1])?);
+                    if width < 0.0 || height < 0.0 {
+                        return Err("height or width cannot be negative".to_string());
+                    }
+                    unit = parse_unit(&values[2])?;
+                    shape = Some(Shape::Box {
+                        width: width * unit,
+                        height: height * unit,
+                    });
+                    index += 3;
+                }
+                "BYRADIUS" | "BYBOX" => {
+                    return Err(
+                        "exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH"
+                            .to_string(),
+                    )
+                }
+                "ASC" => parsed.descending = Some(false),
+                "DESC" => parsed.descending = Some(true),
+                "COUNT" => {
+                    let count = parse_int_arg(&operands(index, 1)?[0])?;
+                    if count <= 0 {
+                        return Err("COUNT must be > 0".to_string());
+                    }
+                    parsed.count = Some(count as usize);
+                    index += 1;
+                }
+                "ANY" => parsed.any = true,
+                "WITHCOORD" if !store => parsed.withcoord = true,
+                "WITHDIST" if !store => parsed.withdist = true,
+                "WITHHASH" if !store => parsed.withhash = true,
+                "STOREDIST" if store => parsed.storedist = true,
+                _ => return Err(errors::SYNTAX.to_string()),
+            }
+            index += 1;
+        }
+
+        parsed.origin = origin
+            .ok_or("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH")?;
+        parsed.shape =
+            shape.ok_or("exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH")?;
+        parsed.unit = unit;
+        if parsed.any && parsed.count.is_none() {
+            return Err("the ANY argument requires COUNT argument".to_string());
+        }
+        // A plain COUNT keeps the nearest matches
+        if parsed.count.is_some() && !parsed.any && parsed.descending.is_none() {
+            parsed.descending = Some(false);
+        }
+        Ok(parsed)
+    }
+}
+
+struct GeoMatch {
+    member: String,
+    score: f64,
+    distance: f64,
+}
+
+// Scans the cells around the origin and keeps the members inside the shape
+fn search(zset: &SortedSet, args: &SearchArgs) -> Result<Vec<GeoMatch>, &'static str> {
+    let center = match &args.origin {
+        Origin::Point(long, lat) => (*long, *lat),
+        Origin::Member(member) => zset
+            .score(member)
+            .map(geo::position)
+            .ok_or("could not decode requested zset member")?,
+    };
+
+    let mut matches = Vec::new();
+    'cells: for (min, max) in geo::search_ranges(args.shape, center) {
+        let range = ScoreRange {
+            min,
+            max,
+            min_exclusive: false,
+            max_exclusive: true,
+        };
+        for (member, score) in zset.range_by_score(&range, false, 0, None) {
+            if let Some(distance) = args.shape.contains(center, geo::position(score)) {
+                matches.push(GeoMatch {
+                    member,
+                    score,
+                    distance,
+                });
+                // ANY settles for the first matches found
+                if args.any && Some(matches.len()) == args.count {
+                    break 'cells;
+                }
+            }
+        }
+    }
+
+    if let Some(descending) = args.descending {
+        matches.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        if descending {
+            matches.reverse();
+        }
+    }
+    if let Some(count) = args.count {
+        matches.truncate(count);
+    }
+    Ok(matches)
+}
+
+pub struct GeosearchCommand {
+    pub store: bool,
+}
+
+impl Command for GeosearchCommand {
+    fn name(&self) -> &'static str {
+        if self.store {
+            "GEOSEARCHSTORE"
+        } else {
+            "GEOSEARCH"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let min_args = if self.store { 6 } else { 5 };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= min_args => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (destination, source, options) = if self.store {
+            (Some(&args[0]), &args[1], &args[2..])
+        } else {
+            (None, &args[0], &args[1..])
+        };
+        let search_args = match SearchArgs::parse(options, self.store) {
+            Ok(search_args) => search_args,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+
+        let matches = match memory.get_zset(source) {
+            Ok(Some(zset)) => match search(zset, &search_args) {
+                Ok(matches) => matches,
+                Err(err) => return send_error(&mut ctx.stream, err),
+            },
+            Ok(None) => Vec::new(),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let Some(destination) = destination else {
+            let plain = !(search_args.withcoord || search_args.withdist || search_args.withhash);
+            let replies = matches
+                .into_iter()
+                .map(|found| {
+                    if plain {
+                        return Resp::BulkString(Some(found.member.into()));
+                    }
+                    let mut reply = vec![Resp::BulkString(Some(found.member.into()))];
+                    if search_args.withdist {
+                        reply.push(format_distance(found.distance, search_args.unit));
+                    }
+                    if search_args.withhash {
+                        reply.push(Resp::Integer(found.score as i64));
+                    }
+                    if search_args.withcoord {
+                        reply.push(coordinates_resp(geo::position(found.score)));
+                    }
+                    Resp::Array(reply)
+                })
+                .collect();
+            return send_resp(&mut ctx.stream, Resp::Array(replies));
+        };
+
+        // STOREDIST keeps distances in the query's unit instead of geohash scores
+        let mut result = SortedSet::new();
+        for found in matches {
+            let score = if search_args.storedist {
+                found.distance / search_args.unit
+            } else {
+                found.score
+            };
+            result.insert(found.member, score);
+        }
+        let stored = store(&mut memory, &mut expiry_manager, destination, result);
+        if stored > 0 {
+            ctx.state.blocking.signal(destination);
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(stored as i64));
+    }
+}
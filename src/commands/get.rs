@@ -1,6 +1,8 @@
 use crate::{
     commands::command::{
-        errors, parse_bulk_string_arg, send_error, send_resp, Command, CommandContext,
+        apply_expiry, errors, expire_if_needed, parse_bulk_string_arg, parse_bulk_string_args,
+        parse_expiry, send_arity_error, send_error, send_resp, send_wrongtype, Command,
+        CommandContext, Expiry,
     },
     resp::Resp,
 };
@@ -24,21 +26,97 @@ impl Command for GetCommand {
                 let mut memory = ctx.state.memory.lock().unwrap();
 
                 // Check if the key is expired
-                if expiry_manager.is_expired(&key) {
-                    expiry_manager.remove_expiry(&key);
-                    memory.delete(&key);
-                    send_resp(&mut ctx.stream, Resp::BulkString(None));
-                    return;
-                }
+                expire_if_needed(&mut memory, &mut expiry_manager, &key);
 
                 // Retrieve the value if it exists
-                if let Some(value) = memory.get(&key) {
-                    send_resp(&mut ctx.stream, Resp::BulkString(Some(value)));
-                } else {
-                    send_resp(&mut ctx.stream, Resp::BulkString(None));
+                match memory.get(&key) {
+                    Ok(value) => send_resp(&mut ctx.stream, Resp::BulkString(value)),
+                    Err(_) => send_wrongtype(&mut ctx.stream),
                 }
             }
             Err(_) => send_error(&mut ctx.stream, errors::INVALID_GET_KEY),
         }
     }
 }
+
+// GETDEL key, replying with the value of a string key and deleting it
+pub struct GetdelCommand;
+
+impl Command for GetdelCommand {
+    fn name(&self) -> &'static str {
+        "GETDEL"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get(&args[0]) {
+            Ok(value) => {
+                if value.is_some() {
+                    memory.delete(&args[0]);
+                    expiry_manager.remove_expiry(&args[0]);
+                }
+                send_resp(&mut ctx.stream, Resp::BulkString(value));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// GETEX key [EX seconds | PX ms | EXAT timestamp | PXAT ms-timestamp | PERSIST]. Unlike
+// SET, leaving out the option keeps the TTL as it is.
+pub struct GetexCommand;
+
+impl Command for GetexCommand {
+    fn name(&self) -> &'static str {
+        "GETEX"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let expiry = match &args[1..] {
+            [] => Expiry::Keep,
+            [option] if option.eq_ignore_ascii_case("PERSIST") => Expiry::Persist,
+            [option, amount] => {
+                let option = option.to_ascii_uppercase();
+                if !matches!(option.as_str(), "EX" | "PX" | "EXAT" | "PXAT") {
+                    return send_error(&mut ctx.stream, errors::SYNTAX);
+                }
+                match parse_expiry(&option, amount, self.name()) {
+                    Ok(expiry) => expiry,
+                    Err(err) => return send_error(&mut ctx.stream, &err),
+                }
+            }
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        let value = match memory.get(key) {
+            Ok(value) => value,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        if value.is_some() {
+            apply_expiry(&mut expiry_manager, key, expiry);
+            // A deadline already in the past deletes the key right away
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+        send_resp(&mut ctx.stream, Resp::BulkString(value));
+    }
+}
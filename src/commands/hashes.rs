@@ -0,0 +1,445 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, parse_bytes_args, parse_int_arg,
+        send_arity_error, send_error, send_resp, send_wrongtype, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    memory::Memory,
+    resp::Resp,
+};
+
+// Field deadlines are capped like Redis', whose per-field TTLs are stored in 48 bits
+const MAX_FIELD_EXPIRY: i64 = (1 << 48) - 1;
+
+// Empty hashes are never kept around; otherwise the key is reindexed and rescheduled for
+// its next field expiry
+fn settle_hash(memory: &mut Memory, expiry_manager: &mut ExpiryManager, key: &str) {
+    let next = match memory.get_hash(key) {
+        Ok(Some(hash)) if hash.is_empty() => {
+            memory.delete(key);
+            expiry_manager.remove_expiry(key);
+            None
+        }
+        Ok(Some(hash)) => {
+            let next = hash.next_expiry();
+            memory.reindex(key);
+            next
+        }
+        _ => return,
+    };
+    expiry_manager.set_parts_expiry(key, next);
+}
+
+// The FIELDS numfields field [field ...] block the field TTL commands end with
+fn parse_fields(args: &[String]) -> Result<&[String], &'static str> {
+    match args {
+        [keyword, count, fields @ ..] if keyword.eq_ignore_ascii_case("FIELDS") => {
+            match parse_int_arg(count)? {
+                count if count <= 0 => Err("Parameter `numFields` should be greater than 0"),
+                count if count as usize != fields.len() => {
+                    Err("The `numfields` parameter must match the number of arguments")
+                }
+                _ => Ok(fields),
+            }
+        }
+        _ => Err("Mandatory argument FIELDS is missing or not at the right position"),
+    }
+}
+
+fn integer_array(values: impl Iterator<Item = i64>) -> Resp {
+    Resp::Array(values.map(Resp::Integer).collect())
+}
+
+// HSET key field value [field value ...], replying with the number of new fields
+pub struct HsetCommand;
+
+impl Command for HsetCommand {
+    fn name(&self) -> &'static str {
+        "HSET"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 && args.len() % 2 == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let added = match memory.get_or_create_hash(&args[0]) {
+            Ok(hash) => args[1..]
+                .chunks(2)
+                .zip(values[1..].chunks(2))
+                .filter(|(pair, values)| hash.insert(pair[0].clone(), values[1].clone()))
+                .count(),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        memory.reindex(&args[0]);
+        send_resp(&mut ctx.stream, Resp::Integer(added as i64));
+    }
+}
+
+pub struct HgetCommand;
+
+impl Command for HgetCommand {
+    fn name(&self) -> &'static str {
+        "HGET"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_hash(&args[0]) {
+            Ok(hash) => {
+                let value = hash.and_then(|hash| hash.get(&args[1])).map(<[u8]>::to_vec);
+                send_resp(&mut ctx.stream, Resp::BulkString(value));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// HDEL key field [field ...], deleting the key along with its last field
+pub struct HdelCommand;
+
+impl Command for HdelCommand {
+    fn name(&self) -> &'static str {
+        "HDEL"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let removed = match memory.get_hash_mut(&args[0]) {
+            Ok(Some(hash)) => args[1..].iter().filter(|field| hash.remove(field)).count(),
+            Ok(None) => 0,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        settle_hash(&mut memory, &mut expiry_manager, &args[0]);
+        send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+    }
+}
+
+pub struct HgetallCommand;
+
+impl Command for HgetallCommand {
+    fn name(&self) -> &'static str {
+        "HGETALL"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_hash(&args[0]) {
+            Ok(hash) => {
+                let items = hash
+                    .into_iter()
+                    .flat_map(|hash| hash.entries())
+                    .flat_map(|(field, value)| {
+                        [
+                            Resp::BulkString(Some(field.clone().into())),
+                            Resp::BulkString(Some(value.clone())),
+                        ]
+                    })
+                    .collect();
+                send_resp(&mut ctx.stream, Resp::Array(items));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct HlenCommand;
+
+impl Command for HlenCommand {
+    fn name(&self) -> &'static str {
+        "HLEN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_hash(&args[0]) {
+            Ok(hash) => {
+                let len = hash.map_or(0, |hash| hash.len());
+                send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct HexistsCommand;
+
+impl Command for HexistsCommand {
+    fn name(&self) -> &'static str {
+        "HEXISTS"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_hash(&args[0]) {
+            Ok(hash) => {
+                let found = hash.is_some_and(|hash| hash.contains(&args[1]));
+                send_resp(&mut ctx.stream, Resp::Integer(found as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// HEXPIRE key seconds, HPEXPIRE key ms, HEXPIREAT key timestamp and HPEXPIREAT key
+// ms-timestamp, each with [NX | XX | GT | LT] FIELDS numfields field [field ...]. Replies
+// per field: -2 if it doesn't exist, 0 if the condition failed, 1 if the TTL was set and
+// 2 if the deadline had already passed and the field was deleted.
+pub struct HexpireCommand {
+    pub millis: bool,
+    pub at: bool,
+}
+
+impl Command for HexpireCommand {
+    fn name(&self) -> &'static str {
+        match (self.millis, self.at) {
+            (false, false) => "HEXPIRE",
+            (true, false) => "HPEXPIRE",
+            (false, true) => "HEXPIREAT",
+            (true, true) => "HPEXPIREAT",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let condition = match args[2].to_ascii_uppercase().as_str() {
+            option @ ("NX" | "XX" | "GT" | "LT") => Some(option.to_string()),
+            _ => None,
+        };
+        let fields_at = if condition.is_some() { 3 } else { 2 };
+        let fields = match parse_fields(&args[fields_at..]) {
+            Ok(fields) => fields,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let amount = match parse_int_arg(&args[1]) {
+            Ok(amount) if amount >= 0 => amount,
+            Ok(_) => return send_error(&mut ctx.stream, "invalid expire time, must be >= 0"),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let now = ExpiryManager::get_now_ms() as i64;
+        let ms = if self.millis {
+            Some(amount)
+        } else {
+            amount.checked_mul(1000)
+        };
+        let base = if self.at { 0 } else { now };
+        let deadline = match ms.and_then(|ms| ms.checked_add(base)) {
+            Some(deadline) if deadline <= MAX_FIELD_EXPIRY => deadline,
+            _ => {
+                return send_error(
+                    &mut ctx.stream,
+                    &format!(
+                        "invalid expire time in '{}' command",
+                        self.name().to_lowercase()
+                    ),
+                )
+            }
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        let hash = match memory.get_hash_mut(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => {
+                return send_resp(&mut ctx.stream, integer_array(fields.iter().map(|_| -2)))
+            }
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let replies = fields
+            .iter()
+            .map(|field| {
+                if !hash.contains(field) {
+                    return -2;
+                }
+                // A field without a TTL counts as never expiring for GT and LT
+                let current = hash.expiry(field).map(|current| current as i64);
+                let allowed = match (condition.as_deref(), current) {
+                    (Some("NX"), current) => current.is_none(),
+                    (Some("XX"), current) => current.is_some(),
+                    (Some("GT"), current) => current.is_some_and(|current| deadline > current),
+                    (Some("LT"), current) => current.is_none_or(|current| deadline < current),
+                    _ => true,
+                };
+                if !allowed {
+                    0
+                } else if deadline <= now {
+                    hash.remove(field);
+                    2
+                } else {
+                    hash.set_expiry(field, deadline as u64);
+                    1
+                }
+            })
+            .collect::<Vec<_>>();
+        settle_hash(&mut memory, &mut expiry_manager, key);
+        send_resp(&mut ctx.stream, integer_array(replies.into_iter()));
+    }
+}
+
+// HTTL and HPTTL report the time each field has left, HEXPIRETIME and HPEXPIRETIME its
+// absolute deadline; -2 for a missing field and -1 for one without a TTL
+pub struct HttlCommand {
+    pub millis: bool,
+    pub absolute: bool,
+}
+
+impl Command for HttlCommand {
+    fn name(&self) -> &'static str {
+        match (self.millis, self.absolute) {
+            (false, false) => "HTTL",
+            (true, false) => "HPTTL",
+            (false, true) => "HEXPIRETIME",
+            (true, true) => "HPEXPIRETIME",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let fields = match parse_fields(&args[1..]) {
+            Ok(fields) => fields,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let hash = match memory.get_hash(&args[0]) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => {
+                return send_resp(&mut ctx.stream, integer_array(fields.iter().map(|_| -2)))
+            }
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let now = ExpiryManager::get_now_ms();
+        let replies = fields.iter().map(|field| {
+            if !hash.contains(field) {
+                return -2;
+            }
+            let Some(deadline) = hash.expiry(field) else {
+                return -1;
+            };
+            let ms = if self.absolute {
+                deadline
+            } else {
+                deadline.saturating_sub(now)
+            };
+            // Seconds are rounded to the nearest one, as TTL does
+            let reply = if self.millis { ms } else { (ms + 500) / 1000 };
+            reply as i64
+        });
+        send_resp(&mut ctx.stream, integer_array(replies));
+    }
+}
+
+// HPERSIST key FIELDS numfields field [field ...], replying per field with 1 when a TTL
+// was removed, -1 for a field without one and -2 for a missing field
+pub struct HpersistCommand;
+
+impl Command for HpersistCommand {
+    fn name(&self) -> &'static str {
+        "HPERSIST"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let fields = match parse_fields(&args[1..]) {
+            Ok(fields) => fields,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let hash = match memory.get_hash_mut(&args[0]) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => {
+                return send_resp(&mut ctx.stream, integer_array(fields.iter().map(|_| -2)))
+            }
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let replies = fields
+            .iter()
+            .map(|field| match hash.contains(field) {
+                false => -2,
+                true if hash.persist(field) => 1,
+                true => -1,
+            })
+            .collect::<Vec<_>>();
+        settle_hash(&mut memory, &mut expiry_manager, &args[0]);
+        send_resp(&mut ctx.stream, integer_array(replies.into_iter()));
+    }
+}
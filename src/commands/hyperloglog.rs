@@ -0,0 +1,161 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, parse_bytes_args, send_arity_error, send_error,
+        send_ok, send_resp, wrongtype_resp, Command, CommandContext,
+    },
+    memory::Memory,
+    resp::Resp,
+    types::hyperloglog::{self, CORRUPTED_HLL, INVALID_HLL},
+};
+
+// Fetches a HyperLogLog, telling apart other types from strings that aren't HLLs
+fn get_hll<'a>(memory: &'a mut Memory, key: &str) -> Result<Option<&'a mut Vec<u8>>, Resp> {
+    match memory.get_bytes_mut(key) {
+        Ok(Some(bytes)) if !hyperloglog::is_valid(bytes) => {
+            Err(Resp::SimpleError(INVALID_HLL.to_string()))
+        }
+        Ok(bytes) => Ok(bytes),
+        Err(_) => Err(wrongtype_resp()),
+    }
+}
+
+fn corrupted() -> Resp {
+    Resp::SimpleError(CORRUPTED_HLL.to_string())
+}
+
+pub struct PfaddCommand;
+
+impl Command for PfaddCommand {
+    fn name(&self) -> &'static str {
+        "PFADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        // Elements are hashed byte for byte; only the key is read as text
+        let elements = match parse_bytes_args(args) {
+            Ok(elements) => elements,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let created = match get_hll(&mut memory, &args[0]) {
+            Ok(hll) => hll.is_none(),
+            Err(reply) => return send_resp(&mut ctx.stream, reply),
+        };
+        let Ok(hll) = memory.get_or_create_bytes(&args[0]) else {
+            unreachable!()
+        };
+        if created {
+            *hll = hyperloglog::new();
+        }
+
+        // Creating the key counts as a change even without elements
+        match hyperloglog::add(hll, elements[1..].iter().map(Vec::as_slice)) {
+            Ok(changed) => send_resp(&mut ctx.stream, Resp::Integer((changed || created) as i64)),
+            Err(_) => send_resp(&mut ctx.stream, corrupted()),
+        }
+    }
+}
+
+pub struct PfcountCommand;
+
+impl Command for PfcountCommand {
+    fn name(&self) -> &'static str {
+        "PFCOUNT"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in &args {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        // A single key can use, and refresh, the cardinality cached in its header
+        if let [key] = args.as_slice() {
+            let count = match get_hll(&mut memory, key) {
+                Ok(Some(hll)) => hyperloglog::count(hll).map_err(|_| corrupted()),
+                Ok(None) => Ok(0),
+                Err(reply) => Err(reply),
+            };
+            return match count {
+                Ok(count) => send_resp(&mut ctx.stream, Resp::Integer(count as i64)),
+                Err(reply) => send_resp(&mut ctx.stream, reply),
+            };
+        }
+
+        // Several keys are counted as their union
+        let mut max = hyperloglog::empty_registers();
+        for key in &args {
+            match get_hll(&mut memory, key) {
+                Ok(Some(hll)) => {
+                    if hyperloglog::merge(&mut max, hll).is_err() {
+                        return send_resp(&mut ctx.stream, corrupted());
+                    }
+                }
+                Ok(None) => {}
+                Err(reply) => return send_resp(&mut ctx.stream, reply),
+            }
+        }
+        let count = hyperloglog::estimate(&max);
+        send_resp(&mut ctx.stream, Resp::Integer(count as i64));
+    }
+}
+
+pub struct PfmergeCommand;
+
+impl Command for PfmergeCommand {
+    fn name(&self) -> &'static str {
+        "PFMERGE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in &args {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        // The destination takes part in the union when it already exists
+        let mut max = hyperloglog::empty_registers();
+        let mut dense = false;
+        for key in &args {
+            match get_hll(&mut memory, key) {
+                Ok(Some(hll)) => {
+                    dense |= hyperloglog::is_dense(hll);
+                    if hyperloglog::merge(&mut max, hll).is_err() {
+                        return send_resp(&mut ctx.stream, corrupted());
+                    }
+                }
+                Ok(None) => {}
+                Err(reply) => return send_resp(&mut ctx.stream, reply),
+            }
+        }
+
+        let Ok(hll) = memory.get_or_create_bytes(&args[0]) else {
+            unreachable!()
+        };
+        hyperloglog::store(hll, &max, dense);
+        send_ok(&mut ctx.stream);
+    }
+}
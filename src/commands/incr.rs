@@ -0,0 +1,104 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+        send_error, send_resp, send_wrongtype, Command, CommandContext,
+    },
+    memory::{NumberError, Value},
+    resp::Resp,
+};
+
+const NOT_A_FLOAT: &str = "value is not a valid float";
+
+// INCR key, DECR key, INCRBY key increment and DECRBY key decrement. Missing keys count
+// from 0 and an existing TTL is kept.
+pub struct IncrCommand {
+    pub decrement: bool,
+    pub by: bool,
+}
+
+impl Command for IncrCommand {
+    fn name(&self) -> &'static str {
+        match (self.decrement, self.by) {
+            (false, false) => "INCR",
+            (true, false) => "DECR",
+            (false, true) => "INCRBY",
+            (true, true) => "DECRBY",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let arity = if self.by { 2 } else { 1 };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == arity => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let amount = match args.get(1).map(|amount| parse_int_arg(amount)) {
+            Some(Ok(amount)) => amount,
+            Some(Err(err)) => return send_error(&mut ctx.stream, err),
+            None => 1,
+        };
+        let delta = match self.decrement {
+            true => amount.checked_neg(),
+            false => Some(amount),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let current = match memory.get_integer(&args[0]) {
+            Ok(current) => current.unwrap_or(0),
+            Err(NumberError::NotANumber) => {
+                return send_error(&mut ctx.stream, errors::NOT_AN_INTEGER)
+            }
+            Err(NumberError::WrongType) => return send_wrongtype(&mut ctx.stream),
+        };
+        let Some(value) = delta.and_then(|delta| current.checked_add(delta)) else {
+            return send_error(&mut ctx.stream, "increment or decrement would overflow");
+        };
+        memory.insert(args[0].clone(), Value::Integer(value));
+        send_resp(&mut ctx.stream, Resp::Integer(value));
+    }
+}
+
+pub struct IncrbyfloatCommand;
+
+impl Command for IncrbyfloatCommand {
+    fn name(&self) -> &'static str {
+        "INCRBYFLOAT"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let increment = match args[1].parse::<f64>() {
+            Ok(increment) if !increment.is_nan() && !args[1].starts_with(char::is_whitespace) => {
+                increment
+            }
+            _ => return send_error(&mut ctx.stream, NOT_A_FLOAT),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let current = match memory.get_float(&args[0]) {
+            Ok(current) => current.unwrap_or(0.0),
+            Err(NumberError::NotANumber) => return send_error(&mut ctx.stream, NOT_A_FLOAT),
+            Err(NumberError::WrongType) => return send_wrongtype(&mut ctx.stream),
+        };
+        let value = current + increment;
+        if !value.is_finite() {
+            return send_error(&mut ctx.stream, "increment would produce NaN or Infinity");
+        }
+        // Stored as a string, which becomes int-encoded when the result is whole. Unlike
+        // scores, Redis never switches to exponent notation here.
+        let formatted = value.to_string();
+        memory.set(args[0].clone(), formatted.clone().into_bytes());
+        send_resp(&mut ctx.stream, Resp::BulkString(Some(formatted.into())));
+    }
+}
@@ -0,0 +1,524 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    commands::command::{
+        error_resp, errors, expire_if_needed, parse_bulk_string_args, parse_int_arg,
+        send_arity_error, send_error, send_resp, wrongtype_resp, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    memory::{self, Memory},
+    resp::Resp,
+    types::json::{self, Format, JsonPath},
+};
+
+fn missing_path(path: &str) -> Resp {
+    error_resp(&format!("Path '{}' does not exist", path))
+}
+
+fn wrong_kind(expected: &str, found: &Value) -> Resp {
+    error_resp(&format!(
+        "wrong type of path value - expected {} but found {}",
+        expected,
+        json::type_name(found)
+    ))
+}
+
+fn parse_path(input: &str) -> Result<JsonPath, Resp> {
+    JsonPath::parse(input).map_err(|message| error_resp(&message))
+}
+
+fn parse_json(input: &str) -> Result<Value, Resp> {
+    serde_json::from_str(input).map_err(|error| error_resp(&error.to_string()))
+}
+
+fn bulk_json(value: &Value) -> Resp {
+    Resp::BulkString(Some(json::serialize(value, &Format::default()).into()))
+}
+
+fn document<'a>(memory: &'a Memory, key: &str) -> Result<Option<&'a Value>, Resp> {
+    memory.get_json(key).map_err(|_| wrongtype_resp())
+}
+
+// Write commands other than JSON.SET need the document to exist already
+fn document_mut<'a>(memory: &'a mut Memory, key: &str) -> Result<&'a mut Value, Resp> {
+    match memory.get_json_mut(key) {
+        Ok(Some(document)) => Ok(document),
+        Ok(None) => Err(error_resp(
+            "could not perform this operation on a key that doesn't exist",
+        )),
+        Err(_) => Err(wrongtype_resp()),
+    }
+}
+
+fn read_matches<T>(
+    document: &Value,
+    path: &JsonPath,
+    mut read: impl FnMut(&Value) -> Result<T, Resp>,
+) -> Vec<Result<T, Resp>> {
+    path.select(document)
+        .iter()
+        .filter_map(|location| json::resolve(document, location))
+        .map(&mut read)
+        .collect()
+}
+
+fn update_matches<T>(
+    document: &mut Value,
+    path: &JsonPath,
+    mut update: impl FnMut(&mut Value) -> Result<T, Resp>,
+) -> Vec<Result<T, Resp>> {
+    let mut results = Vec::new();
+    for location in path.select(document) {
+        if let Some(value) = json::resolve_mut(document, &location) {
+            results.push(update(value));
+        }
+    }
+    results
+}
+
+// JSONPath replies hold one entry per match, nil where the match had the wrong type;
+// legacy paths reply with the last match alone
+fn path_reply(path: &JsonPath, raw: &str, results: Vec<Result<Resp, Resp>>) -> Result<Resp, Resp> {
+    if !path.legacy {
+        let replies = results
+            .into_iter()
+            .map(|result| result.unwrap_or(Resp::BulkString(None)))
+            .collect();
+        return Ok(Resp::Array(replies));
+    }
+    results.into_iter().last().unwrap_or(Err(missing_path(raw)))
+}
+
+type Handler = fn(&[String], &mut Memory, &mut ExpiryManager) -> Result<Resp, Resp>;
+
+// The JSON.* commands share their prelude, so each is a name, an arity check and a handler
+pub struct JsonCommand {
+    name: &'static str,
+    arity: fn(usize) -> bool,
+    handler: Handler,
+}
+
+impl JsonCommand {
+    pub const SET: Self = Self::new("JSON.SET", |n| n == 3 || n == 4, json_set);
+    pub const GET: Self = Self::new("JSON.GET", |n| n >= 1, json_get);
+    pub const DEL: Self = Self::new("JSON.DEL", |n| n == 1 || n == 2, json_del);
+    pub const MGET: Self = Self::new("JSON.MGET", |n| n >= 2, json_mget);
+    pub const TYPE: Self = Self::new("JSON.TYPE", |n| n == 1 || n == 2, json_type);
+    pub const NUMINCRBY: Self = Self::new("JSON.NUMINCRBY", |n| n == 3, json_numincrby);
+    pub const STRAPPEND: Self = Self::new("JSON.STRAPPEND", |n| n == 2 || n == 3, json_strappend);
+    pub const ARRAPPEND: Self = Self::new("JSON.ARRAPPEND", |n| n >= 3, json_arrappend);
+    pub const ARRPOP: Self = Self::new("JSON.ARRPOP", |n| (1..=3).contains(&n), json_arrpop);
+    pub const OBJKEYS: Self = Self::new("JSON.OBJKEYS", |n| n == 1 || n == 2, json_objkeys);
+
+    const fn new(name: &'static str, arity: fn(usize) -> bool, handler: Handler) -> Self {
+        JsonCommand {
+            name,
+            arity,
+            handler,
+        }
+    }
+}
+
+impl Command for JsonCommand {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if (self.arity)(args.len()) => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        match (self.handler)(&args, &mut memory, &mut expiry_manager) {
+            Ok(reply) | Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
+
+// JSON.SET key path value [NX|XX]
+fn json_set(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let (key, raw_path) = (&args[0], &args[1]);
+    let path = parse_path(raw_path)?;
+    let value = parse_json(&args[2])?;
+    let (nx, xx) = match args.get(3).map(|arg| arg.to_ascii_uppercase()).as_deref() {
+        None => (false, false),
+        Some("NX") => (true, false),
+        Some("XX") => (false, true),
+        Some(_) => return Err(error_resp(errors::SYNTAX)),
+    };
+    let ok = Ok(Resp::SimpleString("OK".to_string()));
+    let skipped = Ok(Resp::BulkString(None));
+
+    expire_if_needed(memory, expiry_manager, key);
+    let document = match memory.get_json_mut(key) {
+        Ok(Some(document)) => document,
+        Ok(None) if !path.is_root() => {
+            return Err(error_resp("new objects must be created at the root"))
+        }
+        Ok(None) if xx => return skipped,
+        Ok(None) => {
+            memory.insert(key.clone(), memory::Value::Json(value));
+            return ok;
+        }
+        Err(_) => return Err(wrongtype_resp()),
+    };
+
+    let matches = path.select(document);
+    if !matches.is_empty() {
+        if nx {
+            return skipped;
+        }
+        for location in matches {
+            if let Some(target) = json::resolve_mut(document, &location) {
+                *target = value.clone();
+            }
+        }
+        return ok;
+    }
+    if xx {
+        return skipped;
+    }
+
+    // A path ending in a missing member adds it to every matching parent object
+    let mut created = false;
+    if let Some((parent, name)) = path.split_last_key() {
+        for location in parent.select(document) {
+            if let Some(Value::Object(map)) = json::resolve_mut(document, &location) {
+                map.insert(name.clone(), value.clone());
+                created = true;
+            }
+        }
+    }
+    match created {
+        true => ok,
+        false if path.legacy => Err(missing_path(raw_path)),
+        false => skipped,
+    }
+}
+
+// JSON.GET key [INDENT indent] [NEWLINE newline] [SPACE space] [path ...]
+fn json_get(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let key = &args[0];
+    let mut format = Format::default();
+    let mut raw_paths = Vec::new();
+    let mut index = 1;
+    while index < args.len() {
+        let option = args[index].to_ascii_uppercase();
+        let field = match option.as_str() {
+            "INDENT" => Some(&mut format.indent),
+            "NEWLINE" => Some(&mut format.newline),
+            "SPACE" => Some(&mut format.space),
+            _ => None,
+        };
+        match (field, args.get(index + 1)) {
+            (Some(field), Some(value)) => {
+                *field = value.clone();
+                index += 1;
+            }
+            (Some(_), None) => return Err(error_resp(errors::SYNTAX)),
+            (None, _) => raw_paths.push(args[index].as_str()),
+        }
+        index += 1;
+    }
+    if raw_paths.is_empty() {
+        raw_paths.push(".");
+    }
+    let paths = raw_paths
+        .iter()
+        .map(|raw| parse_path(raw))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    expire_if_needed(memory, expiry_manager, key);
+    let Some(document) = document(memory, key)? else {
+        return Ok(Resp::BulkString(None));
+    };
+
+    let values = |path: &JsonPath| -> Vec<Value> {
+        read_matches(document, path, |value| Ok(value.clone()))
+            .into_iter()
+            .flatten()
+            .collect()
+    };
+    let single = |path: &JsonPath, raw: &str| -> Result<Value, Resp> {
+        values(path)
+            .into_iter()
+            .next()
+            .ok_or_else(|| missing_path(raw))
+    };
+
+    let result = match paths.as_slice() {
+        [path] if path.legacy => single(path, raw_paths[0])?,
+        [path] => Value::Array(values(path)),
+        // Several paths reply with an object keyed by path, in JSONPath form if any path is
+        _ => {
+            let legacy = paths.iter().all(|path| path.legacy);
+            let mut object = Map::new();
+            for (path, raw) in paths.iter().zip(&raw_paths) {
+                let value = match legacy {
+                    true => single(path, raw)?,
+                    false => Value::Array(values(path)),
+                };
+                object.insert(raw.to_string(), value);
+            }
+            Value::Object(object)
+        }
+    };
+    Ok(Resp::BulkString(Some(
+        json::serialize(&result, &format).into(),
+    )))
+}
+
+// JSON.DEL key [path]
+fn json_del(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let key = &args[0];
+    let path = match args.get(1) {
+        Some(raw) => parse_path(raw)?,
+        None => JsonPath::root(),
+    };
+
+    expire_if_needed(memory, expiry_manager, key);
+    let Some(document) = memory.get_json_mut(key).map_err(|_| wrongtype_resp())? else {
+        return Ok(Resp::Integer(0));
+    };
+    if path.is_root() {
+        memory.delete(key);
+        expiry_manager.remove_expiry(key);
+        return Ok(Resp::Integer(1));
+    }
+
+    // Later siblings and nested matches go first so earlier locations stay valid
+    let mut matches = path.select(document);
+    matches.sort_unstable_by(|a, b| b.cmp(a));
+    matches.dedup();
+    let removed = matches
+        .iter()
+        .filter(|location| json::remove(document, location))
+        .count();
+    Ok(Resp::Integer(removed as i64))
+}
+
+// JSON.MGET key [key ...] path
+fn json_mget(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let (path, keys) = args.split_last().unwrap();
+    let path = parse_path(path)?;
+
+    let mut replies = Vec::with_capacity(keys.len());
+    for key in keys {
+        expire_if_needed(memory, expiry_manager, key);
+        let reply = match memory.get_json(key) {
+            Ok(Some(document)) => {
+                let values: Vec<Value> = read_matches(document, &path, |value| Ok(value.clone()))
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                match path.legacy {
+                    true => values.first().map_or(Resp::BulkString(None), bulk_json),
+                    false => bulk_json(&Value::Array(values)),
+                }
+            }
+            _ => Resp::BulkString(None),
+        };
+        replies.push(reply);
+    }
+    Ok(Resp::Array(replies))
+}
+
+// JSON.TYPE key [path]
+fn json_type(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let path = parse_path(args.get(1).map_or(".", String::as_str))?;
+    expire_if_needed(memory, expiry_manager, &args[0]);
+    let Some(document) = document(memory, &args[0])? else {
+        return Ok(Resp::BulkString(None));
+    };
+
+    let types = read_matches(document, &path, |value| {
+        Ok(json::type_name(value).to_string())
+    });
+    let mut types = types.into_iter().flatten();
+    Ok(match path.legacy {
+        true => types
+            .next()
+            .map_or(Resp::BulkString(None), Resp::SimpleString),
+        false => Resp::Array(
+            types
+                .map(|name| Resp::BulkString(Some(name.into())))
+                .collect(),
+        ),
+    })
+}
+
+// JSON.NUMINCRBY key path value
+fn json_numincrby(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let path = parse_path(&args[1])?;
+    let Value::Number(increment) = parse_json(&args[2])? else {
+        return Err(error_resp("expected a number"));
+    };
+
+    expire_if_needed(memory, expiry_manager, &args[0]);
+    let document = document_mut(memory, &args[0])?;
+    // Every sum is worked out before any is stored, so a result that overflows fails the
+    // whole command and leaves the document as it was
+    let mut sums = Vec::new();
+    for location in path.select(document) {
+        let result = match json::resolve(document, &location) {
+            Some(Value::Number(number)) => match json::add_numbers(number, &increment) {
+                Some(sum) => Ok(sum),
+                None => return Err(error_resp("result is not a number")),
+            },
+            Some(value) => Err(wrong_kind("number", value)),
+            None => continue,
+        };
+        sums.push((location, result));
+    }
+    let mut results = Vec::new();
+    for (location, result) in sums {
+        if let (Ok(sum), Some(value)) = (&result, json::resolve_mut(document, &location)) {
+            *value = sum.clone();
+        }
+        results.push(result);
+    }
+
+    // Both forms reply with JSON text; JSONPath wraps the new values in an array
+    if !path.legacy {
+        let values = results
+            .into_iter()
+            .map(|result| result.unwrap_or(Value::Null));
+        return Ok(bulk_json(&Value::Array(values.collect())));
+    }
+    match results.into_iter().last() {
+        Some(result) => result.map(|value| bulk_json(&value)),
+        None => Err(missing_path(&args[1])),
+    }
+}
+
+// JSON.STRAPPEND key [path] value
+fn json_strappend(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let (raw_path, raw_value) = match args {
+        [_, value] => (".", value),
+        [_, path, value] => (path.as_str(), value),
+        _ => unreachable!(),
+    };
+    let path = parse_path(raw_path)?;
+    let Value::String(suffix) = parse_json(raw_value)? else {
+        return Err(error_resp("expected a JSON string"));
+    };
+
+    expire_if_needed(memory, expiry_manager, &args[0]);
+    let document = document_mut(memory, &args[0])?;
+    let results = update_matches(document, &path, |value| match value {
+        Value::String(string) => {
+            string.push_str(&suffix);
+            Ok(Resp::Integer(string.len() as i64))
+        }
+        _ => Err(wrong_kind("string", value)),
+    });
+    path_reply(&path, raw_path, results)
+}
+
+// JSON.ARRAPPEND key path value [value ...]
+fn json_arrappend(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let path = parse_path(&args[1])?;
+    let values = args[2..]
+        .iter()
+        .map(|value| parse_json(value))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    expire_if_needed(memory, expiry_manager, &args[0]);
+    let document = document_mut(memory, &args[0])?;
+    let results = update_matches(document, &path, |value| match value {
+        Value::Array(array) => {
+            array.extend(values.iter().cloned());
+            Ok(Resp::Integer(array.len() as i64))
+        }
+        _ => Err(wrong_kind("array", value)),
+    });
+    path_reply(&path, &args[1], results)
+}
+
+// JSON.ARRPOP key [path [index]]
+fn json_arrpop(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let raw_path = args.get(1).map_or(".", String::as_str);
+    let path = parse_path(raw_path)?;
+    let index = match args.get(2) {
+        Some(index) => parse_int_arg(index).map_err(error_resp)?,
+        None => -1,
+    };
+
+    expire_if_needed(memory, expiry_manager, &args[0]);
+    let document = document_mut(memory, &args[0])?;
+    let results = update_matches(document, &path, |value| match value {
+        Value::Array(array) if array.is_empty() => Ok(Resp::BulkString(None)),
+        // Out of range indices pop the nearest end
+        Value::Array(array) => {
+            let len = array.len() as i64;
+            let index = if index < 0 { len + index } else { index };
+            let popped = array.remove(index.clamp(0, len - 1) as usize);
+            Ok(bulk_json(&popped))
+        }
+        _ => Err(wrong_kind("array", value)),
+    });
+    path_reply(&path, raw_path, results)
+}
+
+// JSON.OBJKEYS key [path]
+fn json_objkeys(
+    args: &[String],
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+) -> Result<Resp, Resp> {
+    let raw_path = args.get(1).map_or(".", String::as_str);
+    let path = parse_path(raw_path)?;
+
+    expire_if_needed(memory, expiry_manager, &args[0]);
+    let Some(document) = document(memory, &args[0])? else {
+        return Ok(Resp::BulkString(None));
+    };
+    let results = read_matches(document, &path, |value| match value {
+        Value::Object(map) => Ok(Resp::Array(
+            map.keys()
+                .map(|key| Resp::BulkString(Some(key.clone().into())))
+                .collect(),
+        )),
+        _ => Err(wrong_kind("object", value)),
+    });
+    path_reply(&path, raw_path, results)
+}
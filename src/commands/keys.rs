@@ -18,7 +18,7 @@ impl Command for KeysCommand {
         }
 
         let pattern = match &args[0] {
-            Resp::BulkString(Some(p)) => p,
+            Resp::BulkString(Some(p)) => String::from_utf8_lossy(p),
             _ => {
                 send_error(&mut ctx.stream, "invalid pattern format");
                 return;
@@ -29,13 +29,13 @@ impl Command for KeysCommand {
         let keys: Vec<String> = memory
             .data
             .keys()
-            .filter(|key| Pattern::new(pattern).is_ok_and(|p| p.matches(key)))
+            .filter(|key| Pattern::new(&pattern).is_ok_and(|p| p.matches(key)))
             .cloned()
             .collect();
 
         let response = Resp::Array(
             keys.into_iter()
-                .map(|k| Resp::BulkString(Some(k)))
+                .map(|k| Resp::BulkString(Some(k.into())))
                 .collect(),
         );
         send_resp(&mut ctx.stream, response);
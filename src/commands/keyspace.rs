@@ -0,0 +1,228 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+        send_error, send_ok, send_resp, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    memory::{Memory, Value},
+    resp::Resp,
+};
+
+// Moves or copies the TTL of `source` onto `destination`, clearing the one it had, along
+// with when parts of the value there next expire
+fn carry_expiry(expiry_manager: &mut ExpiryManager, source: &str, destination: &str) {
+    match expiry_manager.get_expiry(source) {
+        Some(deadline) => expiry_manager.set_expiry_at(destination, deadline),
+        None => expiry_manager.remove_expiry(destination),
+    }
+    let parts = expiry_manager.get_parts_expiry(source);
+    expiry_manager.set_parts_expiry(destination, parts);
+}
+
+// Compaction rules name the series on either end by key, so a renamed series has the
+// links pointing at it repointed
+fn relink_timeseries(memory: &mut Memory, from: &str, to: &str) {
+    let (source, destinations) = match memory.get_timeseries(to) {
+        Ok(Some(series)) => (
+            series.source.clone(),
+            series
+                .rules
+                .iter()
+                .map(|rule| rule.destination.clone())
+                .collect::<Vec<_>>(),
+        ),
+        _ => return,
+    };
+    if let Some(source) = source {
+        if let Ok(Some(series)) = memory.get_timeseries_mut(&source) {
+            for rule in series
+                .rules
+                .iter_mut()
+                .filter(|rule| rule.destination == from)
+            {
+                rule.destination = to.to_string();
+            }
+        }
+    }
+    for destination in destinations {
+        if let Ok(Some(series)) = memory.get_timeseries_mut(&destination) {
+            if series.source.as_deref() == Some(from) {
+                series.source = Some(to.to_string());
+            }
+        }
+    }
+}
+
+// EXISTS key [key ...] and TOUCH key [key ...] both count the keys that exist, repeats
+// included. Without access tracking TOUCH has nothing else to update.
+pub struct ExistsCommand {
+    pub touch: bool,
+}
+
+impl Command for ExistsCommand {
+    fn name(&self) -> &'static str {
+        if self.touch {
+            "TOUCH"
+        } else {
+            "EXISTS"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+
+        let mut count = 0;
+        for key in &args {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+            if memory.data.contains_key(key) {
+                count += 1;
+            }
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(count));
+    }
+}
+
+pub struct TypeCommand;
+
+impl Command for TypeCommand {
+    fn name(&self) -> &'static str {
+        "TYPE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let name = memory.data.get(&args[0]).map_or("none", Value::type_name);
+        send_resp(&mut ctx.stream, Resp::SimpleString(name.to_string()));
+    }
+}
+
+// RENAME source destination and RENAMENX, which leaves an existing destination alone.
+// The TTL and the schedule for field TTLs move with the value, replacing the destination's.
+pub struct RenameCommand {
+    pub nx: bool,
+}
+
+impl Command for RenameCommand {
+    fn name(&self) -> &'static str {
+        if self.nx {
+            "RENAMENX"
+        } else {
+            "RENAME"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (source, destination) = (&args[0], &args[1]);
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+        expire_if_needed(&mut memory, &mut expiry_manager, destination);
+
+        if !memory.data.contains_key(source) {
+            return send_error(&mut ctx.stream, "no such key");
+        }
+        let blocked = source == destination || (self.nx && memory.data.contains_key(destination));
+        if !blocked {
+            if let Some(value) = memory.delete(source) {
+                memory.insert(destination.clone(), value);
+            }
+            carry_expiry(&mut expiry_manager, source, destination);
+            expiry_manager.remove_expiry(source);
+            expiry_manager.set_parts_expiry(source, None);
+            relink_timeseries(&mut memory, source, destination);
+            ctx.state.blocking.signal(destination);
+        }
+
+        if self.nx {
+            send_resp(&mut ctx.stream, Resp::Integer(!blocked as i64));
+        } else {
+            send_ok(&mut ctx.stream);
+        }
+    }
+}
+
+// COPY source destination [DB 0] [REPLACE], carrying the TTL over. Only database 0
+// exists. A copied time series keeps its samples but none of its compaction rules.
+pub struct CopyCommand;
+
+impl Command for CopyCommand {
+    fn name(&self) -> &'static str {
+        "COPY"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (source, destination) = (&args[0], &args[1]);
+
+        let mut replace = false;
+        let mut index = 2;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "REPLACE" => replace = true,
+                "DB" if index + 1 < args.len() => {
+                    index += 1;
+                    match parse_int_arg(&args[index]) {
+                        Ok(0) => {}
+                        Ok(_) => return send_error(&mut ctx.stream, "DB index is out of range"),
+                        Err(err) => return send_error(&mut ctx.stream, err),
+                    }
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+        if source == destination {
+            return send_error(
+                &mut ctx.stream,
+                "source and destination objects are the same",
+            );
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+        expire_if_needed(&mut memory, &mut expiry_manager, destination);
+
+        let Some(value) = memory.data.get(source) else {
+            return send_resp(&mut ctx.stream, Resp::Integer(0));
+        };
+        if !replace && memory.data.contains_key(destination) {
+            return send_resp(&mut ctx.stream, Resp::Integer(0));
+        }
+        let mut value = value.clone();
+        if let Value::TimeSeries(series) = &mut value {
+            series.rules.clear();
+            series.source = None;
+        }
+        memory.insert(destination.clone(), value);
+        carry_expiry(&mut expiry_manager, source, destination);
+        ctx.state.blocking.signal(destination);
+        send_resp(&mut ctx.stream, Resp::Integer(1));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     commands::command::{
-        errors, parse_bulk_string_arg, send_error, send_resp, Command, CommandContext,
+        errors, expire_if_needed, parse_bulk_string_arg, send_error, send_resp, Command,
+        CommandContext,
     },
     resp::Resp,
 };
@@ -22,19 +23,10 @@ impl Command for MgetCommand {
             match parse_bulk_string_arg(arg) {
                 Ok(key) => {
                     // Check if the key is expired
-                    if expiry_manager.is_expired(&key) {
-                        expiry_manager.remove_expiry(&key);
-                        memory.delete(&key);
-                        send_resp(&mut ctx.stream, Resp::BulkString(None));
-                        return;
-                    }
+                    expire_if_needed(&mut memory, &mut expiry_manager, &key);
 
-                    // Retrieve the value if it exists
-                    if let Some(value) = memory.get(&key) {
-                        results.push(Resp::BulkString(Some(value)));
-                    } else {
-                        results.push(Resp::BulkString(None));
-                    }
+                    // Retrieve the value if it exists; non-string keys read as nil
+                    results.push(Resp::BulkString(memory.get(&key).ok().flatten()));
                 }
                 Err(_) => {
                     send_error(&mut ctx.stream, errors::INVALID_GET_KEY);
@@ -1,23 +1,99 @@
+mod bitmaps;
+mod bloom;
+mod bzpop;
+mod cms;
 pub(crate) mod command;
+mod cuckoo;
 mod del;
 mod echo;
+mod expire;
+mod geo;
 mod get;
+mod hashes;
+mod hyperloglog;
+mod incr;
+mod json;
 mod keys;
+mod keyspace;
 mod mget;
+mod mset;
 mod ping;
+mod scan;
+mod search;
 mod set;
+mod sets;
+mod stream_groups;
+mod streams;
+mod strings;
+mod suggest;
+mod tdigest;
+mod timeseries;
+mod topk;
+mod vectorset;
+mod zsetops;
+mod zsets;
 
+use bitmaps::{
+    BitcountCommand, BitfieldCommand, BitopCommand, BitposCommand, GetbitCommand, SetbitCommand,
+};
+use bloom::{BfAddCommand, BfExistsCommand, BfInfoCommand, BfReserveCommand};
+use bzpop::{BzmpopCommand, BzpopCommand};
+use cms::{CmsIncrbyCommand, CmsInitCommand, CmsMergeCommand, CmsQueryCommand};
 pub use command::{send_error, Command, CommandContext};
+use cuckoo::{CfAddCommand, CfCountCommand, CfDelCommand};
 use del::DelCommand;
 use echo::EchoCommand;
-use get::GetCommand;
+use expire::{ExpireCommand, PersistCommand, TtlCommand};
+use geo::{GeoLookupCommand, GeoaddCommand, GeodistCommand, GeosearchCommand};
+use get::{GetCommand, GetdelCommand, GetexCommand};
+use hashes::{
+    HdelCommand, HexistsCommand, HexpireCommand, HgetCommand, HgetallCommand, HlenCommand,
+    HpersistCommand, HsetCommand, HttlCommand,
+};
+use hyperloglog::{PfaddCommand, PfcountCommand, PfmergeCommand};
+use incr::{IncrCommand, IncrbyfloatCommand};
+use json::JsonCommand;
 use keys::KeysCommand;
+use keyspace::{CopyCommand, ExistsCommand, RenameCommand, TypeCommand};
 use mget::MgetCommand;
+use mset::MsetCommand;
 use ping::PingCommand;
-use set::SetCommand;
+use scan::{HscanCommand, ScanCommand, SscanCommand, ZscanCommand};
+use search::{
+    FtAggregateCommand, FtCreateCommand, FtDropindexCommand, FtInfoCommand, FtSearchCommand,
+};
+use set::{GetsetCommand, SetCommand, SetexCommand, SetnxCommand};
+use sets::{
+    SaddCommand, ScardCommand, SetAlgebraCommand, SetOp, SintercardCommand, SismemberCommand,
+    SmembersCommand, SmismemberCommand, SmoveCommand, SpopCommand, SrandmemberCommand, SremCommand,
+};
+use stream_groups::{
+    XackCommand, XautoclaimCommand, XclaimCommand, XgroupCommand, XpendingCommand,
+    XreadgroupCommand,
+};
+use streams::{
+    XaddCommand, XdelCommand, XinfoCommand, XlenCommand, XrangeCommand, XreadCommand, XtrimCommand,
+};
+use strings::{AppendCommand, GetrangeCommand, LcsCommand, SetrangeCommand, StrlenCommand};
+use suggest::{FtSugaddCommand, FtSugdelCommand, FtSuggetCommand, FtSuglenCommand};
+use tdigest::{TdigestAddCommand, TdigestCreateCommand, TdigestMergeCommand, TdigestQueryCommand};
+use timeseries::{
+    TsAddCommand, TsCreateCommand, TsCreateruleCommand, TsDeleteruleCommand, TsIncrbyCommand,
+    TsMaddCommand, TsRangeCommand,
+};
+use topk::{TopkAddCommand, TopkListCommand, TopkReserveCommand};
+use vectorset::{
+    VaddCommand, VembCommand, VgetattrCommand, VremCommand, VsetattrCommand, VsimCommand,
+    VsizeCommand,
+};
+use zsetops::{ZintercardCommand, ZrangestoreCommand, ZsetAlgebraCommand};
+use zsets::{
+    RangeBy, ZaddCommand, ZcardCommand, ZcountCommand, ZincrbyCommand, ZmscoreCommand, ZpopCommand,
+    ZrandmemberCommand, ZrangeCommand, ZrankCommand, ZremCommand, ZremrangeCommand, ZscoreCommand,
+};
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 pub struct CommandRegistry {
     commands: HashMap<String, Box<dyn Command + Send + Sync>>,
@@ -35,9 +111,245 @@ impl CommandRegistry {
         registry.register(Box::new(GetCommand));
         registry.register(Box::new(SetCommand));
         registry.register(Box::new(KeysCommand)); // Register KEYS command
-        registry.register(Box::new(DelCommand));
+        registry.register(Box::new(DelCommand { unlink: false }));
         registry.register(Box::new(MgetCommand));
 
+        // Compound string write commands
+        registry.register(Box::new(MsetCommand { nx: false }));
+        registry.register(Box::new(MsetCommand { nx: true }));
+        registry.register(Box::new(SetnxCommand));
+        registry.register(Box::new(SetexCommand { millis: false }));
+        registry.register(Box::new(SetexCommand { millis: true }));
+        registry.register(Box::new(GetsetCommand));
+        registry.register(Box::new(GetdelCommand));
+        registry.register(Box::new(GetexCommand));
+
+        // Expiration commands
+        for (millis, at) in [(false, false), (true, false), (false, true), (true, true)] {
+            registry.register(Box::new(ExpireCommand { millis, at }));
+            registry.register(Box::new(TtlCommand {
+                millis,
+                absolute: at,
+            }));
+        }
+        registry.register(Box::new(PersistCommand));
+
+        // Keyspace commands
+        registry.register(Box::new(DelCommand { unlink: true }));
+        registry.register(Box::new(ExistsCommand { touch: false }));
+        registry.register(Box::new(ExistsCommand { touch: true }));
+        registry.register(Box::new(TypeCommand));
+        registry.register(Box::new(RenameCommand { nx: false }));
+        registry.register(Box::new(RenameCommand { nx: true }));
+        registry.register(Box::new(CopyCommand));
+
+        // Cursor scan commands
+        registry.register(Box::new(ScanCommand));
+        registry.register(Box::new(SscanCommand));
+        registry.register(Box::new(ZscanCommand));
+        registry.register(Box::new(HscanCommand));
+
+        // String counter commands
+        for (decrement, by) in [(false, false), (true, false), (false, true), (true, true)] {
+            registry.register(Box::new(IncrCommand { decrement, by }));
+        }
+        registry.register(Box::new(IncrbyfloatCommand));
+
+        // String manipulation commands
+        registry.register(Box::new(AppendCommand));
+        registry.register(Box::new(StrlenCommand));
+        registry.register(Box::new(GetrangeCommand { substr: false }));
+        registry.register(Box::new(GetrangeCommand { substr: true }));
+        registry.register(Box::new(SetrangeCommand));
+        registry.register(Box::new(LcsCommand));
+
+        // Bitmap commands
+        registry.register(Box::new(SetbitCommand));
+        registry.register(Box::new(GetbitCommand));
+        registry.register(Box::new(BitcountCommand));
+        registry.register(Box::new(BitposCommand));
+        registry.register(Box::new(BitopCommand));
+        registry.register(Box::new(BitfieldCommand { read_only: false }));
+        registry.register(Box::new(BitfieldCommand { read_only: true }));
+
+        // HyperLogLog commands
+        registry.register(Box::new(PfaddCommand));
+        registry.register(Box::new(PfcountCommand));
+        registry.register(Box::new(PfmergeCommand));
+
+        // Bloom and Cuckoo filter commands
+        registry.register(Box::new(BfReserveCommand));
+        registry.register(Box::new(BfAddCommand { multi: false }));
+        registry.register(Box::new(BfAddCommand { multi: true }));
+        registry.register(Box::new(BfExistsCommand { multi: false }));
+        registry.register(Box::new(BfExistsCommand { multi: true }));
+        registry.register(Box::new(BfInfoCommand));
+        registry.register(Box::new(CfAddCommand));
+        registry.register(Box::new(CfCountCommand { exists: true }));
+        registry.register(Box::new(CfCountCommand { exists: false }));
+        registry.register(Box::new(CfDelCommand));
+
+        // Sketch commands
+        registry.register(Box::new(CmsInitCommand {
+            by_probability: false,
+        }));
+        registry.register(Box::new(CmsInitCommand {
+            by_probability: true,
+        }));
+        registry.register(Box::new(CmsIncrbyCommand));
+        registry.register(Box::new(CmsQueryCommand));
+        registry.register(Box::new(CmsMergeCommand));
+        registry.register(Box::new(TopkReserveCommand));
+        registry.register(Box::new(TopkAddCommand));
+        registry.register(Box::new(TopkListCommand));
+        registry.register(Box::new(TdigestCreateCommand));
+        registry.register(Box::new(TdigestAddCommand));
+        registry.register(Box::new(TdigestQueryCommand { cdf: false }));
+        registry.register(Box::new(TdigestQueryCommand { cdf: true }));
+        registry.register(Box::new(TdigestMergeCommand));
+
+        // Time series commands
+        registry.register(Box::new(TsCreateCommand));
+        registry.register(Box::new(TsAddCommand));
+        registry.register(Box::new(TsMaddCommand));
+        registry.register(Box::new(TsIncrbyCommand { decrement: false }));
+        registry.register(Box::new(TsIncrbyCommand { decrement: true }));
+        for (reverse, multi) in [(false, false), (true, false), (false, true), (true, true)] {
+            registry.register(Box::new(TsRangeCommand { reverse, multi }));
+        }
+        registry.register(Box::new(TsCreateruleCommand));
+        registry.register(Box::new(TsDeleteruleCommand));
+
+        // Search index commands
+        registry.register(Box::new(FtCreateCommand));
+        registry.register(Box::new(FtSearchCommand));
+        registry.register(Box::new(FtAggregateCommand));
+        registry.register(Box::new(FtDropindexCommand));
+        registry.register(Box::new(FtInfoCommand));
+
+        // Autocomplete commands
+        registry.register(Box::new(FtSugaddCommand));
+        registry.register(Box::new(FtSuggetCommand));
+        registry.register(Box::new(FtSugdelCommand));
+        registry.register(Box::new(FtSuglenCommand));
+
+        // Vector set commands
+        registry.register(Box::new(VaddCommand));
+        registry.register(Box::new(VremCommand));
+        registry.register(Box::new(VsimCommand));
+        registry.register(Box::new(VsizeCommand { dim: false }));
+        registry.register(Box::new(VsizeCommand { dim: true }));
+        registry.register(Box::new(VembCommand));
+        registry.register(Box::new(VgetattrCommand));
+        registry.register(Box::new(VsetattrCommand));
+
+        // Geo commands
+        registry.register(Box::new(GeoaddCommand));
+        registry.register(Box::new(GeodistCommand));
+        registry.register(Box::new(GeoLookupCommand { hash: false }));
+        registry.register(Box::new(GeoLookupCommand { hash: true }));
+        registry.register(Box::new(GeosearchCommand { store: false }));
+        registry.register(Box::new(GeosearchCommand { store: true }));
+
+        // JSON commands
+        registry.register(Box::new(JsonCommand::SET));
+        registry.register(Box::new(JsonCommand::GET));
+        registry.register(Box::new(JsonCommand::DEL));
+        registry.register(Box::new(JsonCommand::MGET));
+        registry.register(Box::new(JsonCommand::TYPE));
+        registry.register(Box::new(JsonCommand::NUMINCRBY));
+        registry.register(Box::new(JsonCommand::STRAPPEND));
+        registry.register(Box::new(JsonCommand::ARRAPPEND));
+        registry.register(Box::new(JsonCommand::ARRPOP));
+        registry.register(Box::new(JsonCommand::OBJKEYS));
+
+        // Hash commands
+        registry.register(Box::new(HsetCommand));
+        registry.register(Box::new(HgetCommand));
+        registry.register(Box::new(HdelCommand));
+        registry.register(Box::new(HgetallCommand));
+        registry.register(Box::new(HlenCommand));
+        registry.register(Box::new(HexistsCommand));
+        for (millis, at) in [(false, false), (true, false), (false, true), (true, true)] {
+            registry.register(Box::new(HexpireCommand { millis, at }));
+            registry.register(Box::new(HttlCommand {
+                millis,
+                absolute: at,
+            }));
+        }
+        registry.register(Box::new(HpersistCommand));
+
+        // Set commands
+        registry.register(Box::new(SaddCommand));
+        registry.register(Box::new(SremCommand));
+        registry.register(Box::new(SismemberCommand));
+        registry.register(Box::new(SmismemberCommand));
+        registry.register(Box::new(SmembersCommand));
+        registry.register(Box::new(ScardCommand));
+        registry.register(Box::new(SpopCommand));
+        registry.register(Box::new(SrandmemberCommand));
+        registry.register(Box::new(SmoveCommand));
+        registry.register(Box::new(SintercardCommand));
+        for op in [SetOp::Inter, SetOp::Union, SetOp::Diff] {
+            registry.register(Box::new(SetAlgebraCommand { op, store: false }));
+            registry.register(Box::new(SetAlgebraCommand { op, store: true }));
+        }
+
+        // Sorted set commands
+        registry.register(Box::new(ZaddCommand));
+        registry.register(Box::new(ZincrbyCommand));
+        registry.register(Box::new(ZremCommand));
+        registry.register(Box::new(ZscoreCommand));
+        registry.register(Box::new(ZmscoreCommand));
+        registry.register(Box::new(ZcardCommand));
+        registry.register(Box::new(ZrandmemberCommand));
+        for by in [RangeBy::Rank, RangeBy::Score, RangeBy::Lex] {
+            registry.register(Box::new(ZremrangeCommand { by }));
+        }
+        for by in [RangeBy::Score, RangeBy::Lex] {
+            registry.register(Box::new(ZcountCommand { by }));
+        }
+        for reverse in [false, true] {
+            registry.register(Box::new(ZrankCommand { reverse }));
+        }
+        for highest in [false, true] {
+            registry.register(Box::new(ZpopCommand { highest }));
+            registry.register(Box::new(BzpopCommand { highest }));
+        }
+        registry.register(Box::new(BzmpopCommand));
+        for (name, by, reverse) in [
+            ("ZRANGE", RangeBy::Rank, false),
+            ("ZREVRANGE", RangeBy::Rank, true),
+            ("ZRANGEBYSCORE", RangeBy::Score, false),
+            ("ZREVRANGEBYSCORE", RangeBy::Score, true),
+            ("ZRANGEBYLEX", RangeBy::Lex, false),
+            ("ZREVRANGEBYLEX", RangeBy::Lex, true),
+        ] {
+            registry.register(Box::new(ZrangeCommand { name, by, reverse }));
+        }
+        for op in [SetOp::Union, SetOp::Inter, SetOp::Diff] {
+            registry.register(Box::new(ZsetAlgebraCommand { op, store: false }));
+            registry.register(Box::new(ZsetAlgebraCommand { op, store: true }));
+        }
+        registry.register(Box::new(ZintercardCommand));
+        registry.register(Box::new(ZrangestoreCommand));
+
+        // Stream commands
+        registry.register(Box::new(XaddCommand));
+        registry.register(Box::new(XtrimCommand));
+        registry.register(Box::new(XrangeCommand { reverse: false }));
+        registry.register(Box::new(XrangeCommand { reverse: true }));
+        registry.register(Box::new(XlenCommand));
+        registry.register(Box::new(XdelCommand));
+        registry.register(Box::new(XreadCommand));
+        registry.register(Box::new(XinfoCommand));
+        registry.register(Box::new(XgroupCommand));
+        registry.register(Box::new(XreadgroupCommand));
+        registry.register(Box::new(XackCommand));
+        registry.register(Box::new(XpendingCommand));
+        registry.register(Box::new(XclaimCommand));
+        registry.register(Box::new(XautoclaimCommand));
+
         registry
     }
 
@@ -53,9 +365,10 @@ impl CommandRegistry {
     }
 }
 
-// Make the registry thread-safe and shareable
-pub type SharedRegistry = Arc<Mutex<CommandRegistry>>;
+// Make the registry thread-safe and shareable; it is read-only once built, so no lock is
+// needed and a blocked command never holds up other clients
+pub type SharedRegistry = Arc<CommandRegistry>;
 
 pub fn create_registry() -> SharedRegistry {
-    Arc::new(Mutex::new(CommandRegistry::new()))
+    Arc::new(CommandRegistry::new())
 }
@@ -0,0 +1,58 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, parse_bytes_args, send_arity_error, send_error,
+        send_ok, send_resp, Command, CommandContext,
+    },
+    resp::Resp,
+};
+
+// MSET key value [key value ...] and MSETNX, which writes nothing and replies 0 when any
+// of the keys already exists. Both hold the locks for the whole batch, so other clients
+// see either none or all of the writes.
+pub struct MsetCommand {
+    pub nx: bool,
+}
+
+impl Command for MsetCommand {
+    fn name(&self) -> &'static str {
+        if self.nx {
+            "MSETNX"
+        } else {
+            "MSET"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() && args.len() % 2 == 0 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+
+        if self.nx {
+            for pair in args.chunks(2) {
+                expire_if_needed(&mut memory, &mut expiry_manager, &pair[0]);
+                if memory.data.contains_key(&pair[0]) {
+                    return send_resp(&mut ctx.stream, Resp::Integer(0));
+                }
+            }
+        }
+        for (pair, values) in args.chunks(2).zip(values.chunks(2)) {
+            memory.set(pair[0].clone(), values[1].clone());
+            expiry_manager.remove_expiry(&pair[0]);
+        }
+
+        if self.nx {
+            send_resp(&mut ctx.stream, Resp::Integer(1));
+        } else {
+            send_ok(&mut ctx.stream);
+        }
+    }
+}
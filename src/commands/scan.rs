@@ -0,0 +1,223 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+        send_error, send_resp, send_wrongtype, Command, CommandContext,
+    },
+    resp::Resp,
+    types::zset::format_score,
+};
+use glob::Pattern;
+
+const DEFAULT_COUNT: usize = 10;
+
+// MATCH, COUNT and (for SCAN only) TYPE, following the cursor
+struct ScanOptions {
+    cursor: u64,
+    // An unparsable pattern matches nothing, as with KEYS
+    pattern: Option<Result<Pattern, glob::PatternError>>,
+    count: usize,
+    kind: Option<String>,
+}
+
+impl ScanOptions {
+    fn parse(args: &[String], allow_type: bool) -> Result<Self, &'static str> {
+        let cursor = args[0].parse::<u64>().map_err(|_| "invalid cursor")?;
+        let mut options = ScanOptions {
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+            kind: None,
+        };
+        let mut index = 1;
+        while index < args.len() {
+            let Some(value) = args.get(index + 1) else {
+                return Err(errors::SYNTAX);
+            };
+            match args[index].to_ascii_uppercase().as_str() {
+                "MATCH" => options.pattern = Some(Pattern::new(value)),
+                "COUNT" => match parse_int_arg(value)? {
+                    count if count >= 1 => options.count = count as usize,
+                    _ => return Err(errors::SYNTAX),
+                },
+                "TYPE" if allow_type => options.kind = Some(value.clone()),
+                _ => return Err(errors::SYNTAX),
+            }
+            index += 2;
+        }
+        Ok(options)
+    }
+
+    fn matches(&self, member: &str) -> bool {
+        match &self.pattern {
+            Some(Ok(pattern)) => pattern.matches(member),
+            Some(Err(_)) => false,
+            None => true,
+        }
+    }
+}
+
+fn scan_reply(cursor: u64, items: Vec<impl Into<Vec<u8>>>) -> Resp {
+    Resp::Array(vec![
+        Resp::BulkString(Some(cursor.to_string().into())),
+        Resp::Array(
+            items
+                .into_iter()
+                .map(|item| Resp::BulkString(Some(item.into())))
+                .collect(),
+        ),
+    ])
+}
+
+// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]. Each call looks at about COUNT
+// keys, so the filters may leave a step with fewer or none; the walk is over when the
+// returned cursor is 0.
+pub struct ScanCommand;
+
+impl Command for ScanCommand {
+    fn name(&self) -> &'static str {
+        "SCAN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let options = match ScanOptions::parse(&args, true) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+
+        let (keys, cursor) = memory.scan(options.cursor, options.count);
+        let mut found = Vec::new();
+        for key in keys {
+            expire_if_needed(&mut memory, &mut expiry_manager, &key);
+            let Some(value) = memory.data.get(&key) else {
+                continue;
+            };
+            let kind_matches = options
+                .kind
+                .as_ref()
+                .is_none_or(|kind| kind.eq_ignore_ascii_case(value.type_name()));
+            if kind_matches && options.matches(&key) {
+                found.push(key);
+            }
+        }
+        send_resp(&mut ctx.stream, scan_reply(cursor, found));
+    }
+}
+
+// SSCAN key cursor [MATCH pattern] [COUNT count]
+pub struct SscanCommand;
+
+impl Command for SscanCommand {
+    fn name(&self) -> &'static str {
+        "SSCAN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let options = match ScanOptions::parse(&args[1..], false) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let (members, cursor) = match memory.get_set(&args[0]) {
+            Ok(Some(set)) => set.scan(options.cursor, options.count),
+            Ok(None) => (Vec::new(), 0),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let members = members
+            .into_iter()
+            .filter(|member| options.matches(member))
+            .collect();
+        send_resp(&mut ctx.stream, scan_reply(cursor, members));
+    }
+}
+
+// ZSCAN key cursor [MATCH pattern] [COUNT count], replying with member-score pairs
+pub struct ZscanCommand;
+
+impl Command for ZscanCommand {
+    fn name(&self) -> &'static str {
+        "ZSCAN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let options = match ScanOptions::parse(&args[1..], false) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let (entries, cursor) = match memory.get_zset(&args[0]) {
+            Ok(Some(zset)) => zset.scan(options.cursor, options.count),
+            Ok(None) => (Vec::new(), 0),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let items = entries
+            .into_iter()
+            .filter(|(member, _)| options.matches(member))
+            .flat_map(|(member, score)| [member, format_score(score)])
+            .collect();
+        send_resp(&mut ctx.stream, scan_reply(cursor, items));
+    }
+}
+
+// HSCAN key cursor [MATCH pattern] [COUNT count], replying with field-value pairs; MATCH
+// applies to the fields
+pub struct HscanCommand;
+
+impl Command for HscanCommand {
+    fn name(&self) -> &'static str {
+        "HSCAN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let options = match ScanOptions::parse(&args[1..], false) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let (entries, cursor) = match memory.get_hash(&args[0]) {
+            Ok(Some(hash)) => hash.scan(options.cursor, options.count),
+            Ok(None) => (Vec::new(), 0),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let items = entries
+            .into_iter()
+            .filter(|(field, _)| options.matches(field))
+            .flat_map(|(field, value)| [field.into_bytes(), value])
+            .collect();
+        send_resp(&mut ctx.stream, scan_reply(cursor, items));
+    }
+}
@@ -0,0 +1,867 @@
+use std::collections::HashMap;
+
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_ok,
+        send_resp, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    memory::Memory,
+    resp::Resp,
+    types::{
+        search::{Field, FieldKind, SearchIndex},
+        zset::format_score,
+    },
+};
+
+const UNKNOWN_INDEX: &str = "Unknown index name";
+const DEFAULT_LIMIT: usize = 10;
+
+fn bulk(text: impl Into<Vec<u8>>) -> Resp {
+    Resp::BulkString(Some(text.into()))
+}
+
+// A `count arg [arg ...]` block starting at `at`, as PREFIX, RETURN, LOAD, GROUPBY,
+// REDUCE and SORTBY take
+fn counted(args: &[String], at: usize) -> Result<&[String], &'static str> {
+    let count = args
+        .get(at)
+        .and_then(|count| count.parse::<usize>().ok())
+        .ok_or(errors::SYNTAX)?;
+    args.get(at + 1..at + 1 + count).ok_or(errors::SYNTAX)
+}
+
+fn parse_limit(args: &[String], at: usize) -> Result<(usize, usize), &'static str> {
+    match (args.get(at + 1), args.get(at + 2)) {
+        (Some(offset), Some(count)) => match (offset.parse(), count.parse()) {
+            (Ok(offset), Ok(count)) => Ok((offset, count)),
+            _ => Err(errors::SYNTAX),
+        },
+        _ => Err(errors::SYNTAX),
+    }
+}
+
+// Expires the query's matches whose key or fields are past due but not yet swept, so the
+// query then runs over live data only
+fn expire_matches(
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+    name: &str,
+    input: &str,
+) {
+    let Some(index) = memory.get_index(name) else {
+        return;
+    };
+    let Ok(query) = index.parse_query(input) else {
+        return;
+    };
+    for key in index.search(&query) {
+        expire_if_needed(memory, expiry_manager, &key);
+    }
+}
+
+// An aggregation property, written `@name`
+fn property(arg: &str) -> Result<String, String> {
+    match arg.strip_prefix('@') {
+        Some(name) if !name.is_empty() => Ok(name.to_string()),
+        _ => Err(format!("Bad property `{}`: properties start with @", arg)),
+    }
+}
+
+// FT.CREATE index [ON HASH] [PREFIX count prefix ...] SCHEMA field [AS attribute]
+// TEXT [WEIGHT weight] | NUMERIC | TAG [SEPARATOR sep] [CASESENSITIVE], each optionally
+// SORTABLE. Hashes already under the prefixes are indexed straight away.
+pub struct FtCreateCommand;
+
+impl FtCreateCommand {
+    fn parse(args: &[String]) -> Result<SearchIndex, String> {
+        let mut prefixes = Vec::new();
+        let mut index = 1;
+        loop {
+            match args
+                .get(index)
+                .map(|arg| arg.to_ascii_uppercase())
+                .as_deref()
+            {
+                Some("ON") => match args.get(index + 1) {
+                    Some(kind) if kind.eq_ignore_ascii_case("HASH") => index += 2,
+                    _ => return Err("Only HASH indexes are supported".to_string()),
+                },
+                Some("PREFIX") => {
+                    let block = counted(args, index + 1)?;
+                    prefixes.extend(block.iter().cloned());
+                    index += 2 + block.len();
+                }
+                Some("SCHEMA") => break,
+                _ => return Err(errors::SYNTAX.to_string()),
+            }
+        }
+        index += 1;
+        if prefixes.is_empty() {
+            prefixes.push(String::new());
+        }
+
+        let mut fields: Vec<Field> = Vec::new();
+        while index < args.len() {
+            let identifier = args[index].clone();
+            let mut attribute = identifier.clone();
+            index += 1;
+            if args
+                .get(index)
+                .is_some_and(|arg| arg.eq_ignore_ascii_case("AS"))
+            {
+                attribute = args.get(index + 1).ok_or(errors::SYNTAX)?.clone();
+                index += 2;
+            }
+            let kind = match args
+                .get(index)
+                .map(|arg| arg.to_ascii_uppercase())
+                .as_deref()
+            {
+                Some("TEXT") => FieldKind::Text { weight: 1.0 },
+                Some("NUMERIC") => FieldKind::Numeric,
+                Some("TAG") => FieldKind::Tag {
+                    separator: ',',
+                    case_sensitive: false,
+                },
+                _ => return Err(format!("Invalid field type for field `{}`", identifier)),
+            };
+            index += 1;
+            let mut field = Field {
+                identifier,
+                attribute,
+                kind,
+                sortable: false,
+            };
+
+            // Field options run until an argument that isn't one, which starts the next field
+            while let Some(option) = args.get(index).map(|arg| arg.to_ascii_uppercase()) {
+                let value = args.get(index + 1);
+                match (option.as_str(), &mut field.kind, value) {
+                    ("SORTABLE", _, _) => field.sortable = true,
+                    ("NOSTEM", FieldKind::Text { .. }, _) => {}
+                    ("WEIGHT", FieldKind::Text { weight }, Some(value)) => {
+                        *weight = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|weight| weight.is_finite() && *weight >= 0.0)
+                            .ok_or("Bad arguments for WEIGHT: Could not convert argument")?;
+                        index += 1;
+                    }
+                    ("SEPARATOR", FieldKind::Tag { separator, .. }, Some(value)) => {
+                        let mut chars = value.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => *separator = c,
+                            _ => return Err("Tag separator must be a single character".to_string()),
+                        }
+                        index += 1;
+                    }
+                    ("CASESENSITIVE", FieldKind::Tag { case_sensitive, .. }, _) => {
+                        *case_sensitive = true
+                    }
+                    _ => break,
+                }
+                index += 1;
+            }
+
+            if fields
+                .iter()
+                .any(|other| other.attribute == field.attribute)
+            {
+                return Err(format!("Duplicate field in schema - {}", field.attribute));
+            }
+            fields.push(field);
+        }
+        if fields.is_empty() {
+            return Err("Fields arguments are missing".to_string());
+        }
+        Ok(SearchIndex::new(args[0].clone(), prefixes, fields))
+    }
+}
+
+impl Command for FtCreateCommand {
+    fn name(&self) -> &'static str {
+        "FT.CREATE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let index = match Self::parse(&args) {
+            Ok(index) => index,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+
+        let mut memory = ctx.state.memory.lock().unwrap();
+        if memory.create_index(index) {
+            send_ok(&mut ctx.stream);
+        } else {
+            send_error(&mut ctx.stream, "Index already exists");
+        }
+    }
+}
+
+// FT.SEARCH index query [NOCONTENT] [WITHSCORES] [RETURN count field ...]
+// [SORTBY field [ASC|DESC]] [LIMIT offset count], replying with the number of matches and
+// then each key with its fields. Without SORTBY, matches come best TF-IDF score first.
+pub struct FtSearchCommand;
+
+struct SearchOptions {
+    nocontent: bool,
+    withscores: bool,
+    fields: Option<Vec<String>>,
+    sort_by: Option<(String, bool)>,
+    limit: (usize, usize),
+}
+
+impl SearchOptions {
+    fn parse(args: &[String]) -> Result<Self, &'static str> {
+        let mut options = SearchOptions {
+            nocontent: false,
+            withscores: false,
+            fields: None,
+            sort_by: None,
+            limit: (0, DEFAULT_LIMIT),
+        };
+        let mut index = 2;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "NOCONTENT" => options.nocontent = true,
+                "WITHSCORES" => options.withscores = true,
+                "RETURN" => {
+                    let fields = counted(args, index + 1)?;
+                    options.fields = Some(fields.to_vec());
+                    index += 1 + fields.len();
+                }
+                "SORTBY" => {
+                    let field = args.get(index + 1).ok_or(errors::SYNTAX)?.clone();
+                    index += 1;
+                    let order = args.get(index + 1).map(|arg| arg.to_ascii_uppercase());
+                    let descending = order.as_deref() == Some("DESC");
+                    if matches!(order.as_deref(), Some("ASC" | "DESC")) {
+                        index += 1;
+                    }
+                    options.sort_by = Some((field, descending));
+                }
+                "LIMIT" => {
+                    options.limit = parse_limit(args, index)?;
+                    index += 2;
+                }
+                "DIALECT" if index + 1 < args.len() => index += 1,
+                _ => return Err(errors::SYNTAX),
+            }
+            index += 1;
+        }
+        Ok(options)
+    }
+}
+
+impl Command for FtSearchCommand {
+    fn name(&self) -> &'static str {
+        "FT.SEARCH"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let options = match SearchOptions::parse(&args) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_matches(&mut memory, &mut expiry_manager, &args[0], &args[1]);
+        let Some(index) = memory.get_index(&args[0]) else {
+            return send_error(&mut ctx.stream, UNKNOWN_INDEX);
+        };
+        let query = match index.parse_query(&args[1]) {
+            Ok(query) => query,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+
+        let mut matches = index
+            .search(&query)
+            .into_iter()
+            .map(|key| {
+                let score = index.score(&query, &key);
+                (key, score)
+            })
+            .collect::<Vec<_>>();
+        match &options.sort_by {
+            Some((attribute, descending)) => {
+                let Some(field) = index.field(attribute) else {
+                    return send_error(
+                        &mut ctx.stream,
+                        &format!("Property `{}` not loaded nor in schema", attribute),
+                    );
+                };
+                let mut keyed = matches
+                    .into_iter()
+                    .map(|(key, score)| (index.sort_value(&key, field), key, score))
+                    .collect::<Vec<_>>();
+                // Documents without the field sort last either way
+                keyed.sort_by(|(a, a_key, _), (b, b_key, _)| {
+                    let order = match (a, b) {
+                        (Some(a), Some(b)) if *descending => b.partial_cmp(a),
+                        (Some(a), Some(b)) => a.partial_cmp(b),
+                        (a, b) => b.is_some().partial_cmp(&a.is_some()),
+                    };
+                    order
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then_with(|| a_key.cmp(b_key))
+                });
+                matches = keyed
+                    .into_iter()
+                    .map(|(_, key, score)| (key, score))
+                    .collect();
+            }
+            None => matches
+                .sort_by(|(a_key, a), (b_key, b)| b.total_cmp(a).then_with(|| a_key.cmp(b_key))),
+        }
+
+        let (offset, count) = options.limit;
+        let mut items = vec![Resp::Integer(matches.len() as i64)];
+        for (key, score) in matches.into_iter().skip(offset).take(count) {
+            items.push(bulk(key.clone()));
+            if options.withscores {
+                items.push(bulk(format_score(score)));
+            }
+            if options.nocontent {
+                continue;
+            }
+            let Ok(Some(hash)) = memory.get_hash(&key) else {
+                items.push(Resp::Array(Vec::new()));
+                continue;
+            };
+            let pairs: Vec<(String, &[u8])> = match &options.fields {
+                // RETURN names attributes, read from the hash fields they index
+                Some(names) => names
+                    .iter()
+                    .filter_map(|name| {
+                        let identifier = index
+                            .field(name)
+                            .map_or(name, |field| &index.fields[field].identifier);
+                        hash.get(identifier).map(|value| (name.clone(), value))
+                    })
+                    .collect(),
+                None => hash
+                    .entries()
+                    .map(|(field, value)| (field.clone(), value.as_slice()))
+                    .collect(),
+            };
+            let fields = pairs
+                .into_iter()
+                .flat_map(|(field, value)| [bulk(field), bulk(value.to_vec())])
+                .collect();
+            items.push(Resp::Array(fields));
+        }
+        send_resp(&mut ctx.stream, Resp::Array(items));
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Cell {
+    Null,
+    Text(String),
+    List(Vec<String>),
+}
+
+impl Cell {
+    fn number(&self) -> Option<f64> {
+        match self {
+            Cell::Text(text) => text.parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+
+    fn to_resp(&self) -> Resp {
+        match self {
+            Cell::Null => Resp::BulkString(None),
+            Cell::Text(text) => bulk(text.clone()),
+            Cell::List(items) => Resp::Array(items.iter().cloned().map(bulk).collect()),
+        }
+    }
+}
+
+// Numbers compare as numbers, anything else as text; missing values sort last
+fn compare_cells(a: Option<&Cell>, b: Option<&Cell>) -> std::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match (a.number(), b.number(), a, b) {
+            (Some(a), Some(b), _, _) => a.total_cmp(&b),
+            (_, _, Cell::Text(a), Cell::Text(b)) => a.cmp(b),
+            _ => std::cmp::Ordering::Equal,
+        },
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    }
+}
+
+// One row of an aggregation: the properties it shows, and the rest of its document,
+// which steps may still read
+struct Row {
+    cells: Vec<(String, Cell)>,
+    source: HashMap<String, String>,
+}
+
+impl Row {
+    fn get(&self, name: &str) -> Option<Cell> {
+        self.cells
+            .iter()
+            .find(|(cell, _)| cell == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| self.source.get(name).cloned().map(Cell::Text))
+    }
+}
+
+enum ReduceFunction {
+    Count,
+    CountDistinct,
+    Sum,
+    Min,
+    Max,
+    Avg,
+    ToList,
+}
+
+struct Reducer {
+    function: ReduceFunction,
+    property: Option<String>,
+    alias: String,
+}
+
+impl Reducer {
+    fn reduce(&self, rows: &[&Row]) -> Cell {
+        let values = || {
+            rows.iter()
+                .filter_map(|row| row.get(self.property.as_deref()?))
+                .filter(|cell| *cell != Cell::Null)
+        };
+        let numbers = || values().filter_map(|cell| cell.number());
+        let number = |number: f64| Cell::Text(format_score(number));
+        match self.function {
+            ReduceFunction::Count => number(rows.len() as f64),
+            ReduceFunction::CountDistinct => {
+                let mut distinct = values().collect::<Vec<_>>();
+                distinct.sort_by(|a, b| compare_cells(Some(a), Some(b)));
+                distinct.dedup();
+                number(distinct.len() as f64)
+            }
+            ReduceFunction::Sum => number(numbers().sum()),
+            ReduceFunction::Min => numbers().reduce(f64::min).map_or(Cell::Null, number),
+            ReduceFunction::Max => numbers().reduce(f64::max).map_or(Cell::Null, number),
+            ReduceFunction::Avg => {
+                let (sum, count) = numbers().fold((0.0, 0), |(sum, count), n| (sum + n, count + 1));
+                if count == 0 {
+                    Cell::Null
+                } else {
+                    number(sum / count as f64)
+                }
+            }
+            ReduceFunction::ToList => {
+                let mut items = Vec::new();
+                for cell in values() {
+                    if let Cell::Text(text) = cell {
+                        if !items.contains(&text) {
+                            items.push(text);
+                        }
+                    }
+                }
+                Cell::List(items)
+            }
+        }
+    }
+}
+
+enum Step {
+    GroupBy(Vec<String>, Vec<Reducer>),
+    SortBy(Vec<(String, bool)>, Option<usize>),
+    Limit(usize, usize),
+}
+
+// The LOAD list (None for `LOAD *`) and the pipeline steps, in order
+fn parse_pipeline(args: &[String]) -> Result<(Option<Vec<String>>, Vec<Step>), String> {
+    let mut load = Some(Vec::new());
+    let mut steps = Vec::new();
+    let mut index = 2;
+    while index < args.len() {
+        match args[index].to_ascii_uppercase().as_str() {
+            "LOAD" if args.get(index + 1).is_some_and(|arg| arg == "*") => {
+                load = None;
+                index += 2;
+            }
+            "LOAD" => {
+                let block = counted(args, index + 1)?;
+                if let Some(load) = &mut load {
+                    for arg in block {
+                        load.push(arg.strip_prefix('@').unwrap_or(arg).to_string());
+                    }
+                }
+                index += 2 + block.len();
+            }
+            "GROUPBY" => {
+                let block = counted(args, index + 1)?;
+                let properties = block
+                    .iter()
+                    .map(|arg| property(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                index += 2 + block.len();
+                let mut reducers = Vec::new();
+                while args
+                    .get(index)
+                    .is_some_and(|arg| arg.eq_ignore_ascii_case("REDUCE"))
+                {
+                    let name = args
+                        .get(index + 1)
+                        .ok_or(errors::SYNTAX)?
+                        .to_ascii_uppercase();
+                    let block = counted(args, index + 2)?;
+                    index += 3 + block.len();
+                    let function = match (name.as_str(), block.len()) {
+                        ("COUNT", 0) => ReduceFunction::Count,
+                        ("COUNT_DISTINCT", 1) => ReduceFunction::CountDistinct,
+                        ("SUM", 1) => ReduceFunction::Sum,
+                        ("MIN", 1) => ReduceFunction::Min,
+                        ("MAX", 1) => ReduceFunction::Max,
+                        ("AVG", 1) => ReduceFunction::Avg,
+                        ("TOLIST", 1) => ReduceFunction::ToList,
+                        (
+                            "COUNT" | "COUNT_DISTINCT" | "SUM" | "MIN" | "MAX" | "AVG" | "TOLIST",
+                            _,
+                        ) => return Err(format!("Bad arguments for {}", name)),
+                        _ => return Err(format!("Unknown reducer `{}`", name)),
+                    };
+                    let property = block.first().map(|arg| property(arg)).transpose()?;
+                    // Unnamed results get RediSearch's generated names, like
+                    // __generated_aliassumprice
+                    let mut alias = format!(
+                        "__generated_alias{}{}",
+                        name.to_lowercase(),
+                        property.as_deref().unwrap_or("")
+                    );
+                    if args
+                        .get(index)
+                        .is_some_and(|arg| arg.eq_ignore_ascii_case("AS"))
+                    {
+                        alias = args.get(index + 1).ok_or(errors::SYNTAX)?.clone();
+                        index += 2;
+                    }
+                    reducers.push(Reducer {
+                        function,
+                        property,
+                        alias,
+                    });
+                }
+                steps.push(Step::GroupBy(properties, reducers));
+            }
+            "SORTBY" => {
+                let block = counted(args, index + 1)?;
+                index += 2 + block.len();
+                let mut keys: Vec<(String, bool)> = Vec::new();
+                for arg in block {
+                    match (arg.to_ascii_uppercase().as_str(), keys.last_mut()) {
+                        ("ASC", Some(last)) => last.1 = false,
+                        ("DESC", Some(last)) => last.1 = true,
+                        _ => keys.push((property(arg)?, false)),
+                    }
+                }
+                let mut max = None;
+                if args
+                    .get(index)
+                    .is_some_and(|arg| arg.eq_ignore_ascii_case("MAX"))
+                {
+                    let value = args.get(index + 1).and_then(|max| max.parse().ok());
+                    max = Some(value.ok_or(errors::SYNTAX)?);
+                    index += 2;
+                }
+                steps.push(Step::SortBy(keys, max));
+            }
+            "LIMIT" => {
+                let (offset, count) = parse_limit(args, index)?;
+                steps.push(Step::Limit(offset, count));
+                index += 3;
+            }
+            "DIALECT" if index + 1 < args.len() => index += 2,
+            "VERBATIM" => index += 1,
+            _ => return Err(errors::SYNTAX.to_string()),
+        }
+    }
+    Ok((load, steps))
+}
+
+// The rows an aggregation starts from: each matching document with its hash fields,
+// readable under both field and attribute names
+fn source_rows(memory: &Memory, index: &SearchIndex, keys: Vec<String>) -> Vec<Row> {
+    keys.into_iter()
+        .map(|key| {
+            let mut source = HashMap::new();
+            if let Ok(Some(hash)) = memory.get_hash(&key) {
+                for (field, value) in hash.entries() {
+                    source.insert(field.clone(), String::from_utf8_lossy(value).into_owned());
+                }
+                for field in &index.fields {
+                    if let Some(value) = hash.get(&field.identifier) {
+                        let value = String::from_utf8_lossy(value).into_owned();
+                        source.insert(field.attribute.clone(), value);
+                    }
+                }
+            }
+            source.insert("__key".to_string(), key);
+            Row {
+                cells: Vec::new(),
+                source,
+            }
+        })
+        .collect()
+}
+
+// FT.AGGREGATE index query [LOAD count field ... | LOAD *] followed by any sequence of
+// GROUPBY count @property ... [REDUCE function count arg ... [AS name] ...],
+// SORTBY count @property [ASC|DESC] ... [MAX n] and LIMIT offset count steps, each
+// applied to the rows the previous one produced. Reducers: COUNT, COUNT_DISTINCT, SUM,
+// MIN, MAX, AVG and TOLIST.
+pub struct FtAggregateCommand;
+
+impl Command for FtAggregateCommand {
+    fn name(&self) -> &'static str {
+        "FT.AGGREGATE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (load, steps) = match parse_pipeline(&args) {
+            Ok(pipeline) => pipeline,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_matches(&mut memory, &mut expiry_manager, &args[0], &args[1]);
+        let Some(index) = memory.get_index(&args[0]) else {
+            return send_error(&mut ctx.stream, UNKNOWN_INDEX);
+        };
+        let query = match index.parse_query(&args[1]) {
+            Ok(query) => query,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+        let mut keys = index.search(&query);
+        keys.sort();
+
+        let mut rows = source_rows(&memory, index, keys);
+        for row in &mut rows {
+            row.cells = match &load {
+                Some(names) => names
+                    .iter()
+                    .filter_map(|name| {
+                        Some((name.clone(), Cell::Text(row.source.get(name)?.clone())))
+                    })
+                    .collect(),
+                None => {
+                    let mut cells = row
+                        .source
+                        .iter()
+                        .filter(|(name, _)| *name != "__key")
+                        .map(|(name, value)| (name.clone(), Cell::Text(value.clone())))
+                        .collect::<Vec<_>>();
+                    cells.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    cells
+                }
+            };
+        }
+
+        let mut total = None;
+        for step in steps {
+            match step {
+                Step::GroupBy(properties, reducers) => {
+                    let mut groups: Vec<(Vec<Cell>, Vec<&Row>)> = Vec::new();
+                    let mut positions: HashMap<Vec<Cell>, usize> = HashMap::new();
+                    for row in &rows {
+                        let values = properties
+                            .iter()
+                            .map(|name| row.get(name).unwrap_or(Cell::Null))
+                            .collect::<Vec<_>>();
+                        let position = *positions.entry(values.clone()).or_insert_with(|| {
+                            groups.push((values, Vec::new()));
+                            groups.len() - 1
+                        });
+                        groups[position].1.push(row);
+                    }
+                    rows = groups
+                        .into_iter()
+                        .map(|(values, members)| {
+                            let mut cells =
+                                properties.iter().cloned().zip(values).collect::<Vec<_>>();
+                            for reducer in &reducers {
+                                cells.push((reducer.alias.clone(), reducer.reduce(&members)));
+                            }
+                            Row {
+                                cells,
+                                source: HashMap::new(),
+                            }
+                        })
+                        .collect();
+                }
+                Step::SortBy(keys, max) => {
+                    rows.sort_by(|a, b| {
+                        keys.iter()
+                            .fold(std::cmp::Ordering::Equal, |order, (name, descending)| {
+                                order.then_with(|| {
+                                    let (a, b) = (a.get(name), b.get(name));
+                                    match (&a, &b, descending) {
+                                        (Some(_), Some(_), true) => {
+                                            compare_cells(b.as_ref(), a.as_ref())
+                                        }
+                                        _ => compare_cells(a.as_ref(), b.as_ref()),
+                                    }
+                                })
+                            })
+                    });
+                    if let Some(max) = max {
+                        rows.truncate(max);
+                    }
+                }
+                Step::Limit(offset, count) => {
+                    total.get_or_insert(rows.len());
+                    rows = rows.into_iter().skip(offset).take(count).collect();
+                }
+            }
+        }
+
+        let mut items = vec![Resp::Integer(total.unwrap_or(rows.len()) as i64)];
+        for row in rows {
+            let cells = row
+                .cells
+                .iter()
+                .flat_map(|(name, value)| [bulk(name.clone()), value.to_resp()])
+                .collect();
+            items.push(Resp::Array(cells));
+        }
+        send_resp(&mut ctx.stream, Resp::Array(items));
+    }
+}
+
+// FT.DROPINDEX index [DD]; DD also deletes the hashes the index covered
+pub struct FtDropindexCommand;
+
+impl Command for FtDropindexCommand {
+    fn name(&self) -> &'static str {
+        "FT.DROPINDEX"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let delete_documents = match args.get(1) {
+            Some(option) if option.eq_ignore_ascii_case("DD") => true,
+            Some(_) => return send_error(&mut ctx.stream, errors::SYNTAX),
+            None => false,
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        let Some(index) = memory.drop_index(&args[0]) else {
+            return send_error(&mut ctx.stream, UNKNOWN_INDEX);
+        };
+        if delete_documents {
+            for key in index.keys() {
+                memory.delete(key);
+                expiry_manager.remove_expiry(key);
+                expiry_manager.set_parts_expiry(key, None);
+            }
+        }
+        send_ok(&mut ctx.stream);
+    }
+}
+
+// FT.INFO index: the definition, schema and index sizes
+pub struct FtInfoCommand;
+
+impl Command for FtInfoCommand {
+    fn name(&self) -> &'static str {
+        "FT.INFO"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let memory = ctx.state.memory.lock().unwrap();
+        let Some(index) = memory.get_index(&args[0]) else {
+            return send_error(&mut ctx.stream, UNKNOWN_INDEX);
+        };
+
+        let prefixes = index.prefixes.iter().cloned().map(bulk).collect();
+        let definition = vec![
+            bulk("key_type"),
+            bulk("HASH"),
+            bulk("prefixes"),
+            Resp::Array(prefixes),
+        ];
+        let attributes = index
+            .fields
+            .iter()
+            .map(|field| {
+                let mut items = vec![
+                    bulk("identifier"),
+                    bulk(field.identifier.clone()),
+                    bulk("attribute"),
+                    bulk(field.attribute.clone()),
+                    bulk("type"),
+                    bulk(field.kind.type_name()),
+                ];
+                match field.kind {
+                    FieldKind::Text { weight } => {
+                        items.extend([bulk("WEIGHT"), bulk(format_score(weight))])
+                    }
+                    FieldKind::Tag {
+                        separator,
+                        case_sensitive,
+                    } => {
+                        items.extend([bulk("SEPARATOR"), bulk(separator.to_string())]);
+                        if case_sensitive {
+                            items.push(bulk("CASESENSITIVE"));
+                        }
+                    }
+                    FieldKind::Numeric => {}
+                }
+                if field.sortable {
+                    items.push(bulk("SORTABLE"));
+                }
+                Resp::Array(items)
+            })
+            .collect();
+
+        let info = vec![
+            bulk("index_name"),
+            bulk(index.name.clone()),
+            bulk("index_definition"),
+            Resp::Array(definition),
+            bulk("attributes"),
+            Resp::Array(attributes),
+            bulk("num_docs"),
+            Resp::Integer(index.len() as i64),
+            bulk("num_terms"),
+            Resp::Integer(index.term_count() as i64),
+            bulk("num_records"),
+            Resp::Integer(index.record_count() as i64),
+            bulk("hash_indexing_failures"),
+            Resp::Integer(index.failures as i64),
+        ];
+        send_resp(&mut ctx.stream, Resp::Array(info));
+    }
+}
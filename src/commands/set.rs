@@ -1,11 +1,14 @@
 use crate::{
     commands::command::{
-        errors, parse_bulk_string_arg, parse_optional_flags, send_error, send_ok, Command,
-        CommandContext,
+        apply_expiry, errors, expire_if_needed, parse_bulk_string_args, parse_bytes_args,
+        parse_expiry, send_arity_error, send_error, send_ok, send_resp, send_wrongtype, Command,
+        CommandContext, Expiry,
     },
     resp::Resp,
 };
 
+// SET key value [NX | XX] [GET] [EX seconds | PX ms | EXAT timestamp | PXAT ms-timestamp
+// | KEEPTTL]. Without an expiry option any existing TTL is cleared.
 pub struct SetCommand;
 
 impl Command for SetCommand {
@@ -14,46 +17,164 @@ impl Command for SetCommand {
     }
 
     fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
-        if args.len() < 2 {
-            send_error(
-                &mut ctx.stream,
-                "SET requires at least key and value arguments",
-            );
-            return;
-        }
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (key, value) = (&args[0], &values[1]);
 
-        match (
-            parse_bulk_string_arg(&args[0]),
-            parse_bulk_string_arg(&args[1]),
-        ) {
-            (Ok(key), Ok(value)) => {
-                // Parse optional flags (e.g., PX, EX)
-                let flags = match parse_optional_flags(&args[2..]) {
-                    Ok(flags) => flags,
-                    Err(err) => {
-                        send_error(&mut ctx.stream, err);
-                        return;
+        let (mut nx, mut xx, mut get) = (false, false, false);
+        let mut expiry = None;
+        let mut index = 2;
+        while index < args.len() {
+            let option = args[index].to_ascii_uppercase();
+            match option.as_str() {
+                "NX" if !xx => nx = true,
+                "XX" if !nx => xx = true,
+                "GET" => get = true,
+                "KEEPTTL" if expiry.is_none() => expiry = Some(Expiry::Keep),
+                "EX" | "PX" | "EXAT" | "PXAT" if expiry.is_none() && index + 1 < args.len() => {
+                    index += 1;
+                    match parse_expiry(&option, &args[index], self.name()) {
+                        Ok(parsed) => expiry = Some(parsed),
+                        Err(err) => return send_error(&mut ctx.stream, &err),
                     }
-                };
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
 
-                let px = flags.get("PX").cloned();
-                let ex = flags.get("EX").map(|&s| s * 1000);
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
 
-                // Set the key-value pair in memory
-                {
-                    let mut memory = ctx.state.memory.lock().unwrap();
-                    memory.set(key.clone(), value.clone());
-                }
+        // GET only works against strings, and fails before anything is written
+        let old = match get.then(|| memory.get(key)) {
+            Some(Ok(old)) => old,
+            Some(Err(_)) => return send_wrongtype(&mut ctx.stream),
+            None => None,
+        };
+        let exists = memory.data.contains_key(key);
+        if (nx && exists) || (xx && !exists) {
+            let reply = if get { old } else { None };
+            return send_resp(&mut ctx.stream, Resp::BulkString(reply));
+        }
 
-                // Set the expiration if provided
-                if let Some(expiry) = px.or(ex) {
-                    let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
-                    expiry_manager.set_expiry(&key, expiry);
-                }
+        memory.set(key.clone(), value.clone());
+        apply_expiry(&mut expiry_manager, key, expiry.unwrap_or(Expiry::Persist));
 
-                send_ok(&mut ctx.stream);
-            }
-            _ => send_error(&mut ctx.stream, errors::INVALID_SET_KEY),
+        if get {
+            send_resp(&mut ctx.stream, Resp::BulkString(old));
+        } else {
+            send_ok(&mut ctx.stream);
+        }
+    }
+}
+
+// SETNX key value, replying 1 when the key was set
+pub struct SetnxCommand;
+
+impl Command for SetnxCommand {
+    fn name(&self) -> &'static str {
+        "SETNX"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if memory.data.contains_key(&args[0]) {
+            return send_resp(&mut ctx.stream, Resp::Integer(0));
+        }
+        memory.set(args[0].clone(), values[1].clone());
+        send_resp(&mut ctx.stream, Resp::Integer(1));
+    }
+}
+
+// SETEX key seconds value and PSETEX key milliseconds value
+pub struct SetexCommand {
+    pub millis: bool,
+}
+
+impl Command for SetexCommand {
+    fn name(&self) -> &'static str {
+        if self.millis {
+            "PSETEX"
+        } else {
+            "SETEX"
         }
     }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let unit = if self.millis { "PX" } else { "EX" };
+        let expiry = match parse_expiry(unit, &args[1], self.name()) {
+            Ok(expiry) => expiry,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        memory.set(args[0].clone(), values[2].clone());
+        apply_expiry(&mut expiry_manager, &args[0], expiry);
+        send_ok(&mut ctx.stream);
+    }
+}
+
+// GETSET key value, replying with the old value and clearing any TTL like SET does
+pub struct GetsetCommand;
+
+impl Command for GetsetCommand {
+    fn name(&self) -> &'static str {
+        "GETSET"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let old = match memory.get(&args[0]) {
+            Ok(old) => old,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        memory.set(args[0].clone(), values[1].clone());
+        expiry_manager.remove_expiry(&args[0]);
+        send_resp(&mut ctx.stream, Resp::BulkString(old));
+    }
 }
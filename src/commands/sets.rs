@@ -0,0 +1,518 @@
+use std::collections::HashSet;
+
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+        send_error, send_resp, send_wrongtype, Command, CommandContext, MAX_RANDOM_REPLY,
+    },
+    memory::{Memory, Value, WrongType},
+    resp::Resp,
+    types::set::SetValue,
+};
+
+fn bulk_array(members: Vec<String>) -> Resp {
+    Resp::Array(
+        members
+            .into_iter()
+            .map(|member| Resp::BulkString(Some(member.into())))
+            .collect(),
+    )
+}
+
+pub struct SaddCommand;
+
+impl Command for SaddCommand {
+    fn name(&self) -> &'static str {
+        "SADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_or_create_set(&args[0]) {
+            Ok(set) => {
+                let added = args[1..]
+                    .iter()
+                    .filter(|member| set.insert(member.to_string()))
+                    .count();
+                send_resp(&mut ctx.stream, Resp::Integer(added as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct SremCommand;
+
+impl Command for SremCommand {
+    fn name(&self) -> &'static str {
+        "SREM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let (removed, now_empty) = match memory.get_set_mut(&args[0]) {
+            Ok(Some(set)) => {
+                let removed = args[1..].iter().filter(|member| set.remove(member)).count();
+                (removed, set.is_empty())
+            }
+            Ok(None) => (0, false),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        // Empty sets are never kept around
+        if now_empty {
+            memory.delete(&args[0]);
+            expiry_manager.remove_expiry(&args[0]);
+        }
+
+        send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+    }
+}
+
+pub struct SismemberCommand;
+
+impl Command for SismemberCommand {
+    fn name(&self) -> &'static str {
+        "SISMEMBER"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_set(&args[0]) {
+            Ok(set) => {
+                let found = set.is_some_and(|set| set.contains(&args[1]));
+                send_resp(&mut ctx.stream, Resp::Integer(found as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct SmismemberCommand;
+
+impl Command for SmismemberCommand {
+    fn name(&self) -> &'static str {
+        "SMISMEMBER"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_set(&args[0]) {
+            Ok(set) => {
+                let results = args[1..]
+                    .iter()
+                    .map(|member| Resp::Integer(set.is_some_and(|set| set.contains(member)) as i64))
+                    .collect();
+                send_resp(&mut ctx.stream, Resp::Array(results));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct SmembersCommand;
+
+impl Command for SmembersCommand {
+    fn name(&self) -> &'static str {
+        "SMEMBERS"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_set(&args[0]) {
+            Ok(set) => {
+                let members = set.map(|set| set.members()).unwrap_or_default();
+                send_resp(&mut ctx.stream, bulk_array(members));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct ScardCommand;
+
+impl Command for ScardCommand {
+    fn name(&self) -> &'static str {
+        "SCARD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_set(&args[0]) {
+            Ok(set) => {
+                let len = set.map_or(0, |set| set.len());
+                send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// Parses the optional COUNT argument of SPOP and SRANDMEMBER
+fn parse_count(args: &[String]) -> Result<Option<i64>, &'static str> {
+    match args.get(1) {
+        Some(count) => parse_int_arg(count).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub struct SpopCommand;
+
+impl Command for SpopCommand {
+    fn name(&self) -> &'static str {
+        "SPOP"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let count = match parse_count(&args) {
+            Ok(Some(count)) if count < 0 => {
+                return send_error(&mut ctx.stream, "value is out of range, must be positive")
+            }
+            Ok(count) => count,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let set = match memory.get_set_mut(&args[0]) {
+            Ok(Some(set)) => set,
+            Ok(None) if count.is_some() => return send_resp(&mut ctx.stream, Resp::Array(vec![])),
+            Ok(None) => return send_resp(&mut ctx.stream, Resp::BulkString(None)),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let popped = set.random_distinct(count.unwrap_or(1) as usize);
+        for member in &popped {
+            set.remove(member);
+        }
+        if set.is_empty() {
+            memory.delete(&args[0]);
+            expiry_manager.remove_expiry(&args[0]);
+        }
+
+        let response = match count {
+            Some(_) => bulk_array(popped),
+            None => Resp::BulkString(popped.into_iter().next().map(String::into_bytes)),
+        };
+        send_resp(&mut ctx.stream, response);
+    }
+}
+
+pub struct SrandmemberCommand;
+
+impl Command for SrandmemberCommand {
+    fn name(&self) -> &'static str {
+        "SRANDMEMBER"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let count = match parse_count(&args) {
+            Ok(count) => count,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let set = match memory.get_set(&args[0]) {
+            Ok(set) => set,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let response = match (set, count) {
+            (None, Some(_)) => Resp::Array(vec![]),
+            (None, None) => Resp::BulkString(None),
+            (Some(set), None) => Resp::BulkString(set.random_member().map(String::into_bytes)),
+            // A negative count allows the same member to be returned several times
+            (Some(set), Some(count)) if count < 0 => bulk_array(
+                (0..count.unsigned_abs().min(MAX_RANDOM_REPLY))
+                    .filter_map(|_| set.random_member())
+                    .collect(),
+            ),
+            (Some(set), Some(count)) => bulk_array(set.random_distinct(count as usize)),
+        };
+        send_resp(&mut ctx.stream, response);
+    }
+}
+
+pub struct SmoveCommand;
+
+impl Command for SmoveCommand {
+    fn name(&self) -> &'static str {
+        "SMOVE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (source, destination, member) = (&args[0], &args[1], &args[2]);
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+        expire_if_needed(&mut memory, &mut expiry_manager, destination);
+
+        // Both keys are type-checked before anything is modified
+        if memory.get_set(destination).is_err() {
+            return send_wrongtype(&mut ctx.stream);
+        }
+        let found = match memory.get_set(source) {
+            Ok(set) => set.is_some_and(|set| set.contains(member)),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        if found && source != destination {
+            if let Ok(Some(set)) = memory.get_set_mut(source) {
+                set.remove(member);
+                if set.is_empty() {
+                    memory.delete(source);
+                    expiry_manager.remove_expiry(source);
+                }
+            }
+            if let Ok(set) = memory.get_or_create_set(destination) {
+                set.insert(member.clone());
+            }
+        }
+
+        send_resp(&mut ctx.stream, Resp::Integer(found as i64));
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+// Orders the sets for an intersection, or None when a key is missing and it is empty
+fn smallest_first(sets: Vec<Option<&SetValue>>) -> Option<Vec<&SetValue>> {
+    let mut sets: Vec<&SetValue> = sets.into_iter().collect::<Option<_>>()?;
+    sets.sort_by_key(|set| set.len());
+    Some(sets)
+}
+
+// Walks the smallest set and probes the others, lazily so SINTERCARD can stop at LIMIT
+fn intersection<'a>(sets: &'a [&'a SetValue]) -> impl Iterator<Item = String> + 'a {
+    sets[0]
+        .iter()
+        .filter(|member| sets[1..].iter().all(|set| set.contains(member)))
+}
+
+// Evaluates a set operation, treating missing keys as empty sets
+fn compute(memory: &Memory, keys: &[String], op: SetOp) -> Result<Vec<String>, WrongType> {
+    let mut sets = Vec::with_capacity(keys.len());
+    for key in keys {
+        sets.push(memory.get_set(key)?);
+    }
+
+    let result = match op {
+        SetOp::Inter => match smallest_first(sets) {
+            Some(sets) => intersection(&sets).collect(),
+            None => Vec::new(),
+        },
+        SetOp::Union => {
+            let mut union = HashSet::new();
+            for set in sets.into_iter().flatten() {
+                union.extend(set.members());
+            }
+            union.into_iter().collect()
+        }
+        SetOp::Diff => match sets[0] {
+            Some(first) => first
+                .members()
+                .into_iter()
+                .filter(|member| sets[1..].iter().flatten().all(|set| !set.contains(member)))
+                .collect(),
+            None => Vec::new(),
+        },
+    };
+
+    Ok(result)
+}
+
+// SINTER/SUNION/SDIFF and their *STORE variants share one implementation
+pub struct SetAlgebraCommand {
+    pub op: SetOp,
+    pub store: bool,
+}
+
+impl Command for SetAlgebraCommand {
+    fn name(&self) -> &'static str {
+        match (self.op, self.store) {
+            (SetOp::Inter, false) => "SINTER",
+            (SetOp::Union, false) => "SUNION",
+            (SetOp::Diff, false) => "SDIFF",
+            (SetOp::Inter, true) => "SINTERSTORE",
+            (SetOp::Union, true) => "SUNIONSTORE",
+            (SetOp::Diff, true) => "SDIFFSTORE",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let min_args = if self.store { 2 } else { 1 };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= min_args => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in &args {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        let keys = if self.store { &args[1..] } else { &args[..] };
+        let members = match compute(&memory, keys, self.op) {
+            Ok(members) => members,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        if !self.store {
+            return send_resp(&mut ctx.stream, bulk_array(members));
+        }
+
+        // The destination is overwritten, dropping any TTL it had
+        let destination = &args[0];
+        let len = members.len();
+        expiry_manager.remove_expiry(destination);
+        if members.is_empty() {
+            memory.delete(destination);
+        } else {
+            memory.insert(
+                destination.clone(),
+                Value::Set(SetValue::from_members(members)),
+            );
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+    }
+}
+
+pub struct SintercardCommand;
+
+impl Command for SintercardCommand {
+    fn name(&self) -> &'static str {
+        "SINTERCARD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let numkeys = match parse_int_arg(&args[0]) {
+            Ok(numkeys) if numkeys > 0 => numkeys as usize,
+            Ok(_) => return send_error(&mut ctx.stream, "numkeys should be greater than 0"),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        if args.len() - 1 < numkeys {
+            return send_error(
+                &mut ctx.stream,
+                "Number of keys can't be greater than number of args",
+            );
+        }
+
+        let keys = &args[1..=numkeys];
+        let limit = match &args[numkeys + 1..] {
+            [] => 0,
+            [option, limit] if option.eq_ignore_ascii_case("LIMIT") => match parse_int_arg(limit) {
+                Ok(limit) if limit >= 0 => limit as usize,
+                Ok(_) => return send_error(&mut ctx.stream, "LIMIT can't be negative"),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            },
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in keys {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        let sets = match keys.iter().map(|key| memory.get_set(key)).collect() {
+            Ok(sets) => sets,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let count = match smallest_first(sets) {
+            Some(sets) => match limit {
+                0 => intersection(&sets).count(),
+                limit => intersection(&sets).take(limit).count(),
+            },
+            None => 0,
+        };
+        send_resp(&mut ctx.stream, Resp::Integer(count as i64));
+    }
+}
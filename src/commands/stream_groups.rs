@@ -0,0 +1,795 @@
+use std::time::Duration;
+
+use crate::{
+    commands::{
+        command::{
+            coded_error_resp, error_resp, errors, expire_if_needed, park_on_keys, parse_block_arg,
+            parse_bulk_string_args, parse_int_arg, send_arity_error, send_error, send_resp,
+            send_wrongtype, wrongtype_resp, Command, CommandContext,
+        },
+        streams::{entry_resp, id_resp, parse_range_bound},
+    },
+    expiry_manager::ExpiryManager,
+    memory::Memory,
+    resp::Resp,
+    types::stream::{ConsumerGroup, Stream, StreamId},
+};
+
+fn nogroup(key: &str, group: &str) -> Resp {
+    coded_error_resp(
+        "NOGROUP",
+        &format!("No such key '{}' or consumer group '{}'", key, group),
+    )
+}
+
+// Resolves a group position, where `$` stands for the stream's last ID
+fn parse_group_id(input: &str, stream: &Stream) -> Result<StreamId, Resp> {
+    if input == "$" {
+        Ok(stream.last_id)
+    } else {
+        StreamId::parse(input, 0).map_err(error_resp)
+    }
+}
+
+fn parse_ms(input: &str) -> Result<u64, Resp> {
+    parse_int_arg(input)
+        .map(|ms| ms.max(0) as u64)
+        .map_err(error_resp)
+}
+
+// Reads `ENTRIESREAD n`, the only option shared by XGROUP CREATE and SETID
+fn parse_entries_read(args: &[String]) -> Result<Option<u64>, Resp> {
+    match args {
+        [] => Ok(None),
+        [option, value] if option.eq_ignore_ascii_case("ENTRIESREAD") => {
+            match parse_int_arg(value).map_err(error_resp)? {
+                value if value < -1 => {
+                    Err(error_resp("value for ENTRIESREAD must be positive or -1"))
+                }
+                -1 => Ok(None),
+                value => Ok(Some(value as u64)),
+            }
+        }
+        _ => Err(error_resp(errors::SYNTAX)),
+    }
+}
+
+fn xgroup(args: &[String], memory: &mut Memory) -> Result<Resp, Resp> {
+    let subcommand = args[0].to_ascii_uppercase();
+    let arity = |ok: bool| {
+        if ok {
+            Ok(())
+        } else {
+            Err(error_resp(&format!(
+                "wrong number of arguments for 'xgroup|{}' command",
+                subcommand.to_lowercase()
+            )))
+        }
+    };
+
+    match subcommand.as_str() {
+        "CREATE" => {
+            arity(args.len() >= 4)?;
+            let (key, group) = (&args[1], &args[2]);
+            let mut options = &args[4..];
+            let mkstream = options
+                .first()
+                .is_some_and(|option| option.eq_ignore_ascii_case("MKSTREAM"));
+            if mkstream {
+                options = &options[1..];
+            }
+            let entries_read = parse_entries_read(options)?;
+
+            let stream = match memory.get_stream(key) {
+                Ok(Some(_)) => memory.get_stream_mut(key).ok().flatten().unwrap(),
+                Ok(None) if mkstream => memory.get_or_create_stream(key).ok().unwrap(),
+                Ok(None) => return Err(error_resp("The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.")),
+                Err(_) => return Err(wrongtype_resp()),
+            };
+            if stream.groups.contains_key(group) {
+                return Err(coded_error_resp(
+                    "BUSYGROUP",
+                    "Consumer Group name already exists",
+                ));
+            }
+
+            let id = parse_group_id(&args[3], stream)?;
+            let entries_read = entries_read.or_else(|| {
+                if id == stream.last_id {
+                    Some(stream.entries_added)
+                } else if id == StreamId::MIN {
+                    Some(0)
+                } else {
+                    None
+                }
+            });
+            stream
+                .groups
+                .insert(group.clone(), ConsumerGroup::new(id, entries_read));
+            Ok(Resp::SimpleString("OK".to_string()))
+        }
+        "SETID" => {
+            arity(args.len() >= 4)?;
+            let (key, group) = (&args[1], &args[2]);
+            let entries_read = parse_entries_read(&args[4..])?;
+            let stream = match memory.get_stream_mut(key) {
+                Ok(Some(stream)) => stream,
+                Ok(None) => return Err(nogroup(key, group)),
+                Err(_) => return Err(wrongtype_resp()),
+            };
+            let id = parse_group_id(&args[3], stream)?;
+            let group = stream
+                .groups
+                .get_mut(group)
+                .ok_or_else(|| nogroup(key, group))?;
+            group.last_delivered_id = id;
+            group.entries_read = entries_read;
+            Ok(Resp::SimpleString("OK".to_string()))
+        }
+        "DESTROY" => {
+            arity(args.len() == 3)?;
+            match memory.get_stream_mut(&args[1]) {
+                Ok(Some(stream)) => Ok(Resp::Integer(
+                    stream.groups.remove(&args[2]).is_some() as i64
+                )),
+                Ok(None) => Err(nogroup(&args[1], &args[2])),
+                Err(_) => Err(wrongtype_resp()),
+            }
+        }
+        "CREATECONSUMER" | "DELCONSUMER" => {
+            arity(args.len() == 4)?;
+            let (key, group_name, consumer) = (&args[1], &args[2], &args[3]);
+            let group = match memory.get_stream_mut(key) {
+                Ok(Some(stream)) => stream
+                    .groups
+                    .get_mut(group_name)
+                    .ok_or_else(|| nogroup(key, group_name))?,
+                Ok(None) => return Err(nogroup(key, group_name)),
+                Err(_) => return Err(wrongtype_resp()),
+            };
+
+            if subcommand == "CREATECONSUMER" {
+                let created = !group.consumers.contains_key(consumer);
+                group.consumer(consumer, ExpiryManager::get_now_ms());
+                Ok(Resp::Integer(created as i64))
+            } else {
+                let pending = group.delete_consumer(consumer).unwrap_or(0);
+                Ok(Resp::Integer(pending as i64))
+            }
+        }
+        _ => Err(error_resp(&format!(
+            "unknown subcommand '{}'. Try XGROUP HELP.",
+            args[0]
+        ))),
+    }
+}
+
+pub struct XgroupCommand;
+
+impl Command for XgroupCommand {
+    fn name(&self) -> &'static str {
+        "XGROUP"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        if let Some(key) = args.get(1) {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        match xgroup(&args, &mut memory) {
+            Ok(reply) | Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
+
+pub struct ReadGroupArgs {
+    pub group: String,
+    pub consumer: String,
+    pub count: Option<usize>,
+    pub noack: bool,
+    // Some when BLOCK was given, holding the timeout (None blocks forever)
+    pub block: Option<Option<Duration>>,
+    pub keys: Vec<String>,
+    pub ids: Vec<String>,
+}
+
+impl ReadGroupArgs {
+    // Parses `GROUP group consumer [COUNT count] [BLOCK ms] [NOACK] STREAMS key ... id ...`
+    pub fn parse(args: &[String]) -> Result<Self, &'static str> {
+        if args.len() < 3 || !args[0].eq_ignore_ascii_case("GROUP") {
+            return Err("Missing GROUP option for XREADGROUP");
+        }
+        let mut parsed = ReadGroupArgs {
+            group: args[1].clone(),
+            consumer: args[2].clone(),
+            count: None,
+            noack: false,
+            block: None,
+            keys: Vec::new(),
+            ids: Vec::new(),
+        };
+
+        let mut index = 3;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "COUNT" if index + 1 < args.len() => {
+                    let count = parse_int_arg(&args[index + 1])?;
+                    parsed.count = Some(count).filter(|&c| c > 0).map(|c| c as usize);
+                    index += 1;
+                }
+                "BLOCK" if index + 1 < args.len() => {
+                    parsed.block = Some(parse_block_arg(&args[index + 1])?);
+                    index += 1;
+                }
+                "NOACK" => parsed.noack = true,
+                "STREAMS" => {
+                    let streams = &args[index + 1..];
+                    if streams.is_empty() || !streams.len().is_multiple_of(2) {
+                        return Err("Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified.");
+                    }
+                    let (keys, ids) = streams.split_at(streams.len() / 2);
+                    parsed.keys = keys.to_vec();
+                    parsed.ids = ids.to_vec();
+                    return Ok(parsed);
+                }
+                _ => return Err(errors::SYNTAX),
+            }
+            index += 1;
+        }
+
+        Err(errors::SYNTAX)
+    }
+}
+
+// Serves one stream of an XREADGROUP call. Returns None when `>` finds nothing new;
+// reads of the consumer's pending history always produce a reply.
+pub fn read_group_stream(
+    memory: &mut Memory,
+    key: &str,
+    id: &str,
+    args: &ReadGroupArgs,
+) -> Result<Option<Resp>, Resp> {
+    let missing = || {
+        coded_error_resp(
+            "NOGROUP",
+            &format!(
+                "No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                key, args.group
+            ),
+        )
+    };
+    let stream = match memory.get_stream_mut(key) {
+        Ok(Some(stream)) => stream,
+        Ok(None) => return Err(missing()),
+        Err(_) => return Err(wrongtype_resp()),
+    };
+    let Some(last_delivered_id) = stream.groups.get(&args.group).map(|g| g.last_delivered_id)
+    else {
+        return Err(missing());
+    };
+    let now = ExpiryManager::get_now_ms();
+
+    if id == ">" {
+        let entries: Vec<_> = match last_delivered_id.next() {
+            Some(start) => stream
+                .range(start, StreamId::MAX, args.count, false)
+                .into_iter()
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let group = stream.groups.get_mut(&args.group).unwrap();
+        let consumer = group.consumer(&args.consumer, now);
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        consumer.active_time = Some(now);
+
+        for entry in &entries {
+            group.last_delivered_id = entry.id;
+            if !args.noack {
+                group.assign(entry.id, &args.consumer, now, 1);
+            }
+        }
+        group.entries_read = group.entries_read.map(|read| read + entries.len() as u64);
+
+        let entries = entries.iter().map(entry_resp).collect();
+        return Ok(Some(Resp::Array(vec![
+            Resp::BulkString(Some(key.into())),
+            Resp::Array(entries),
+        ])));
+    }
+
+    // Replaying history: entries already delivered to this consumer after `id`
+    let start = StreamId::parse(id, 0)
+        .map_err(error_resp)?
+        .next()
+        .unwrap_or(StreamId::MAX);
+    let group = stream.groups.get_mut(&args.group).unwrap();
+    let ids: Vec<StreamId> = group
+        .consumer(&args.consumer, now)
+        .pending
+        .range(start..)
+        .take(args.count.unwrap_or(usize::MAX))
+        .copied()
+        .collect();
+
+    let entries = ids
+        .into_iter()
+        .map(|id| match stream.get(id) {
+            Some(entry) => entry_resp(entry),
+            None => Resp::Array(vec![id_resp(id), Resp::BulkString(None)]),
+        })
+        .collect();
+    Ok(Some(Resp::Array(vec![
+        Resp::BulkString(Some(key.into())),
+        Resp::Array(entries),
+    ])))
+}
+
+// Runs XREADGROUP over every requested stream; None if nothing was delivered
+pub fn read_group(memory: &mut Memory, args: &ReadGroupArgs) -> Result<Option<Resp>, Resp> {
+    let mut replies = Vec::new();
+    for (key, id) in args.keys.iter().zip(&args.ids) {
+        if let Some(reply) = read_group_stream(memory, key, id, args)? {
+            replies.push(reply);
+        }
+    }
+    Ok((!replies.is_empty()).then_some(Resp::Array(replies)))
+}
+
+pub struct XreadgroupCommand;
+
+impl Command for XreadgroupCommand {
+    fn name(&self) -> &'static str {
+        "XREADGROUP"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 6 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match ReadGroupArgs::parse(&args) {
+            Ok(args) => args,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let timeout = {
+            let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+            let mut memory = ctx.state.memory.lock().unwrap();
+            for key in &args.keys {
+                expire_if_needed(&mut memory, &mut expiry_manager, key);
+            }
+
+            match (read_group(&mut memory, &args), args.block) {
+                (Ok(None), Some(timeout)) => timeout,
+                (Ok(reply), _) => {
+                    return send_resp(&mut ctx.stream, reply.unwrap_or(Resp::BulkString(None)))
+                }
+                (Err(reply), _) => return send_resp(&mut ctx.stream, reply),
+            }
+        };
+
+        // Errors end the wait too, e.g. when the group is destroyed under a parked client
+        let reply = park_on_keys(ctx, &args.keys, timeout, |memory, _, _| {
+            read_group(memory, &args).unwrap_or_else(Some)
+        });
+        send_resp(&mut ctx.stream, reply.unwrap_or(Resp::BulkString(None)));
+    }
+}
+
+pub struct XackCommand;
+
+impl Command for XackCommand {
+    fn name(&self) -> &'static str {
+        "XACK"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let ids: Result<Vec<StreamId>, &'static str> =
+            args[2..].iter().map(|id| StreamId::parse(id, 0)).collect();
+        let ids = match ids {
+            Ok(ids) => ids,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_stream_mut(&args[0]) {
+            Ok(stream) => {
+                let acked = match stream.and_then(|stream| stream.groups.get_mut(&args[1])) {
+                    Some(group) => ids.into_iter().filter(|&id| group.ack(id)).count(),
+                    None => 0,
+                };
+                send_resp(&mut ctx.stream, Resp::Integer(acked as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// The summary form: total pending, smallest and greatest IDs, and a count per consumer
+fn pending_summary(group: &ConsumerGroup) -> Resp {
+    let (Some((&min, _)), Some((&max, _))) = (
+        group.pending.first_key_value(),
+        group.pending.last_key_value(),
+    ) else {
+        return Resp::Array(vec![
+            Resp::Integer(0),
+            Resp::BulkString(None),
+            Resp::BulkString(None),
+            Resp::BulkString(None),
+        ]);
+    };
+
+    let consumers = group
+        .consumers
+        .iter()
+        .filter(|(_, consumer)| !consumer.pending.is_empty())
+        .map(|(name, consumer)| {
+            Resp::Array(vec![
+                Resp::BulkString(Some(name.clone().into())),
+                Resp::BulkString(Some(consumer.pending.len().to_string().into())),
+            ])
+        })
+        .collect();
+    Resp::Array(vec![
+        Resp::Integer(group.pending.len() as i64),
+        id_resp(min),
+        id_resp(max),
+        Resp::Array(consumers),
+    ])
+}
+
+fn xpending(args: &[String], memory: &Memory) -> Result<Resp, Resp> {
+    let (key, group_name) = (&args[0], &args[1]);
+    let group = match memory.get_stream(key) {
+        Ok(Some(stream)) => stream
+            .groups
+            .get(group_name)
+            .ok_or_else(|| nogroup(key, group_name))?,
+        Ok(None) => return Err(nogroup(key, group_name)),
+        Err(_) => return Err(wrongtype_resp()),
+    };
+    if args.len() == 2 {
+        return Ok(pending_summary(group));
+    }
+
+    // Extended form: [IDLE min-idle-time] start end count [consumer]
+    let mut rest = &args[2..];
+    let mut min_idle = 0;
+    if rest[0].eq_ignore_ascii_case("IDLE") {
+        min_idle = parse_ms(rest.get(1).ok_or_else(|| error_resp(errors::SYNTAX))?)?;
+        rest = &rest[2..];
+    }
+    let (start, end, count, consumer) = match rest {
+        [start, end, count] => (start, end, count, None),
+        [start, end, count, consumer] => (start, end, count, Some(consumer)),
+        _ => return Err(error_resp(errors::SYNTAX)),
+    };
+    let start = parse_range_bound(start, true).map_err(error_resp)?;
+    let end = parse_range_bound(end, false).map_err(error_resp)?;
+    let count = parse_int_arg(count).map_err(error_resp)?.max(0) as usize;
+
+    let (Some(start), Some(end)) = (start, end) else {
+        return Ok(Resp::Array(vec![]));
+    };
+    if start > end {
+        return Ok(Resp::Array(vec![]));
+    }
+    let now = ExpiryManager::get_now_ms();
+    let entries = group
+        .pending
+        .range(start..=end)
+        .filter(|(_, entry)| consumer.is_none_or(|consumer| &entry.consumer == consumer))
+        .filter(|(_, entry)| now.saturating_sub(entry.delivery_time) >= min_idle)
+        .take(count)
+        .map(|(&id, entry)| {
+            Resp::Array(vec![
+                id_resp(id),
+                Resp::BulkString(Some(entry.consumer.clone().into())),
+                Resp::Integer(now.saturating_sub(entry.delivery_time) as i64),
+                Resp::Integer(entry.delivery_count as i64),
+            ])
+        })
+        .collect();
+    Ok(Resp::Array(entries))
+}
+
+pub struct XpendingCommand;
+
+impl Command for XpendingCommand {
+    fn name(&self) -> &'static str {
+        "XPENDING"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 || args.len() >= 5 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match xpending(&args, &memory) {
+            Ok(reply) | Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
+
+struct ClaimOptions {
+    // Absolute delivery time to record, derived from IDLE or TIME
+    delivery_time: Option<u64>,
+    retry_count: Option<u64>,
+    force: bool,
+    justid: bool,
+    last_id: Option<StreamId>,
+}
+
+// Moves a pending entry to `consumer`, returning false if it no longer qualifies.
+// Entries deleted from the stream are dropped from the PEL instead.
+fn claim(
+    stream: &mut Stream,
+    group_name: &str,
+    id: StreamId,
+    consumer: &str,
+    min_idle: u64,
+    options: &ClaimOptions,
+    now: u64,
+) -> bool {
+    let exists = stream.get(id).is_some();
+    let group = stream.groups.get_mut(group_name).unwrap();
+
+    let previous_count = match group.pending.get(&id) {
+        Some(_) if !exists => {
+            group.ack(id);
+            return false;
+        }
+        Some(entry) if now.saturating_sub(entry.delivery_time) < min_idle => return false,
+        Some(entry) => entry.delivery_count,
+        None if options.force && exists => 0,
+        None => return false,
+    };
+
+    let delivery_count = options.retry_count.unwrap_or(if options.justid {
+        previous_count
+    } else {
+        previous_count + 1
+    });
+    group.consumer(consumer, now).active_time = Some(now);
+    group.assign(
+        id,
+        consumer,
+        options.delivery_time.unwrap_or(now),
+        delivery_count,
+    );
+    true
+}
+
+fn claimed_resp(stream: &Stream, ids: Vec<StreamId>, justid: bool) -> Resp {
+    if justid {
+        Resp::Array(ids.into_iter().map(id_resp).collect())
+    } else {
+        Resp::Array(
+            ids.into_iter()
+                .filter_map(|id| stream.get(id))
+                .map(entry_resp)
+                .collect(),
+        )
+    }
+}
+
+fn group_stream<'a>(
+    memory: &'a mut Memory,
+    key: &str,
+    group: &str,
+) -> Result<&'a mut Stream, Resp> {
+    match memory.get_stream_mut(key) {
+        Ok(Some(stream)) if stream.groups.contains_key(group) => Ok(stream),
+        Ok(_) => Err(nogroup(key, group)),
+        Err(_) => Err(wrongtype_resp()),
+    }
+}
+
+fn xclaim(args: &[String], memory: &mut Memory) -> Result<Resp, Resp> {
+    let (key, group, consumer) = (&args[0], &args[1], &args[2]);
+    let min_idle = parse_ms(&args[3])?;
+    let now = ExpiryManager::get_now_ms();
+
+    // IDs run until the first argument that isn't one; options follow
+    let mut ids = Vec::new();
+    let mut index = 4;
+    while let Some(Ok(id)) = args.get(index).map(|id| StreamId::parse(id, 0)) {
+        ids.push(id);
+        index += 1;
+    }
+    if ids.is_empty() {
+        return Err(error_resp(crate::types::stream::INVALID_ID));
+    }
+
+    let mut options = ClaimOptions {
+        delivery_time: None,
+        retry_count: None,
+        force: false,
+        justid: false,
+        last_id: None,
+    };
+    while index < args.len() {
+        let value = args.get(index + 1);
+        match args[index].to_ascii_uppercase().as_str() {
+            "FORCE" => options.force = true,
+            "JUSTID" => options.justid = true,
+            "IDLE" if value.is_some() => {
+                options.delivery_time = Some(now.saturating_sub(parse_ms(value.unwrap())?));
+                index += 1;
+            }
+            "TIME" if value.is_some() => {
+                options.delivery_time = Some(parse_ms(value.unwrap())?);
+                index += 1;
+            }
+            "RETRYCOUNT" if value.is_some() => {
+                options.retry_count = Some(parse_ms(value.unwrap())?);
+                index += 1;
+            }
+            "LASTID" if value.is_some() => {
+                options.last_id = Some(StreamId::parse(value.unwrap(), 0).map_err(error_resp)?);
+                index += 1;
+            }
+            _ => {
+                return Err(error_resp(&format!(
+                    "Unrecognized XCLAIM option '{}'",
+                    args[index]
+                )))
+            }
+        }
+        index += 1;
+    }
+
+    let stream = group_stream(memory, key, group)?;
+    if let Some(last_id) = options.last_id {
+        let group = stream.groups.get_mut(group).unwrap();
+        group.last_delivered_id = group.last_delivered_id.max(last_id);
+    }
+
+    let claimed: Vec<StreamId> = ids
+        .into_iter()
+        .filter(|&id| claim(stream, group, id, consumer, min_idle, &options, now))
+        .collect();
+    Ok(claimed_resp(stream, claimed, options.justid))
+}
+
+pub struct XclaimCommand;
+
+impl Command for XclaimCommand {
+    fn name(&self) -> &'static str {
+        "XCLAIM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 5 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match xclaim(&args, &mut memory) {
+            Ok(reply) | Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
+
+fn xautoclaim(args: &[String], memory: &mut Memory) -> Result<Resp, Resp> {
+    let (key, group, consumer) = (&args[0], &args[1], &args[2]);
+    let min_idle = parse_ms(&args[3])?;
+    let start = parse_range_bound(&args[4], true)
+        .map_err(error_resp)?
+        .unwrap_or(StreamId::MAX);
+
+    let mut count = 100;
+    let mut justid = false;
+    let mut index = 5;
+    while index < args.len() {
+        match args[index].to_ascii_uppercase().as_str() {
+            "COUNT" if index + 1 < args.len() => {
+                count = match parse_int_arg(&args[index + 1]) {
+                    Ok(count) if count > 0 => count as usize,
+                    _ => return Err(error_resp("COUNT must be > 0")),
+                };
+                index += 1;
+            }
+            "JUSTID" => justid = true,
+            _ => return Err(error_resp(errors::SYNTAX)),
+        }
+        index += 1;
+    }
+
+    let stream = group_stream(memory, key, group)?;
+    let now = ExpiryManager::get_now_ms();
+    let options = ClaimOptions {
+        delivery_time: None,
+        retry_count: None,
+        force: false,
+        justid,
+        last_id: None,
+    };
+
+    // Bound the scan like Redis does, so a PEL full of young entries can't stall the server
+    let attempts = count.saturating_mul(10);
+    let candidates: Vec<StreamId> = stream.groups[group]
+        .pending
+        .range(start..)
+        .take(attempts + 1)
+        .map(|(&id, _)| id)
+        .collect();
+
+    let mut cursor = StreamId::MIN;
+    let mut claimed = Vec::new();
+    let mut deleted = Vec::new();
+    for (examined, id) in candidates.into_iter().enumerate() {
+        if examined == attempts || claimed.len() == count {
+            cursor = id;
+            break;
+        }
+        if stream.get(id).is_none() {
+            stream.groups.get_mut(group).unwrap().ack(id);
+            deleted.push(id);
+        } else if claim(stream, group, id, consumer, min_idle, &options, now) {
+            claimed.push(id);
+        }
+    }
+
+    Ok(Resp::Array(vec![
+        id_resp(cursor),
+        claimed_resp(stream, claimed, justid),
+        Resp::Array(deleted.into_iter().map(id_resp).collect()),
+    ]))
+}
+
+pub struct XautoclaimCommand;
+
+impl Command for XautoclaimCommand {
+    fn name(&self) -> &'static str {
+        "XAUTOCLAIM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 5 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match xautoclaim(&args, &mut memory) {
+            Ok(reply) | Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
@@ -0,0 +1,715 @@
+use crate::{
+    commands::command::{
+        coded_error_resp, errors, expire_if_needed, park_on_keys, parse_block_arg,
+        parse_bulk_string_args, parse_bytes_args, parse_int_arg, send_arity_error, send_error,
+        send_resp, send_wrongtype, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    memory::Memory,
+    resp::Resp,
+    types::stream::{ConsumerGroup, Stream, StreamEntry, StreamId, TrimStrategy},
+};
+
+pub fn entry_resp(entry: &StreamEntry) -> Resp {
+    let mut fields = Vec::with_capacity(entry.fields.len() * 2);
+    for (field, value) in &entry.fields {
+        fields.push(Resp::BulkString(Some(field.clone())));
+        fields.push(Resp::BulkString(Some(value.clone())));
+    }
+    Resp::Array(vec![
+        Resp::BulkString(Some(entry.id.to_string().into())),
+        Resp::Array(fields),
+    ])
+}
+
+pub fn entries_resp(entries: Vec<&StreamEntry>) -> Resp {
+    Resp::Array(entries.into_iter().map(entry_resp).collect())
+}
+
+pub fn id_resp(id: StreamId) -> Resp {
+    Resp::BulkString(Some(id.to_string().into()))
+}
+
+pub struct TrimArgs {
+    pub strategy: TrimStrategy,
+    pub approximate: bool,
+    pub limit: Option<usize>,
+}
+
+// Parses `MAXLEN|MINID [=|~] threshold [LIMIT count]` starting at `args[*index]`
+fn parse_trim(args: &[String], index: &mut usize) -> Result<TrimArgs, &'static str> {
+    let is_maxlen = args[*index].eq_ignore_ascii_case("MAXLEN");
+    *index += 1;
+
+    let approximate = match args.get(*index).map(String::as_str) {
+        Some("~") => true,
+        Some("=") => false,
+        _ => {
+            *index -= 1;
+            false
+        }
+    };
+    *index += 1;
+
+    let threshold = args.get(*index).ok_or(errors::SYNTAX)?;
+    let strategy = if is_maxlen {
+        match parse_int_arg(threshold)? {
+            max_len if max_len < 0 => return Err("The MAXLEN argument must be >= 0."),
+            max_len => TrimStrategy::MaxLen(max_len as usize),
+        }
+    } else {
+        TrimStrategy::MinId(StreamId::parse(threshold, 0)?)
+    };
+    *index += 1;
+
+    let mut limit = None;
+    if args
+        .get(*index)
+        .is_some_and(|arg| arg.eq_ignore_ascii_case("LIMIT"))
+    {
+        let count = args.get(*index + 1).ok_or(errors::SYNTAX)?;
+        if !approximate {
+            return Err("syntax error, LIMIT cannot be used without the special ~ option");
+        }
+        match parse_int_arg(count)? {
+            count if count < 0 => return Err("The LIMIT argument must be >= 0."),
+            0 => {}
+            count => limit = Some(count as usize),
+        }
+        *index += 2;
+    } else if approximate {
+        // Mirror Redis' default effort bound for approximate trimming
+        limit = Some(100 * 100);
+    }
+
+    Ok(TrimArgs {
+        strategy,
+        approximate,
+        limit,
+    })
+}
+
+pub struct XaddCommand;
+
+impl Command for XaddCommand {
+    fn name(&self) -> &'static str {
+        "XADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let mut nomkstream = false;
+        let mut trim = None;
+        let mut index = 1;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "NOMKSTREAM" => {
+                    nomkstream = true;
+                    index += 1;
+                }
+                "MAXLEN" | "MINID" => match parse_trim(&args, &mut index) {
+                    Ok(parsed) => trim = Some(parsed),
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                },
+                _ => break,
+            }
+        }
+
+        let fields = values.get(index + 1..).unwrap_or_default();
+        if fields.is_empty() || fields.len() % 2 != 0 {
+            return send_arity_error(&mut ctx.stream, self.name());
+        }
+        let requested_id = &args[index];
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        let created = match memory.get_stream(key) {
+            Ok(None) if nomkstream => return send_resp(&mut ctx.stream, Resp::BulkString(None)),
+            Ok(stream) => stream.is_none(),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let Ok(stream) = memory.get_or_create_stream(key) else {
+            return send_wrongtype(&mut ctx.stream);
+        };
+
+        let id = match stream.next_id(requested_id, ExpiryManager::get_now_ms()) {
+            Ok(id) => id,
+            Err(err) => {
+                // Don't leave behind a stream created just for this failed call
+                if created {
+                    memory.delete(key);
+                }
+                return send_error(&mut ctx.stream, err);
+            }
+        };
+
+        let pairs = fields
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+        stream.append(id, pairs);
+        if let Some(trim) = trim {
+            stream.trim(trim.strategy, trim.approximate, trim.limit);
+        }
+        ctx.state.blocking.signal(key);
+
+        send_resp(&mut ctx.stream, id_resp(id));
+    }
+}
+
+pub struct XtrimCommand;
+
+impl Command for XtrimCommand {
+    fn name(&self) -> &'static str {
+        "XTRIM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut index = 1;
+        let trim = match args[1].to_ascii_uppercase().as_str() {
+            "MAXLEN" | "MINID" => match parse_trim(&args, &mut index) {
+                Ok(trim) if index == args.len() => trim,
+                Ok(_) => return send_error(&mut ctx.stream, errors::SYNTAX),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            },
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_stream_mut(&args[0]) {
+            Ok(stream) => {
+                let removed = stream.map_or(0, |stream| {
+                    stream.trim(trim.strategy, trim.approximate, trim.limit)
+                });
+                send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// Resolves an XRANGE bound: `-`, `+`, `ms`, `ms-seq`, or an exclusive `(ms-seq`
+pub fn parse_range_bound(input: &str, is_start: bool) -> Result<Option<StreamId>, &'static str> {
+    match input {
+        "-" => return Ok(Some(StreamId::MIN)),
+        "+" => return Ok(Some(StreamId::MAX)),
+        _ => {}
+    }
+
+    let default_seq = if is_start { 0 } else { u64::MAX };
+    match input.strip_prefix('(') {
+        // An exclusive bound past either end of the ID space matches nothing
+        Some(id) => {
+            let id = StreamId::parse(id, default_seq)?;
+            Ok(if is_start { id.next() } else { id.prev() })
+        }
+        None => StreamId::parse(input, default_seq).map(Some),
+    }
+}
+
+pub struct XrangeCommand {
+    pub reverse: bool,
+}
+
+impl Command for XrangeCommand {
+    fn name(&self) -> &'static str {
+        if self.reverse {
+            "XREVRANGE"
+        } else {
+            "XRANGE"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 || args.len() == 5 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        // XREVRANGE takes the bounds the other way round
+        let (start, end) = if self.reverse {
+            (&args[2], &args[1])
+        } else {
+            (&args[1], &args[2])
+        };
+        let bounds = parse_range_bound(start, true)
+            .and_then(|start| Ok((start, parse_range_bound(end, false)?)));
+        let (start, end) = match bounds {
+            Ok(bounds) => bounds,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let count = match args.get(3..) {
+            Some([option, count]) if option.eq_ignore_ascii_case("COUNT") => {
+                match parse_int_arg(count) {
+                    Ok(count) => Some(count.max(0) as usize),
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                }
+            }
+            Some([]) | None => None,
+            Some(_) => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let stream = match memory.get_stream(&args[0]) {
+            Ok(stream) => stream,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let entries = match (stream, start, end) {
+            (Some(stream), Some(start), Some(end)) => stream.range(start, end, count, self.reverse),
+            _ => Vec::new(),
+        };
+        send_resp(&mut ctx.stream, entries_resp(entries));
+    }
+}
+
+// Entries after each stream's position, as XREAD replies; None if there are none yet
+fn read_streams(
+    memory: &Memory,
+    positions: &[(String, Option<StreamId>)],
+    count: Option<usize>,
+) -> Option<Resp> {
+    let replies: Vec<Resp> = positions
+        .iter()
+        .filter_map(|(key, start)| {
+            let stream = memory.get_stream(key).ok().flatten()?;
+            let entries = stream.range((*start)?, StreamId::MAX, count, false);
+            if entries.is_empty() {
+                return None;
+            }
+            Some(Resp::Array(vec![
+                Resp::BulkString(Some(key.clone().into())),
+                entries_resp(entries),
+            ]))
+        })
+        .collect();
+    (!replies.is_empty()).then_some(Resp::Array(replies))
+}
+
+pub struct XreadCommand;
+
+impl Command for XreadCommand {
+    fn name(&self) -> &'static str {
+        "XREAD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut count = None;
+        let mut block = None;
+        let mut index = 0;
+        let streams = loop {
+            let value = args.get(index + 1);
+            match args
+                .get(index)
+                .map(|arg| arg.to_ascii_uppercase())
+                .as_deref()
+            {
+                Some("COUNT") if value.is_some() => match parse_int_arg(value.unwrap()) {
+                    Ok(value) => count = Some(value).filter(|&c| c > 0).map(|c| c as usize),
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                },
+                Some("BLOCK") if value.is_some() => match parse_block_arg(value.unwrap()) {
+                    Ok(timeout) => block = Some(timeout),
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                },
+                Some("STREAMS") => break &args[index + 1..],
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 2;
+        };
+        if streams.is_empty() || !streams.len().is_multiple_of(2) {
+            return send_error(&mut ctx.stream, "Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.");
+        }
+        let (keys, ids) = streams.split_at(streams.len() / 2);
+
+        // Resolve every ID to the first position to read from, so `$` is fixed before parking
+        let positions = {
+            let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+            let mut memory = ctx.state.memory.lock().unwrap();
+            let mut positions = Vec::with_capacity(keys.len());
+            for (key, id) in keys.iter().zip(ids) {
+                expire_if_needed(&mut memory, &mut expiry_manager, key);
+                let stream = match memory.get_stream(key) {
+                    Ok(stream) => stream,
+                    Err(_) => return send_wrongtype(&mut ctx.stream),
+                };
+                let start = match id.as_str() {
+                    "$" => stream.map_or(StreamId::MIN, |stream| stream.last_id).next(),
+                    // `+` reads the last entry, or whatever comes next if there is none
+                    "+" => match stream.and_then(|stream| stream.last_entry()) {
+                        Some(entry) => Some(entry.id),
+                        None => stream.map_or(StreamId::MIN, |stream| stream.last_id).next(),
+                    },
+                    id => match StreamId::parse(id, 0) {
+                        Ok(id) => id.next(),
+                        Err(err) => return send_error(&mut ctx.stream, err),
+                    },
+                };
+                positions.push((key.clone(), start));
+            }
+
+            if block.is_none() {
+                let reply = read_streams(&memory, &positions, count);
+                return send_resp(&mut ctx.stream, reply.unwrap_or(Resp::BulkString(None)));
+            }
+            positions
+        };
+
+        // Readers don't consume anything, so every parked client sees the new entries
+        let reply = park_on_keys(ctx, keys, block.unwrap(), |memory, _, _| {
+            read_streams(memory, &positions, count)
+        });
+        send_resp(&mut ctx.stream, reply.unwrap_or(Resp::BulkString(None)));
+    }
+}
+
+pub struct XlenCommand;
+
+impl Command for XlenCommand {
+    fn name(&self) -> &'static str {
+        "XLEN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_stream(&args[0]) {
+            Ok(stream) => {
+                let len = stream.map_or(0, |stream| stream.len());
+                send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct XdelCommand;
+
+impl Command for XdelCommand {
+    fn name(&self) -> &'static str {
+        "XDEL"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let ids: Result<Vec<StreamId>, &'static str> =
+            args[1..].iter().map(|id| StreamId::parse(id, 0)).collect();
+        let ids = match ids {
+            Ok(ids) => ids,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_stream_mut(&args[0]) {
+            Ok(stream) => {
+                let deleted = stream.map_or(0, |stream| {
+                    ids.into_iter().filter(|&id| stream.delete(id)).count()
+                });
+                send_resp(&mut ctx.stream, Resp::Integer(deleted as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// Group details for XINFO STREAM FULL, with PELs capped at `count` entries
+fn full_groups_info(stream: &Stream, count: Option<usize>) -> Resp {
+    let bulk = |value: &str| Resp::BulkString(Some(value.into()));
+    let limit = count.unwrap_or(usize::MAX);
+    let groups = stream
+        .groups
+        .iter()
+        .map(|(name, group)| {
+            let pending = group
+                .pending
+                .iter()
+                .take(limit)
+                .map(|(&id, entry)| {
+                    Resp::Array(vec![
+                        id_resp(id),
+                        bulk(&entry.consumer),
+                        Resp::Integer(entry.delivery_time as i64),
+                        Resp::Integer(entry.delivery_count as i64),
+                    ])
+                })
+                .collect();
+            let consumers = group
+                .consumers
+                .iter()
+                .map(|(consumer_name, consumer)| {
+                    let pending = consumer
+                        .pending
+                        .iter()
+                        .take(limit)
+                        .map(|&id| {
+                            let entry = &group.pending[&id];
+                            Resp::Array(vec![
+                                id_resp(id),
+                                Resp::Integer(entry.delivery_time as i64),
+                                Resp::Integer(entry.delivery_count as i64),
+                            ])
+                        })
+                        .collect();
+                    Resp::Array(vec![
+                        bulk("name"),
+                        bulk(consumer_name),
+                        bulk("seen-time"),
+                        Resp::Integer(consumer.seen_time as i64),
+                        bulk("active-time"),
+                        Resp::Integer(consumer.active_time.map_or(-1, |time| time as i64)),
+                        bulk("pel-count"),
+                        Resp::Integer(consumer.pending.len() as i64),
+                        bulk("pending"),
+                        Resp::Array(pending),
+                    ])
+                })
+                .collect();
+
+            Resp::Array(vec![
+                bulk("name"),
+                bulk(name),
+                bulk("last-delivered-id"),
+                id_resp(group.last_delivered_id),
+                bulk("entries-read"),
+                group
+                    .entries_read
+                    .map_or(Resp::BulkString(None), |read| Resp::Integer(read as i64)),
+                bulk("lag"),
+                Resp::Integer(stream.lag(group) as i64),
+                bulk("pel-count"),
+                Resp::Integer(group.pending.len() as i64),
+                bulk("pending"),
+                Resp::Array(pending),
+                bulk("consumers"),
+                Resp::Array(consumers),
+            ])
+        })
+        .collect();
+    Resp::Array(groups)
+}
+
+fn stream_info(stream: &Stream, full: Option<usize>) -> Resp {
+    let bulk = |value: &str| Resp::BulkString(Some(value.into()));
+    let first_id = stream.first_entry().map_or(StreamId::MIN, |entry| entry.id);
+
+    let mut info = vec![
+        bulk("length"),
+        Resp::Integer(stream.len() as i64),
+        bulk("radix-tree-keys"),
+        Resp::Integer(stream.node_count() as i64),
+        bulk("radix-tree-nodes"),
+        Resp::Integer(stream.node_count() as i64 + 1),
+        bulk("last-generated-id"),
+        id_resp(stream.last_id),
+        bulk("max-deleted-entry-id"),
+        id_resp(stream.max_deleted_id),
+        bulk("entries-added"),
+        Resp::Integer(stream.entries_added as i64),
+        bulk("recorded-first-entry-id"),
+        id_resp(first_id),
+    ];
+
+    match full {
+        Some(count) => {
+            let count = Some(count).filter(|&count| count > 0);
+            let entries = stream.range(StreamId::MIN, StreamId::MAX, count, false);
+            info.extend([
+                bulk("entries"),
+                entries_resp(entries),
+                bulk("groups"),
+                full_groups_info(stream, count),
+            ]);
+        }
+        None => {
+            let entry =
+                |entry: Option<&StreamEntry>| entry.map_or(Resp::BulkString(None), entry_resp);
+            info.extend([
+                bulk("groups"),
+                Resp::Integer(stream.groups.len() as i64),
+                bulk("first-entry"),
+                entry(stream.first_entry()),
+                bulk("last-entry"),
+                entry(stream.last_entry()),
+            ]);
+        }
+    }
+
+    Resp::Array(info)
+}
+
+// Summary of each consumer group, as listed by XINFO GROUPS
+fn groups_info(stream: &Stream) -> Resp {
+    let bulk = |value: &str| Resp::BulkString(Some(value.into()));
+    let groups = stream
+        .groups
+        .iter()
+        .map(|(name, group)| {
+            Resp::Array(vec![
+                bulk("name"),
+                bulk(name),
+                bulk("consumers"),
+                Resp::Integer(group.consumers.len() as i64),
+                bulk("pending"),
+                Resp::Integer(group.pending.len() as i64),
+                bulk("last-delivered-id"),
+                id_resp(group.last_delivered_id),
+                bulk("entries-read"),
+                group
+                    .entries_read
+                    .map_or(Resp::BulkString(None), |read| Resp::Integer(read as i64)),
+                bulk("lag"),
+                Resp::Integer(stream.lag(group) as i64),
+            ])
+        })
+        .collect();
+    Resp::Array(groups)
+}
+
+fn consumers_info(group: &ConsumerGroup, now: u64) -> Resp {
+    let bulk = |value: &str| Resp::BulkString(Some(value.into()));
+    let consumers = group
+        .consumers
+        .iter()
+        .map(|(name, consumer)| {
+            let inactive = consumer
+                .active_time
+                .map_or(-1, |active| now.saturating_sub(active) as i64);
+            Resp::Array(vec![
+                bulk("name"),
+                bulk(name),
+                bulk("pending"),
+                Resp::Integer(consumer.pending.len() as i64),
+                bulk("idle"),
+                Resp::Integer(now.saturating_sub(consumer.seen_time) as i64),
+                bulk("inactive"),
+                Resp::Integer(inactive),
+            ])
+        })
+        .collect();
+    Resp::Array(consumers)
+}
+
+enum InfoRequest {
+    Stream(Option<usize>),
+    Groups,
+    Consumers(String),
+}
+
+pub struct XinfoCommand;
+
+impl Command for XinfoCommand {
+    fn name(&self) -> &'static str {
+        "XINFO"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let subcommand = args[0].to_ascii_uppercase();
+        let key = &args[1];
+
+        let request = match (subcommand.as_str(), &args[2..]) {
+            ("STREAM", []) => InfoRequest::Stream(None),
+            ("STREAM", [full]) if full.eq_ignore_ascii_case("FULL") => {
+                InfoRequest::Stream(Some(10))
+            }
+            ("STREAM", [full, option, count])
+                if full.eq_ignore_ascii_case("FULL") && option.eq_ignore_ascii_case("COUNT") =>
+            {
+                match parse_int_arg(count) {
+                    Ok(count) => InfoRequest::Stream(Some(count.max(0) as usize)),
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                }
+            }
+            ("STREAM", _) => return send_error(&mut ctx.stream, errors::SYNTAX),
+            ("GROUPS", []) => InfoRequest::Groups,
+            ("CONSUMERS", [group]) => InfoRequest::Consumers(group.clone()),
+            ("GROUPS" | "CONSUMERS", _) => {
+                return send_error(
+                    &mut ctx.stream,
+                    &format!(
+                        "wrong number of arguments for 'xinfo|{}' command",
+                        subcommand.to_lowercase()
+                    ),
+                )
+            }
+            _ => {
+                return send_error(
+                    &mut ctx.stream,
+                    &format!("unknown subcommand '{}'. Try XINFO HELP.", args[0]),
+                )
+            }
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        let stream = match memory.get_stream(key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return send_error(&mut ctx.stream, "no such key"),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let reply = match request {
+            InfoRequest::Stream(full) => stream_info(stream, full),
+            InfoRequest::Groups => groups_info(stream),
+            InfoRequest::Consumers(group) => match stream.groups.get(&group) {
+                Some(group) => consumers_info(group, ExpiryManager::get_now_ms()),
+                None => coded_error_resp(
+                    "NOGROUP",
+                    &format!("No such consumer group '{}' for key name '{}'", group, key),
+                ),
+            },
+        };
+        send_resp(&mut ctx.stream, reply);
+    }
+}
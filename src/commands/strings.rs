@@ -0,0 +1,358 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_bytes_args, parse_int_arg,
+        send_arity_error, send_error, send_resp, send_wrongtype, Command, CommandContext,
+    },
+    resp::Resp,
+};
+
+// Largest string SETRANGE may grow, matching Redis' default proto-max-bulk-len
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+// Largest LCS table Redis agrees to build, in cells
+const MAX_LCS_CELLS: usize = u32::MAX as usize / 4;
+
+fn bytes_resp(bytes: &[u8]) -> Resp {
+    Resp::BulkString(Some(bytes.to_vec()))
+}
+
+// APPEND key value, replying with the new length
+pub struct AppendCommand;
+
+impl Command for AppendCommand {
+    fn name(&self) -> &'static str {
+        "APPEND"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_or_create_bytes(&args[0]) {
+            Ok(bytes) => {
+                bytes.extend_from_slice(&values[1]);
+                send_resp(&mut ctx.stream, Resp::Integer(bytes.len() as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct StrlenCommand;
+
+impl Command for StrlenCommand {
+    fn name(&self) -> &'static str {
+        "STRLEN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_bytes(&args[0]) {
+            Ok(bytes) => {
+                let len = bytes.map_or(0, |bytes| bytes.len());
+                send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// GETRANGE key start end, and its old name SUBSTR. Offsets are inclusive and negative
+// ones count from the end.
+pub struct GetrangeCommand {
+    pub substr: bool,
+}
+
+impl Command for GetrangeCommand {
+    fn name(&self) -> &'static str {
+        if self.substr {
+            "SUBSTR"
+        } else {
+            "GETRANGE"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (start, end) = match (parse_int_arg(&args[1]), parse_int_arg(&args[2])) {
+            (Ok(start), Ok(end)) => (start, end),
+            (Err(err), _) | (_, Err(err)) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let bytes = match memory.get_bytes(&args[0]) {
+            Ok(bytes) => bytes.unwrap_or_default(),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let len = bytes.len() as i64;
+        if start < 0 && end < 0 && start > end {
+            return send_resp(&mut ctx.stream, bytes_resp(&[]));
+        }
+        let clamp = |offset: i64| {
+            if offset < 0 {
+                (len + offset).max(0)
+            } else {
+                offset
+            }
+        };
+        let (start, end) = (clamp(start), clamp(end).min(len - 1));
+        if len == 0 || start > end {
+            return send_resp(&mut ctx.stream, bytes_resp(&[]));
+        }
+        send_resp(
+            &mut ctx.stream,
+            bytes_resp(&bytes[start as usize..=end as usize]),
+        );
+    }
+}
+
+// SETRANGE key offset value, zero-padding the string when the offset is past its end
+pub struct SetrangeCommand;
+
+impl Command for SetrangeCommand {
+    fn name(&self) -> &'static str {
+        "SETRANGE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let values = match parse_bytes_args(args) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let offset = match parse_int_arg(&args[1]) {
+            Ok(offset) if offset >= 0 => offset as usize,
+            Ok(_) => return send_error(&mut ctx.stream, "offset is out of range"),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let value = &values[2];
+        if offset.saturating_add(value.len()) > MAX_STRING_LEN {
+            return send_error(
+                &mut ctx.stream,
+                "string exceeds maximum allowed size (proto-max-bulk-len)",
+            );
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        // An empty value changes nothing and never creates the key
+        if value.is_empty() {
+            return match memory.get_bytes(&args[0]) {
+                Ok(bytes) => {
+                    let len = bytes.map_or(0, |bytes| bytes.len());
+                    send_resp(&mut ctx.stream, Resp::Integer(len as i64))
+                }
+                Err(_) => send_wrongtype(&mut ctx.stream),
+            };
+        }
+        match memory.get_or_create_bytes(&args[0]) {
+            Ok(bytes) => {
+                if bytes.len() < offset + value.len() {
+                    bytes.resize(offset + value.len(), 0);
+                }
+                bytes[offset..offset + value.len()].copy_from_slice(value);
+                send_resp(&mut ctx.stream, Resp::Integer(bytes.len() as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// A matching pair of inclusive ranges in the two strings
+struct Match {
+    a: (usize, usize),
+    b: (usize, usize),
+}
+
+// Longest common subsequence of `a` and `b`, plus its matching ranges walked back from
+// the end of both strings, as Redis reports them. None when the table would be larger
+// than MAX_LCS_CELLS.
+fn lcs(a: &[u8], b: &[u8], min_match_len: usize) -> Option<(Vec<u8>, Vec<Match>)> {
+    let width = b.len() + 1;
+    let cells = (a.len() + 1)
+        .checked_mul(width)
+        .filter(|&cells| cells <= MAX_LCS_CELLS)?;
+    let mut table = vec![0u32; cells];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            table[i * width + j] = if a[i - 1] == b[j - 1] {
+                table[(i - 1) * width + j - 1] + 1
+            } else {
+                table[(i - 1) * width + j].max(table[i * width + j - 1])
+            };
+        }
+    }
+
+    let mut sequence = Vec::with_capacity(table[a.len() * width + b.len()] as usize);
+    let mut matches = Vec::new();
+    let mut current: Option<Match> = None;
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 && j > 0 {
+        let mut emit = false;
+        if a[i - 1] == b[j - 1] {
+            sequence.push(a[i - 1]);
+            match &mut current {
+                // Contiguous with the range so far, which grows backwards
+                Some(range) if range.a.0 == i && range.b.0 == j => {
+                    range.a.0 -= 1;
+                    range.b.0 -= 1;
+                }
+                Some(_) => emit = true,
+                None => {
+                    current = Some(Match {
+                        a: (i - 1, i - 1),
+                        b: (j - 1, j - 1),
+                    })
+                }
+            }
+            // Matching either string's first byte ends the walk
+            if current
+                .as_ref()
+                .is_some_and(|range| range.a.0 == 0 || range.b.0 == 0)
+            {
+                emit = true;
+            }
+            i -= 1;
+            j -= 1;
+        } else {
+            if table[(i - 1) * width + j] > table[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            emit = current.is_some();
+        }
+        if emit {
+            if let Some(range) = current.take() {
+                if range.a.1 - range.a.0 + 1 >= min_match_len {
+                    matches.push(range);
+                }
+            }
+        }
+    }
+    sequence.reverse();
+    Some((sequence, matches))
+}
+
+// LCS key1 key2 [LEN] [IDX] [MINMATCHLEN len] [WITHMATCHLEN]
+pub struct LcsCommand;
+
+impl Command for LcsCommand {
+    fn name(&self) -> &'static str {
+        "LCS"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let (mut len_only, mut idx, mut with_match_len) = (false, false, false);
+        let mut min_match_len = 0;
+        let mut index = 2;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "LEN" => len_only = true,
+                "IDX" => idx = true,
+                "WITHMATCHLEN" => with_match_len = true,
+                "MINMATCHLEN" if index + 1 < args.len() => {
+                    index += 1;
+                    min_match_len = match parse_int_arg(&args[index]) {
+                        Ok(len) => len.max(0) as usize,
+                        Err(err) => return send_error(&mut ctx.stream, err),
+                    };
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+        if len_only && idx {
+            return send_error(
+                &mut ctx.stream,
+                "If you want both the length and indexes, please just use IDX.",
+            );
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[1]);
+
+        let (a, b) = match (memory.get_bytes(&args[0]), memory.get_bytes(&args[1])) {
+            (Ok(a), Ok(b)) => (a.unwrap_or_default(), b.unwrap_or_default()),
+            _ => {
+                return send_error(
+                    &mut ctx.stream,
+                    "The specified keys must contain string values",
+                )
+            }
+        };
+        let Some((sequence, matches)) = lcs(&a, &b, min_match_len) else {
+            return send_error(&mut ctx.stream, "String too long for LCS");
+        };
+
+        if len_only {
+            return send_resp(&mut ctx.stream, Resp::Integer(sequence.len() as i64));
+        }
+        if !idx {
+            return send_resp(&mut ctx.stream, bytes_resp(&sequence));
+        }
+        let range = |(start, end): (usize, usize)| {
+            Resp::Array(vec![Resp::Integer(start as i64), Resp::Integer(end as i64)])
+        };
+        let matches = matches
+            .into_iter()
+            .map(|found| {
+                let mut entry = vec![range(found.a), range(found.b)];
+                if with_match_len {
+                    entry.push(Resp::Integer((found.a.1 - found.a.0 + 1) as i64));
+                }
+                Resp::Array(entry)
+            })
+            .collect();
+        send_resp(
+            &mut ctx.stream,
+            Resp::Array(vec![
+                Resp::BulkString(Some(b"matches".to_vec())),
+                Resp::Array(matches),
+                Resp::BulkString(Some(b"len".to_vec())),
+                Resp::Integer(sequence.len() as i64),
+            ]),
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_resp,
+        send_wrongtype, Command, CommandContext,
+    },
+    resp::Resp,
+    types::zset::format_score,
+};
+
+const DEFAULT_MAX: usize = 5;
+
+// FT.SUGADD key string score [INCR] [PAYLOAD payload], replying with the dictionary size
+pub struct FtSugaddCommand;
+
+impl Command for FtSugaddCommand {
+    fn name(&self) -> &'static str {
+        "FT.SUGADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let score = match args[2].parse::<f64>() {
+            Ok(score) if score.is_finite() => score,
+            _ => return send_error(&mut ctx.stream, "invalid score"),
+        };
+
+        let mut incr = false;
+        let mut payload = None;
+        let mut index = 3;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "INCR" => incr = true,
+                "PAYLOAD" if index + 1 < args.len() => {
+                    index += 1;
+                    payload = Some(args[index].clone());
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_or_create_suggestions(&args[0]) {
+            Ok(trie) => {
+                trie.add(&args[1], score, incr, payload);
+                send_resp(&mut ctx.stream, Resp::Integer(trie.len() as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// FT.SUGGET key prefix [FUZZY] [WITHSCORES] [WITHPAYLOADS] [MAX max]
+pub struct FtSuggetCommand;
+
+impl Command for FtSuggetCommand {
+    fn name(&self) -> &'static str {
+        "FT.SUGGET"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let (mut fuzzy, mut withscores, mut withpayloads) = (false, false, false);
+        let mut max = DEFAULT_MAX;
+        let mut index = 2;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "FUZZY" => fuzzy = true,
+                "WITHSCORES" => withscores = true,
+                "WITHPAYLOADS" => withpayloads = true,
+                "MAX" if index + 1 < args.len() => {
+                    index += 1;
+                    max = match args[index].parse::<usize>() {
+                        Ok(max) => max,
+                        Err(_) => return send_error(&mut ctx.stream, "Invalid MAX"),
+                    };
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let trie = match memory.get_suggestions(&args[0]) {
+            Ok(Some(trie)) => trie,
+            Ok(None) => return send_resp(&mut ctx.stream, Resp::Array(Vec::new())),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let mut replies = Vec::new();
+        for suggestion in trie.complete(&args[1], fuzzy).into_iter().take(max) {
+            replies.push(Resp::BulkString(Some(suggestion.string.clone().into())));
+            if withscores {
+                replies.push(Resp::BulkString(Some(
+                    format_score(suggestion.score).into(),
+                )));
+            }
+            if withpayloads {
+                replies.push(Resp::BulkString(
+                    suggestion.payload.clone().map(String::into_bytes),
+                ));
+            }
+        }
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
+
+pub struct FtSugdelCommand;
+
+impl Command for FtSugdelCommand {
+    fn name(&self) -> &'static str {
+        "FT.SUGDEL"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let (removed, empty) = match memory.get_suggestions_mut(&args[0]) {
+            Ok(Some(trie)) => (trie.remove(&args[1]), trie.len() == 0),
+            Ok(None) => (false, false),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        if empty {
+            memory.delete(&args[0]);
+            expiry_manager.remove_expiry(&args[0]);
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+    }
+}
+
+pub struct FtSuglenCommand;
+
+impl Command for FtSuglenCommand {
+    fn name(&self) -> &'static str {
+        "FT.SUGLEN"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_suggestions(&args[0]) {
+            Ok(trie) => send_resp(
+                &mut ctx.stream,
+                Resp::Integer(trie.map_or(0, |trie| trie.len()) as i64),
+            ),
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_resp,
+        send_wrongtype, Command, CommandContext,
+    },
+    memory::Value,
+    resp::Resp,
+    types::{
+        tdigest::{TDigest, DEFAULT_COMPRESSION},
+        zset::format_score,
+    },
+};
+
+const MISSING: &str = "T-Digest: key does not exist";
+
+fn parse_compression(input: &str) -> Result<f64, &'static str> {
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|compression| *compression > 0.0 && compression.is_finite())
+        .ok_or("T-Digest: error parsing compression parameter")
+}
+
+fn parse_values(args: &[String]) -> Result<Vec<f64>, &'static str> {
+    args.iter()
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| !value.is_nan())
+                .ok_or("T-Digest: error parsing val parameter")
+        })
+        .collect()
+}
+
+fn double_resp(value: f64) -> Resp {
+    if value.is_nan() {
+        Resp::BulkString(Some(b"nan".to_vec()))
+    } else {
+        Resp::BulkString(Some(format_score(value).into()))
+    }
+}
+
+// TDIGEST.CREATE key [COMPRESSION compression]
+pub struct TdigestCreateCommand;
+
+impl Command for TdigestCreateCommand {
+    fn name(&self) -> &'static str {
+        "TDIGEST.CREATE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let compression = match &args[1..] {
+            [] => DEFAULT_COMPRESSION,
+            [option, value] if option.eq_ignore_ascii_case("COMPRESSION") => {
+                match parse_compression(value) {
+                    Ok(compression) => compression,
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                }
+            }
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if memory.data.contains_key(&args[0]) {
+            return send_error(&mut ctx.stream, "T-Digest: key already exists");
+        }
+        memory.insert(args[0].clone(), Value::TDigest(TDigest::new(compression)));
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
+
+pub struct TdigestAddCommand;
+
+impl Command for TdigestAddCommand {
+    fn name(&self) -> &'static str {
+        "TDIGEST.ADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let values = match parse_values(&args[1..]) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_tdigest_mut(&args[0]) {
+            Ok(Some(digest)) => {
+                for value in values {
+                    digest.add(value);
+                }
+                send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+            }
+            Ok(None) => send_error(&mut ctx.stream, MISSING),
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// TDIGEST.QUANTILE key quantile [quantile ...] and TDIGEST.CDF key value [value ...]
+pub struct TdigestQueryCommand {
+    pub cdf: bool,
+}
+
+impl Command for TdigestQueryCommand {
+    fn name(&self) -> &'static str {
+        if self.cdf {
+            "TDIGEST.CDF"
+        } else {
+            "TDIGEST.QUANTILE"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let values = match parse_values(&args[1..]) {
+            Ok(values) => values,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        if !self.cdf && values.iter().any(|q| !(0.0..=1.0).contains(q)) {
+            return send_error(&mut ctx.stream, "T-Digest: quantile should be in [0,1]");
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let digest = match memory.get_tdigest(&args[0]) {
+            Ok(Some(digest)) => digest,
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let replies = values
+            .into_iter()
+            .map(|value| match self.cdf {
+                true => double_resp(digest.cdf(value)),
+                false => double_resp(digest.quantile(value)),
+            })
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
+
+// TDIGEST.MERGE destination numkeys source [source ...] [COMPRESSION compression] [OVERRIDE]
+pub struct TdigestMergeCommand;
+
+impl Command for TdigestMergeCommand {
+    fn name(&self) -> &'static str {
+        "TDIGEST.MERGE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let destination = &args[0];
+        let numkeys = match args[1].parse::<usize>() {
+            Ok(numkeys) if numkeys > 0 && 2 + numkeys <= args.len() => numkeys,
+            _ => return send_error(&mut ctx.stream, "T-Digest: error parsing numkeys"),
+        };
+        let sources = &args[2..2 + numkeys];
+
+        let mut compression = None;
+        let mut override_destination = false;
+        let mut index = 2 + numkeys;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "OVERRIDE" => override_destination = true,
+                "COMPRESSION" if index + 1 < args.len() => {
+                    index += 1;
+                    match parse_compression(&args[index]) {
+                        Ok(value) => compression = Some(value),
+                        Err(err) => return send_error(&mut ctx.stream, err),
+                    }
+                }
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in std::iter::once(destination).chain(sources) {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        let mut digests = Vec::with_capacity(numkeys);
+        for key in sources {
+            match memory.get_tdigest(key) {
+                Ok(Some(digest)) => digests.push(digest),
+                Ok(None) => return send_error(&mut ctx.stream, MISSING),
+                Err(_) => return send_wrongtype(&mut ctx.stream),
+            }
+        }
+
+        // Without OVERRIDE an existing destination's own data is kept in the result
+        let existing = match memory.get_tdigest(destination) {
+            Ok(existing) => existing.filter(|_| !override_destination),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let compression = compression.unwrap_or_else(|| {
+            digests
+                .iter()
+                .chain(&existing)
+                .map(|digest| digest.compression)
+                .fold(0.0, f64::max)
+        });
+        let mut merged = TDigest::new(compression);
+        for digest in digests.into_iter().chain(existing) {
+            merged.merge(digest);
+        }
+
+        memory.insert(destination.clone(), Value::TDigest(merged));
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
@@ -0,0 +1,614 @@
+use crate::{
+    commands::command::{
+        error_resp, errors, expire_if_needed, parse_bulk_string_args, send_arity_error, send_error,
+        send_resp, send_wrongtype, wrongtype_resp, Command, CommandContext,
+    },
+    expiry_manager::ExpiryManager,
+    memory::{Memory, Value},
+    resp::Resp,
+    types::{
+        timeseries::{
+            aggregate, bucket_start, Aggregation, DuplicatePolicy, LabelFilter, Rule, TimeSeries,
+        },
+        zset::format_score,
+    },
+};
+
+const MISSING: &str = "TSDB: the key does not exist";
+
+// Options shared by TS.CREATE, TS.ADD and TS.INCRBY; LABELS consumes the rest of the
+// arguments, as in RedisTimeSeries
+#[derive(Default)]
+struct SeriesOptions {
+    retention: Option<u64>,
+    duplicate_policy: Option<DuplicatePolicy>,
+    on_duplicate: Option<DuplicatePolicy>,
+    labels: Option<Vec<(String, String)>>,
+    timestamp: Option<u64>,
+}
+
+impl SeriesOptions {
+    fn parse(args: &[String]) -> Result<Self, &'static str> {
+        let mut options = SeriesOptions::default();
+        let mut index = 0;
+        while index < args.len() {
+            let value = args.get(index + 1);
+            match (args[index].to_ascii_uppercase().as_str(), value) {
+                ("RETENTION", Some(value)) => {
+                    let retention = value
+                        .parse()
+                        .map_err(|_| "TSDB: Couldn't parse RETENTION")?;
+                    options.retention = Some(retention);
+                }
+                ("DUPLICATE_POLICY", Some(value)) => {
+                    let policy =
+                        DuplicatePolicy::parse(value).ok_or("TSDB: Unknown DUPLICATE_POLICY")?;
+                    options.duplicate_policy = Some(policy);
+                }
+                ("ON_DUPLICATE", Some(value)) => {
+                    let policy =
+                        DuplicatePolicy::parse(value).ok_or("TSDB: Unknown ON_DUPLICATE")?;
+                    options.on_duplicate = Some(policy);
+                }
+                ("TIMESTAMP", Some(value)) => options.timestamp = Some(parse_timestamp(value)?),
+                ("LABELS", _) => {
+                    let pairs = &args[index + 1..];
+                    if !pairs.len().is_multiple_of(2) {
+                        return Err("TSDB: Couldn't parse LABELS");
+                    }
+                    let labels = pairs
+                        .chunks(2)
+                        .map(|pair| (pair[0].clone(), pair[1].clone()))
+                        .collect();
+                    options.labels = Some(labels);
+                    break;
+                }
+                _ => return Err(errors::SYNTAX),
+            }
+            index += 2;
+        }
+        Ok(options)
+    }
+
+    fn create(&self) -> TimeSeries {
+        TimeSeries::new(
+            self.retention.unwrap_or(0),
+            self.duplicate_policy.unwrap_or(DuplicatePolicy::Block),
+            self.labels.clone().unwrap_or_default(),
+        )
+    }
+}
+
+// `*` stands for the current time
+fn parse_timestamp(input: &str) -> Result<u64, &'static str> {
+    if input == "*" {
+        return Ok(ExpiryManager::get_now_ms());
+    }
+    input.parse().map_err(|_| "TSDB: invalid timestamp")
+}
+
+fn parse_value(input: &str) -> Result<f64, &'static str> {
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or("TSDB: invalid value")
+}
+
+fn sample_resp((timestamp, value): (u64, f64)) -> Resp {
+    Resp::Array(vec![
+        Resp::Integer(timestamp as i64),
+        Resp::BulkString(Some(format_score(value).into())),
+    ])
+}
+
+// Adds a sample to an existing series and refreshes the compacted buckets it affects.
+// Errors come back as replies so TS.MADD can report them per sample.
+fn add_sample(
+    memory: &mut Memory,
+    key: &str,
+    timestamp: u64,
+    value: f64,
+    policy: Option<DuplicatePolicy>,
+) -> Result<(), Resp> {
+    let series = match memory.get_timeseries_mut(key) {
+        Ok(Some(series)) => series,
+        Ok(None) => return Err(error_resp(MISSING)),
+        Err(_) => return Err(wrongtype_resp()),
+    };
+    let previous = series.last().map(|(newest, _)| newest);
+    series.add(timestamp, value, policy).map_err(error_resp)?;
+
+    // Only closed buckets are compacted: a sample opening a new bucket closes the one the
+    // previous newest sample was in, and a late sample reopens its own bucket
+    let mut updates = Vec::new();
+    for rule in &series.rules {
+        let Some(previous) = previous else { continue };
+        let open = bucket_start(previous, rule.bucket);
+        let current = bucket_start(timestamp, rule.bucket);
+        let closed = match current.cmp(&open) {
+            std::cmp::Ordering::Greater => open,
+            std::cmp::Ordering::Less => current,
+            std::cmp::Ordering::Equal => continue,
+        };
+        let samples = series.range(closed, closed.saturating_add(rule.bucket - 1));
+        if let Some(&(_, value)) = aggregate(samples, rule.aggregation, rule.bucket).first() {
+            updates.push((rule.destination.clone(), closed, value));
+        }
+    }
+    for (destination, timestamp, value) in updates {
+        if let Ok(Some(series)) = memory.get_timeseries_mut(&destination) {
+            series.upsert(timestamp, value);
+        }
+    }
+    Ok(())
+}
+
+// TS.CREATE key [RETENTION ms] [DUPLICATE_POLICY policy] [LABELS label value ...]
+pub struct TsCreateCommand;
+
+impl Command for TsCreateCommand {
+    fn name(&self) -> &'static str {
+        "TS.CREATE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let options = match SeriesOptions::parse(&args[1..]) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if memory.data.contains_key(&args[0]) {
+            return send_error(&mut ctx.stream, "TSDB: key already exists");
+        }
+        memory.insert(args[0].clone(), Value::TimeSeries(options.create()));
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
+
+// TS.ADD key timestamp value [RETENTION ms] [DUPLICATE_POLICY policy] [ON_DUPLICATE policy]
+// [LABELS label value ...], creating the series with those options when missing
+pub struct TsAddCommand;
+
+impl Command for TsAddCommand {
+    fn name(&self) -> &'static str {
+        "TS.ADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let parsed = parse_timestamp(&args[1]).and_then(|timestamp| {
+            let value = parse_value(&args[2])?;
+            let options = SeriesOptions::parse(&args[3..])?;
+            Ok((timestamp, value, options))
+        });
+        let (timestamp, value, options) = match parsed {
+            Ok(parsed) => parsed,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if !memory.data.contains_key(&args[0]) {
+            memory.insert(args[0].clone(), Value::TimeSeries(options.create()));
+        }
+        match add_sample(
+            &mut memory,
+            &args[0],
+            timestamp,
+            value,
+            options.on_duplicate,
+        ) {
+            Ok(()) => send_resp(&mut ctx.stream, Resp::Integer(timestamp as i64)),
+            Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
+
+// TS.MADD key timestamp value [key timestamp value ...], replying per sample
+pub struct TsMaddCommand;
+
+impl Command for TsMaddCommand {
+    fn name(&self) -> &'static str {
+        "TS.MADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if !args.is_empty() && args.len() % 3 == 0 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let mut samples = Vec::with_capacity(args.len() / 3);
+        for triple in args.chunks(3) {
+            let timestamp = match parse_timestamp(&triple[1]) {
+                Ok(timestamp) => timestamp,
+                Err(err) => return send_error(&mut ctx.stream, err),
+            };
+            match parse_value(&triple[2]) {
+                Ok(value) => samples.push((&triple[0], timestamp, value)),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            }
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        let replies = samples
+            .into_iter()
+            .map(|(key, timestamp, value)| {
+                expire_if_needed(&mut memory, &mut expiry_manager, key);
+                match add_sample(&mut memory, key, timestamp, value, None) {
+                    Ok(()) => Resp::Integer(timestamp as i64),
+                    Err(reply) => reply,
+                }
+            })
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
+
+// TS.INCRBY / TS.DECRBY key value [TIMESTAMP timestamp] [RETENTION ms] [LABELS ...]:
+// adds a sample of the newest value moved by `value`
+pub struct TsIncrbyCommand {
+    pub decrement: bool,
+}
+
+impl Command for TsIncrbyCommand {
+    fn name(&self) -> &'static str {
+        if self.decrement {
+            "TS.DECRBY"
+        } else {
+            "TS.INCRBY"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let parsed =
+            parse_value(&args[1]).and_then(|delta| Ok((delta, SeriesOptions::parse(&args[2..])?)));
+        let (delta, options) = match parsed {
+            Ok(parsed) => parsed,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let delta = if self.decrement { -delta } else { delta };
+        let timestamp = options.timestamp.unwrap_or_else(ExpiryManager::get_now_ms);
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let newest = match memory.get_timeseries(&args[0]) {
+            Ok(Some(series)) => series.last(),
+            Ok(None) => {
+                memory.insert(args[0].clone(), Value::TimeSeries(options.create()));
+                None
+            }
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        if newest.is_some_and(|(newest, _)| timestamp < newest) {
+            return send_error(
+                &mut ctx.stream,
+                "TSDB: timestamp must be equal to or higher than the maximum existing timestamp",
+            );
+        }
+        let value = newest.map_or(0.0, |(_, value)| value) + delta;
+        let policy = Some(DuplicatePolicy::Last);
+        match add_sample(&mut memory, &args[0], timestamp, value, policy) {
+            Ok(()) => send_resp(&mut ctx.stream, Resp::Integer(timestamp as i64)),
+            Err(reply) => send_resp(&mut ctx.stream, reply),
+        }
+    }
+}
+
+// The [COUNT count] [AGGREGATION aggregator bucket] part of the range commands, plus
+// WITHLABELS and FILTER for the multi-series variants
+struct RangeQuery {
+    from: u64,
+    to: u64,
+    count: Option<usize>,
+    aggregation: Option<(Aggregation, u64)>,
+    with_labels: bool,
+    filters: Vec<LabelFilter>,
+}
+
+impl RangeQuery {
+    fn parse(args: &[String], multi: bool) -> Result<Self, &'static str> {
+        let from = match args[0].as_str() {
+            "-" => 0,
+            from => from.parse().map_err(|_| "TSDB: wrong fromTimestamp")?,
+        };
+        let to = match args[1].as_str() {
+            "+" => u64::MAX,
+            to => to.parse().map_err(|_| "TSDB: wrong toTimestamp")?,
+        };
+        let mut query = RangeQuery {
+            from,
+            to,
+            count: None,
+            aggregation: None,
+            with_labels: false,
+            filters: Vec::new(),
+        };
+
+        let mut index = 2;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "COUNT" if index + 1 < args.len() => {
+                    index += 1;
+                    let count = args[index]
+                        .parse()
+                        .map_err(|_| "TSDB: Couldn't parse COUNT")?;
+                    query.count = Some(count);
+                }
+                "AGGREGATION" if index + 2 < args.len() => {
+                    let aggregation = Aggregation::parse(&args[index + 1])
+                        .ok_or("TSDB: Unknown aggregation type")?;
+                    let bucket = args[index + 2]
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|bucket| *bucket > 0)
+                        .ok_or("TSDB: bucketDuration must be greater than zero")?;
+                    query.aggregation = Some((aggregation, bucket));
+                    index += 2;
+                }
+                "WITHLABELS" if multi => query.with_labels = true,
+                "FILTER" if multi => {
+                    query.filters = args[index + 1..]
+                        .iter()
+                        .map(|filter| LabelFilter::parse(filter))
+                        .collect::<Option<_>>()
+                        .ok_or("TSDB: failed parsing labels")?;
+                    break;
+                }
+                _ => return Err(errors::SYNTAX),
+            }
+            index += 1;
+        }
+        if multi && query.filters.is_empty() {
+            return Err("TSDB: missing FILTER argument");
+        }
+        Ok(query)
+    }
+
+    fn samples(&self, series: &TimeSeries, reverse: bool) -> Resp {
+        let mut samples: Vec<(u64, f64)> = match self.aggregation {
+            Some((aggregation, bucket)) => {
+                aggregate(series.range(self.from, self.to), aggregation, bucket)
+            }
+            None => series.range(self.from, self.to).collect(),
+        };
+        if reverse {
+            samples.reverse();
+        }
+        samples.truncate(self.count.unwrap_or(usize::MAX));
+        Resp::Array(samples.into_iter().map(sample_resp).collect())
+    }
+}
+
+// TS.RANGE / TS.REVRANGE key from to [...], and TS.MRANGE / TS.MREVRANGE from to [...]
+// [WITHLABELS] FILTER filter [filter ...] over every series whose labels match
+pub struct TsRangeCommand {
+    pub reverse: bool,
+    pub multi: bool,
+}
+
+impl Command for TsRangeCommand {
+    fn name(&self) -> &'static str {
+        match (self.multi, self.reverse) {
+            (false, false) => "TS.RANGE",
+            (false, true) => "TS.REVRANGE",
+            (true, false) => "TS.MRANGE",
+            (true, true) => "TS.MREVRANGE",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let minimum = if self.multi { 2 } else { 3 };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= minimum => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (key, rest) = match self.multi {
+            true => (None, &args[..]),
+            false => (Some(&args[0]), &args[1..]),
+        };
+        let query = match RangeQuery::parse(rest, self.multi) {
+            Ok(query) => query,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+
+        if let Some(key) = key {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+            return match memory.get_timeseries(key) {
+                Ok(Some(series)) => send_resp(&mut ctx.stream, query.samples(series, self.reverse)),
+                Ok(None) => send_error(&mut ctx.stream, MISSING),
+                Err(_) => send_wrongtype(&mut ctx.stream),
+            };
+        }
+
+        let mut keys: Vec<String> = memory
+            .data
+            .iter()
+            .filter(|(_, value)| matches!(value, Value::TimeSeries(_)))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        let mut replies = Vec::new();
+        for key in keys {
+            expire_if_needed(&mut memory, &mut expiry_manager, &key);
+            let Ok(Some(series)) = memory.get_timeseries(&key) else {
+                continue;
+            };
+            if !query
+                .filters
+                .iter()
+                .all(|filter| filter.matches(&series.labels))
+            {
+                continue;
+            }
+            let labels = match query.with_labels {
+                true => series
+                    .labels
+                    .iter()
+                    .map(|(label, value)| {
+                        Resp::Array(vec![
+                            Resp::BulkString(Some(label.clone().into())),
+                            Resp::BulkString(Some(value.clone().into())),
+                        ])
+                    })
+                    .collect(),
+                false => Vec::new(),
+            };
+            let samples = query.samples(series, self.reverse);
+            replies.push(Resp::Array(vec![
+                Resp::BulkString(Some(key.into())),
+                Resp::Array(labels),
+                samples,
+            ]));
+        }
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
+
+// TS.CREATERULE source destination AGGREGATION aggregator bucket. Compactions don't chain:
+// a destination can't be a source itself, so each sample feeds at most one level.
+pub struct TsCreateruleCommand;
+
+impl Command for TsCreateruleCommand {
+    fn name(&self) -> &'static str {
+        "TS.CREATERULE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 5 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (source, destination) = (&args[0], &args[1]);
+        if !args[2].eq_ignore_ascii_case("AGGREGATION") {
+            return send_error(&mut ctx.stream, errors::SYNTAX);
+        }
+        let Some(aggregation) = Aggregation::parse(&args[3]) else {
+            return send_error(&mut ctx.stream, "TSDB: Unknown aggregation type");
+        };
+        let bucket = match args[4].parse::<u64>() {
+            Ok(bucket) if bucket > 0 => bucket,
+            _ => {
+                return send_error(
+                    &mut ctx.stream,
+                    "TSDB: bucketDuration must be greater than zero",
+                )
+            }
+        };
+        if source == destination {
+            return send_error(
+                &mut ctx.stream,
+                "TSDB: the source key and destination key should be different",
+            );
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+        expire_if_needed(&mut memory, &mut expiry_manager, destination);
+
+        let checks = match (
+            memory.get_timeseries(source),
+            memory.get_timeseries(destination),
+        ) {
+            (Err(_), _) | (_, Err(_)) => return send_wrongtype(&mut ctx.stream),
+            (Ok(None), _) | (_, Ok(None)) => Err(MISSING),
+            (Ok(Some(source)), Ok(Some(destination))) => {
+                if destination.source.is_some() {
+                    Err("TSDB: the destination key already has a src rule")
+                } else if !destination.rules.is_empty() {
+                    Err("TSDB: the destination key already has a dst rule")
+                } else if source.source.is_some() {
+                    Err("TSDB: the source key already has a src rule")
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        if let Err(err) = checks {
+            return send_error(&mut ctx.stream, err);
+        }
+
+        if let Ok(Some(series)) = memory.get_timeseries_mut(destination) {
+            series.source = Some(source.clone());
+        }
+        if let Ok(Some(series)) = memory.get_timeseries_mut(source) {
+            series.rules.push(Rule {
+                destination: destination.clone(),
+                aggregation,
+                bucket,
+            });
+        }
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
+
+// TS.DELETERULE source destination
+pub struct TsDeleteruleCommand;
+
+impl Command for TsDeleteruleCommand {
+    fn name(&self) -> &'static str {
+        "TS.DELETERULE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (source, destination) = (&args[0], &args[1]);
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+        expire_if_needed(&mut memory, &mut expiry_manager, destination);
+
+        let series = match memory.get_timeseries_mut(source) {
+            Ok(Some(series)) => series,
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let Some(index) = series
+            .rules
+            .iter()
+            .position(|rule| rule.destination == *destination)
+        else {
+            return send_error(&mut ctx.stream, "TSDB: compaction rule does not exist");
+        };
+        series.rules.remove(index);
+        if let Ok(Some(series)) = memory.get_timeseries_mut(destination) {
+            series.source = None;
+        }
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    commands::command::{
+        expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_resp,
+        send_wrongtype, Command, CommandContext,
+    },
+    memory::Value,
+    resp::Resp,
+    types::topk::{TopK, DEFAULT_DECAY, DEFAULT_DEPTH, DEFAULT_WIDTH},
+};
+
+const MISSING: &str = "TopK: key does not exist";
+
+// TOPK.RESERVE key topk [width depth decay]
+pub struct TopkReserveCommand;
+
+impl Command for TopkReserveCommand {
+    fn name(&self) -> &'static str {
+        "TOPK.RESERVE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 || args.len() == 5 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let positive = |input: &str| input.parse::<usize>().ok().filter(|v| *v > 0);
+        let Some(k) = positive(&args[1]) else {
+            return send_error(&mut ctx.stream, "TopK: invalid k");
+        };
+        let (width, depth, decay) = match &args[2..] {
+            [] => (DEFAULT_WIDTH, DEFAULT_DEPTH, DEFAULT_DECAY),
+            [width, depth, decay] => {
+                let Some(width) = positive(width) else {
+                    return send_error(&mut ctx.stream, "TopK: invalid width");
+                };
+                let Some(depth) = positive(depth) else {
+                    return send_error(&mut ctx.stream, "TopK: invalid depth");
+                };
+                match decay.parse::<f64>() {
+                    Ok(decay) if decay > 0.0 && decay <= 1.0 => (width, depth, decay),
+                    _ => return send_error(&mut ctx.stream, "TopK: invalid decay value"),
+                }
+            }
+            _ => unreachable!(),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        if memory.data.contains_key(&args[0]) {
+            return send_error(&mut ctx.stream, "TopK: key already exists");
+        }
+        let Some(topk) = TopK::new(k, width, depth, decay) else {
+            return send_error(&mut ctx.stream, "TopK: k or width * depth is too large");
+        };
+        memory.insert(args[0].clone(), Value::TopK(topk));
+        send_resp(&mut ctx.stream, Resp::SimpleString("OK".to_string()));
+    }
+}
+
+// Replies with the item each addition expelled from the top k, or nil
+pub struct TopkAddCommand;
+
+impl Command for TopkAddCommand {
+    fn name(&self) -> &'static str {
+        "TOPK.ADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let topk = match memory.get_topk_mut(&args[0]) {
+            Ok(Some(topk)) => topk,
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let expelled = args[1..]
+            .iter()
+            .map(|item| Resp::BulkString(topk.add(item).map(String::into_bytes)))
+            .collect();
+        send_resp(&mut ctx.stream, Resp::Array(expelled));
+    }
+}
+
+// TOPK.LIST key [WITHCOUNT]
+pub struct TopkListCommand;
+
+impl Command for TopkListCommand {
+    fn name(&self) -> &'static str {
+        "TOPK.LIST"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let withcount = match args.get(1) {
+            Some(option) if option.eq_ignore_ascii_case("WITHCOUNT") => true,
+            Some(_) => return send_error(&mut ctx.stream, "TopK: wrong argument"),
+            None => false,
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let topk = match memory.get_topk(&args[0]) {
+            Ok(Some(topk)) => topk,
+            Ok(None) => return send_error(&mut ctx.stream, MISSING),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let mut items = Vec::new();
+        for (item, count) in topk.list() {
+            items.push(Resp::BulkString(Some(item.clone().into())));
+            if withcount {
+                items.push(Resp::Integer(*count as i64));
+            }
+        }
+        send_resp(&mut ctx.stream, Resp::Array(items));
+    }
+}
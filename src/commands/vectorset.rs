@@ -0,0 +1,381 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, send_arity_error, send_error, send_resp,
+        send_wrongtype, Command, CommandContext,
+    },
+    memory::Value,
+    resp::Resp,
+    types::{
+        json::JsonFilter,
+        vectorset::{Metric, VectorSet, DEFAULT_EF, DEFAULT_M},
+        zset::format_score,
+    },
+};
+
+const DEFAULT_COUNT: usize = 10;
+
+// Reads `VALUES num v1 ... vnum` starting at `args[0]`, returning the vector and how
+// many arguments it spanned
+fn parse_vector(args: &[String]) -> Result<(Vec<f32>, usize), &'static str> {
+    const INVALID: &str = "invalid vector specification";
+    match args {
+        [values, num, rest @ ..] if values.eq_ignore_ascii_case("VALUES") => {
+            let num = num.parse::<usize>().map_err(|_| INVALID)?;
+            if num == 0 || rest.len() < num {
+                return Err(INVALID);
+            }
+            let vector = rest[..num]
+                .iter()
+                .map(|value| value.parse::<f32>().ok().filter(|value| value.is_finite()))
+                .collect::<Option<Vec<f32>>>()
+                .ok_or(INVALID)?;
+            Ok((vector, 2 + num))
+        }
+        _ => Err(INVALID),
+    }
+}
+
+fn parse_attributes(input: &str) -> Result<Option<serde_json::Value>, &'static str> {
+    if input.is_empty() {
+        return Ok(None);
+    }
+    match serde_json::from_str(input) {
+        Ok(value @ serde_json::Value::Object(_)) => Ok(Some(value)),
+        _ => Err("invalid JSON attributes, an object is expected"),
+    }
+}
+
+fn dimension_mismatch(got: usize, expected: usize) -> String {
+    format!(
+        "Vector dimension mismatch - got {} but set has {}",
+        got, expected
+    )
+}
+
+// VADD key VALUES num v1 ... vnum element [SETATTR json] [M m] [EF ef] [METRIC COSINE|L2]
+// M and METRIC only apply when the set is created; EF is the build-time search width
+pub struct VaddCommand;
+
+impl Command for VaddCommand {
+    fn name(&self) -> &'static str {
+        "VADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (vector, consumed) = match parse_vector(&args[1..]) {
+            Ok(parsed) => parsed,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let Some(element) = args.get(1 + consumed) else {
+            return send_arity_error(&mut ctx.stream, self.name());
+        };
+
+        let mut attributes = None;
+        let (mut m, mut ef, mut metric) = (DEFAULT_M, DEFAULT_EF, Metric::Cosine);
+        let mut index = 2 + consumed;
+        while index < args.len() {
+            let Some(value) = args.get(index + 1) else {
+                return send_error(&mut ctx.stream, errors::SYNTAX);
+            };
+            let positive = || value.parse::<usize>().ok().filter(|value| *value > 0);
+            match args[index].to_ascii_uppercase().as_str() {
+                "SETATTR" => match parse_attributes(value) {
+                    Ok(value) => attributes = value,
+                    Err(err) => return send_error(&mut ctx.stream, err),
+                },
+                "M" => match positive() {
+                    Some(value) => m = value,
+                    None => return send_error(&mut ctx.stream, "invalid M"),
+                },
+                "EF" => match positive() {
+                    Some(value) => ef = value,
+                    None => return send_error(&mut ctx.stream, "invalid EF"),
+                },
+                "METRIC" => match Metric::parse(value) {
+                    Some(value) => metric = value,
+                    None => return send_error(&mut ctx.stream, "unknown METRIC"),
+                },
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 2;
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let set = match memory.get_vectorset_mut(&args[0]) {
+            Ok(Some(set)) => set,
+            Ok(None) => {
+                let set = VectorSet::new(vector.len(), metric, m);
+                memory.insert(args[0].clone(), Value::VectorSet(set));
+                match memory.get_vectorset_mut(&args[0]) {
+                    Ok(Some(set)) => set,
+                    _ => unreachable!(),
+                }
+            }
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        if vector.len() != set.dim {
+            return send_error(&mut ctx.stream, &dimension_mismatch(vector.len(), set.dim));
+        }
+        let added = set.insert(element, vector, attributes, ef);
+        send_resp(&mut ctx.stream, Resp::Integer(added as i64));
+    }
+}
+
+pub struct VremCommand;
+
+impl Command for VremCommand {
+    fn name(&self) -> &'static str {
+        "VREM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let (removed, empty) = match memory.get_vectorset_mut(&args[0]) {
+            Ok(Some(set)) => (set.remove(&args[1]), set.len() == 0),
+            Ok(None) => (false, false),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        if empty {
+            memory.delete(&args[0]);
+            expiry_manager.remove_expiry(&args[0]);
+            expiry_manager.set_parts_expiry(&args[0], None);
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+    }
+}
+
+// VSIM key (ELE element | VALUES num v1 ... vnum) [WITHSCORES] [COUNT count] [EF ef]
+// [FILTER expression] [TRUTH]. FILTER takes a JSONPath filter over the attributes, such
+// as `@.year > 1950`; TRUTH scans every element instead of walking the HNSW graph.
+pub struct VsimCommand;
+
+impl Command for VsimCommand {
+    fn name(&self) -> &'static str {
+        "VSIM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (vector, mut index) = if args[1].eq_ignore_ascii_case("ELE") {
+            (None, 3)
+        } else {
+            match parse_vector(&args[1..]) {
+                Ok((vector, consumed)) => (Some(vector), 1 + consumed),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            }
+        };
+
+        let (mut withscores, mut exact) = (false, false);
+        let (mut count, mut ef) = (DEFAULT_COUNT, DEFAULT_EF);
+        let mut filter = None;
+        while index < args.len() {
+            let value = args.get(index + 1);
+            let positive = || value?.parse::<usize>().ok().filter(|value| *value > 0);
+            match args[index].to_ascii_uppercase().as_str() {
+                "WITHSCORES" => withscores = true,
+                "TRUTH" => exact = true,
+                "COUNT" => match positive() {
+                    Some(value) => (count, index) = (value, index + 1),
+                    None => return send_error(&mut ctx.stream, "invalid COUNT"),
+                },
+                "EF" => match positive() {
+                    Some(value) => (ef, index) = (value, index + 1),
+                    None => return send_error(&mut ctx.stream, "invalid EF"),
+                },
+                "FILTER" if value.is_some() => match JsonFilter::parse(&args[index + 1]) {
+                    Ok(value) => (filter, index) = (Some(value), index + 1),
+                    Err(err) => return send_error(&mut ctx.stream, &err),
+                },
+                _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+            }
+            index += 1;
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let set = match memory.get_vectorset(&args[0]) {
+            Ok(Some(set)) => set,
+            Ok(None) => return send_resp(&mut ctx.stream, Resp::Array(Vec::new())),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let vector = match vector {
+            Some(vector) if vector.len() != set.dim => {
+                let message = dimension_mismatch(vector.len(), set.dim);
+                return send_error(&mut ctx.stream, &message);
+            }
+            Some(vector) => vector,
+            None => match args.get(2).and_then(|element| set.vector(element)) {
+                Some(vector) => vector.to_vec(),
+                None => return send_error(&mut ctx.stream, "element not found in set"),
+            },
+        };
+
+        let mut replies = Vec::new();
+        for (element, score) in set.search(&vector, count, ef, filter.as_ref(), exact) {
+            replies.push(Resp::BulkString(Some(element.to_string().into())));
+            if withscores {
+                replies.push(Resp::BulkString(Some(format_score(score).into())));
+            }
+        }
+        send_resp(&mut ctx.stream, Resp::Array(replies));
+    }
+}
+
+// VCARD key and VDIM key
+pub struct VsizeCommand {
+    pub dim: bool,
+}
+
+impl Command for VsizeCommand {
+    fn name(&self) -> &'static str {
+        if self.dim {
+            "VDIM"
+        } else {
+            "VCARD"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_vectorset(&args[0]) {
+            Ok(Some(set)) if self.dim => send_resp(&mut ctx.stream, Resp::Integer(set.dim as i64)),
+            Ok(Some(set)) => send_resp(&mut ctx.stream, Resp::Integer(set.len() as i64)),
+            Ok(None) if self.dim => send_error(&mut ctx.stream, "key does not exist"),
+            Ok(None) => send_resp(&mut ctx.stream, Resp::Integer(0)),
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// VEMB key element: the stored vector, or nil
+pub struct VembCommand;
+
+impl Command for VembCommand {
+    fn name(&self) -> &'static str {
+        "VEMB"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_vectorset(&args[0]) {
+            Ok(set) => match set.and_then(|set| set.vector(&args[1])) {
+                Some(vector) => {
+                    let values = vector
+                        .iter()
+                        .map(|value| Resp::BulkString(Some(value.to_string().into())))
+                        .collect();
+                    send_resp(&mut ctx.stream, Resp::Array(values));
+                }
+                None => send_resp(&mut ctx.stream, Resp::BulkString(None)),
+            },
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// VGETATTR key element
+pub struct VgetattrCommand;
+
+impl Command for VgetattrCommand {
+    fn name(&self) -> &'static str {
+        "VGETATTR"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_vectorset(&args[0]) {
+            Ok(set) => {
+                let attributes = set.and_then(|set| set.attributes(&args[1]));
+                let json = attributes.map(|attributes| attributes.to_string());
+                send_resp(
+                    &mut ctx.stream,
+                    Resp::BulkString(json.map(String::into_bytes)),
+                );
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+// VSETATTR key element json, where an empty string clears the attributes
+pub struct VsetattrCommand;
+
+impl Command for VsetattrCommand {
+    fn name(&self) -> &'static str {
+        "VSETATTR"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let attributes = match parse_attributes(&args[2]) {
+            Ok(attributes) => attributes,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_vectorset_mut(&args[0]) {
+            Ok(set) => {
+                let updated = set.is_some_and(|set| set.set_attributes(&args[1], attributes));
+                send_resp(&mut ctx.stream, Resp::Integer(updated as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
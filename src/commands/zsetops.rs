@@ -0,0 +1,383 @@
+use crate::{
+    commands::{
+        command::{
+            errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+            send_error, send_resp, send_wrongtype, Command, CommandContext,
+        },
+        sets::SetOp,
+        zsets::{scored_array, RangeBy, RangeQuery},
+    },
+    expiry_manager::ExpiryManager,
+    memory::{Memory, Value, WrongType},
+    resp::Resp,
+    types::{
+        set::SetValue,
+        zset::{parse_score, SortedSet},
+    },
+};
+
+// An input of a sorted set operation; plain set members count with score 1
+enum Source<'a> {
+    Missing,
+    Set(&'a SetValue),
+    ZSet(&'a SortedSet),
+}
+
+impl Source<'_> {
+    fn load<'a>(memory: &'a Memory, key: &str) -> Result<Source<'a>, WrongType> {
+        match memory.data.get(key) {
+            Some(Value::ZSet(zset)) => Ok(Source::ZSet(zset)),
+            Some(Value::Set(set)) => Ok(Source::Set(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(Source::Missing),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Source::Missing => 0,
+            Source::Set(set) => set.len(),
+            Source::ZSet(zset) => zset.len(),
+        }
+    }
+
+    fn score(&self, member: &str) -> Option<f64> {
+        match self {
+            Source::Missing => None,
+            Source::Set(set) => set.contains(member).then_some(1.0),
+            Source::ZSet(zset) => zset.score(member),
+        }
+    }
+
+    fn entries(&self) -> Vec<(String, f64)> {
+        match self {
+            Source::Missing => Vec::new(),
+            Source::Set(set) => set.members().into_iter().map(|m| (m, 1.0)).collect(),
+            Source::ZSet(zset) => zset.entries(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(self, current: f64, score: f64) -> f64 {
+        match self {
+            // inf + -inf is defined as 0, like Redis does
+            Aggregate::Sum => Some(current + score).filter(|s| !s.is_nan()).unwrap_or(0.0),
+            Aggregate::Min => current.min(score),
+            Aggregate::Max => current.max(score),
+        }
+    }
+}
+
+// Multiplies a score by its input weight, treating 0 * inf as 0
+fn weighted(score: f64, weight: f64) -> f64 {
+    Some(score * weight).filter(|s| !s.is_nan()).unwrap_or(0.0)
+}
+
+struct Options {
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+    withscores: bool,
+}
+
+// Parses the `numkeys key ...` prefix and returns the keys plus the trailing arguments
+fn split_keys<'a>(
+    args: &'a [String],
+    command: &str,
+) -> Result<(&'a [String], &'a [String]), String> {
+    let numkeys = parse_int_arg(&args[0]).map_err(str::to_string)?;
+    if numkeys < 1 {
+        return Err(format!(
+            "at least 1 input key is needed for '{}' command",
+            command.to_lowercase()
+        ));
+    }
+    let numkeys = numkeys as usize;
+    if args.len() - 1 < numkeys {
+        return Err(errors::SYNTAX.to_string());
+    }
+    Ok((&args[1..=numkeys], &args[numkeys + 1..]))
+}
+
+fn parse_options(
+    rest: &[String],
+    numkeys: usize,
+    op: SetOp,
+    store: bool,
+) -> Result<Options, &'static str> {
+    let mut options = Options {
+        weights: vec![1.0; numkeys],
+        aggregate: Aggregate::Sum,
+        withscores: false,
+    };
+
+    let mut index = 0;
+    while index < rest.len() {
+        let allows_weights = !matches!(op, SetOp::Diff);
+        match rest[index].to_ascii_uppercase().as_str() {
+            "WEIGHTS" if allows_weights && index + numkeys < rest.len() => {
+                for (i, weight) in rest[index + 1..=index + numkeys].iter().enumerate() {
+                    options.weights[i] =
+                        parse_score(weight).map_err(|_| "weight value is not a float")?;
+                }
+                index += numkeys;
+            }
+            "AGGREGATE" if allows_weights && index + 1 < rest.len() => {
+                options.aggregate = match rest[index + 1].to_ascii_uppercase().as_str() {
+                    "SUM" => Aggregate::Sum,
+                    "MIN" => Aggregate::Min,
+                    "MAX" => Aggregate::Max,
+                    _ => return Err(errors::SYNTAX),
+                };
+                index += 1;
+            }
+            "WITHSCORES" if !store => options.withscores = true,
+            _ => return Err(errors::SYNTAX),
+        }
+        index += 1;
+    }
+
+    Ok(options)
+}
+
+fn compute(sources: &[Source], op: SetOp, options: &Options) -> SortedSet {
+    let mut result = SortedSet::new();
+
+    match op {
+        SetOp::Union => {
+            for (source, &weight) in sources.iter().zip(&options.weights) {
+                for (member, score) in source.entries() {
+                    let score = weighted(score, weight);
+                    let score = match result.score(&member) {
+                        Some(current) => options.aggregate.apply(current, score),
+                        None => score,
+                    };
+                    result.insert(member, score);
+                }
+            }
+        }
+        SetOp::Inter => {
+            // Drive the intersection from the smallest input
+            let smallest = sources
+                .iter()
+                .min_by_key(|source| source.len())
+                .expect("at least one input");
+            'members: for (member, _) in smallest.entries() {
+                let mut total: Option<f64> = None;
+                for (source, &weight) in sources.iter().zip(&options.weights) {
+                    let Some(score) = source.score(&member) else {
+                        continue 'members;
+                    };
+                    let score = weighted(score, weight);
+                    total = Some(match total {
+                        Some(current) => options.aggregate.apply(current, score),
+                        None => score,
+                    });
+                }
+                result.insert(member, total.unwrap_or(0.0));
+            }
+        }
+        SetOp::Diff => {
+            for (member, score) in sources[0].entries() {
+                if sources[1..]
+                    .iter()
+                    .all(|source| source.score(&member).is_none())
+                {
+                    result.insert(member, score);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+// Overwrites `destination` with the result, dropping any TTL it had
+pub fn store(
+    memory: &mut Memory,
+    expiry_manager: &mut ExpiryManager,
+    destination: &str,
+    result: SortedSet,
+) -> usize {
+    let len = result.len();
+    expiry_manager.remove_expiry(destination);
+    if result.is_empty() {
+        memory.delete(destination);
+    } else {
+        memory.insert(destination.to_string(), Value::ZSet(result));
+    }
+    len
+}
+
+// ZUNION/ZINTER/ZDIFF and their *STORE variants
+pub struct ZsetAlgebraCommand {
+    pub op: SetOp,
+    pub store: bool,
+}
+
+impl Command for ZsetAlgebraCommand {
+    fn name(&self) -> &'static str {
+        match (self.op, self.store) {
+            (SetOp::Union, false) => "ZUNION",
+            (SetOp::Inter, false) => "ZINTER",
+            (SetOp::Diff, false) => "ZDIFF",
+            (SetOp::Union, true) => "ZUNIONSTORE",
+            (SetOp::Inter, true) => "ZINTERSTORE",
+            (SetOp::Diff, true) => "ZDIFFSTORE",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let min_args = if self.store { 3 } else { 2 };
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= min_args => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let (destination, rest) = if self.store {
+            (Some(&args[0]), &args[1..])
+        } else {
+            (None, &args[..])
+        };
+        let (keys, rest) = match split_keys(rest, self.name()) {
+            Ok(split) => split,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+        let options = match parse_options(rest, keys.len(), self.op, self.store) {
+            Ok(options) => options,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in keys.iter().chain(destination) {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        let sources: Result<Vec<Source>, WrongType> =
+            keys.iter().map(|key| Source::load(&memory, key)).collect();
+        let result = match sources {
+            Ok(sources) => compute(&sources, self.op, &options),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        match destination {
+            Some(destination) => {
+                let len = store(&mut memory, &mut expiry_manager, destination, result);
+                ctx.state.blocking.signal(destination);
+                send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+            }
+            None => send_resp(
+                &mut ctx.stream,
+                scored_array(result.entries(), options.withscores),
+            ),
+        }
+    }
+}
+
+pub struct ZintercardCommand;
+
+impl Command for ZintercardCommand {
+    fn name(&self) -> &'static str {
+        "ZINTERCARD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (keys, rest) = match split_keys(&args, self.name()) {
+            Ok(split) => split,
+            Err(err) => return send_error(&mut ctx.stream, &err),
+        };
+        let limit = match rest {
+            [] => 0,
+            [option, limit] if option.eq_ignore_ascii_case("LIMIT") => match parse_int_arg(limit) {
+                Ok(limit) if limit >= 0 => limit as usize,
+                Ok(_) => return send_error(&mut ctx.stream, "LIMIT can't be negative"),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            },
+            _ => return send_error(&mut ctx.stream, errors::SYNTAX),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        for key in keys {
+            expire_if_needed(&mut memory, &mut expiry_manager, key);
+        }
+
+        let sources: Result<Vec<Source>, WrongType> =
+            keys.iter().map(|key| Source::load(&memory, key)).collect();
+        let sources = match sources {
+            Ok(sources) => sources,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let smallest = sources.iter().min_by_key(|source| source.len()).unwrap();
+        let mut count = 0;
+        for (member, _) in smallest.entries() {
+            if limit != 0 && count >= limit {
+                break;
+            }
+            if sources.iter().all(|source| source.score(&member).is_some()) {
+                count += 1;
+            }
+        }
+        send_resp(&mut ctx.stream, Resp::Integer(count as i64));
+    }
+}
+
+pub struct ZrangestoreCommand;
+
+impl Command for ZrangestoreCommand {
+    fn name(&self) -> &'static str {
+        "ZRANGESTORE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 4 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let query = match RangeQuery::parse(&args[2..], RangeBy::Rank, false) {
+            Ok(query) if query.withscores => return send_error(&mut ctx.stream, errors::SYNTAX),
+            Ok(query) => query,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let (destination, source) = (&args[0], &args[1]);
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, source);
+        expire_if_needed(&mut memory, &mut expiry_manager, destination);
+
+        let entries = match memory.get_zset(source) {
+            Ok(Some(zset)) => query.run(zset),
+            Ok(None) => Ok(Vec::new()),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut result = SortedSet::new();
+        for (member, score) in entries {
+            result.insert(member, score);
+        }
+        let len = store(&mut memory, &mut expiry_manager, destination, result);
+        ctx.state.blocking.signal(destination);
+        send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+    }
+}
@@ -0,0 +1,722 @@
+use crate::{
+    commands::command::{
+        errors, expire_if_needed, parse_bulk_string_args, parse_int_arg, send_arity_error,
+        send_error, send_resp, send_wrongtype, Command, CommandContext, MAX_RANDOM_REPLY,
+    },
+    expiry_manager::ExpiryManager,
+    memory::Memory,
+    random,
+    resp::Resp,
+    types::zset::{format_score, parse_score, LexRange, ScoreRange, SortedSet},
+};
+
+// Flattens (member, score) pairs into a reply, optionally interleaving scores
+pub fn scored_array(entries: Vec<(String, f64)>, withscores: bool) -> Resp {
+    let mut items = Vec::with_capacity(entries.len() * if withscores { 2 } else { 1 });
+    for (member, score) in entries {
+        items.push(Resp::BulkString(Some(member.into())));
+        if withscores {
+            items.push(Resp::BulkString(Some(format_score(score).into())));
+        }
+    }
+    Resp::Array(items)
+}
+
+// Sorted sets are deleted as soon as their last member goes away
+pub fn drop_if_empty(memory: &mut Memory, expiry_manager: &mut ExpiryManager, key: &str) {
+    if let Ok(Some(zset)) = memory.get_zset(key) {
+        if zset.is_empty() {
+            memory.delete(key);
+            expiry_manager.remove_expiry(key);
+        }
+    }
+}
+
+pub struct ZaddCommand;
+
+impl Command for ZaddCommand {
+    fn name(&self) -> &'static str {
+        "ZADD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let key = &args[0];
+
+        let (mut nx, mut xx, mut gt, mut lt, mut ch, mut incr) =
+            (false, false, false, false, false, false);
+        let mut index = 1;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "NX" => nx = true,
+                "XX" => xx = true,
+                "GT" => gt = true,
+                "LT" => lt = true,
+                "CH" => ch = true,
+                "INCR" => incr = true,
+                _ => break,
+            }
+            index += 1;
+        }
+
+        let pairs = &args[index..];
+        if pairs.is_empty() || pairs.len() % 2 != 0 {
+            return send_error(&mut ctx.stream, errors::SYNTAX);
+        }
+        if nx && xx {
+            return send_error(
+                &mut ctx.stream,
+                "XX and NX options at the same time are not compatible",
+            );
+        }
+        if (gt && lt) || (nx && (gt || lt)) {
+            return send_error(
+                &mut ctx.stream,
+                "GT, LT, and/or NX options at the same time are not compatible",
+            );
+        }
+        if incr && pairs.len() > 2 {
+            return send_error(
+                &mut ctx.stream,
+                "INCR option supports a single increment-element pair",
+            );
+        }
+
+        // Validate every score before touching the keyspace
+        let mut elements = Vec::with_capacity(pairs.len() / 2);
+        for pair in pairs.chunks(2) {
+            match parse_score(&pair[0]) {
+                Ok(score) => elements.push((score, &pair[1])),
+                Err(err) => return send_error(&mut ctx.stream, err),
+            }
+        }
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, key);
+
+        let zset = match memory.get_or_create_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let (mut added, mut updated) = (0, 0);
+        let mut incr_result = None;
+        for (score, member) in elements {
+            match zset.score(member) {
+                Some(current) => {
+                    if nx {
+                        continue;
+                    }
+                    let new_score = if incr { current + score } else { score };
+                    if new_score.is_nan() {
+                        drop_if_empty(&mut memory, &mut expiry_manager, key);
+                        return send_error(
+                            &mut ctx.stream,
+                            "resulting score is not a number (NaN)",
+                        );
+                    }
+                    if (gt && new_score <= current) || (lt && new_score >= current) {
+                        continue;
+                    }
+                    if new_score != current {
+                        zset.insert(member.clone(), new_score);
+                        updated += 1;
+                    }
+                    incr_result = Some(new_score);
+                }
+                None => {
+                    if xx {
+                        continue;
+                    }
+                    zset.insert(member.clone(), score);
+                    added += 1;
+                    incr_result = Some(score);
+                }
+            }
+        }
+
+        // XX or NX may have left a freshly created set empty
+        drop_if_empty(&mut memory, &mut expiry_manager, key);
+        if added > 0 {
+            ctx.state.blocking.signal(key);
+        }
+
+        let response = if incr {
+            Resp::BulkString(incr_result.map(format_score).map(String::into_bytes))
+        } else if ch {
+            Resp::Integer(added + updated)
+        } else {
+            Resp::Integer(added)
+        };
+        send_resp(&mut ctx.stream, response);
+    }
+}
+
+pub struct ZincrbyCommand;
+
+impl Command for ZincrbyCommand {
+    fn name(&self) -> &'static str {
+        "ZINCRBY"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let increment = match parse_score(&args[1]) {
+            Ok(increment) => increment,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let zset = match memory.get_or_create_zset(&args[0]) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let score = zset.score(&args[2]).unwrap_or(0.0) + increment;
+        if score.is_nan() {
+            drop_if_empty(&mut memory, &mut expiry_manager, &args[0]);
+            return send_error(&mut ctx.stream, "resulting score is not a number (NaN)");
+        }
+        zset.insert(args[2].clone(), score);
+        ctx.state.blocking.signal(&args[0]);
+        send_resp(
+            &mut ctx.stream,
+            Resp::BulkString(Some(format_score(score).into())),
+        );
+    }
+}
+
+pub struct ZremCommand;
+
+impl Command for ZremCommand {
+    fn name(&self) -> &'static str {
+        "ZREM"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let removed = match memory.get_zset_mut(&args[0]) {
+            Ok(Some(zset)) => args[1..]
+                .iter()
+                .filter(|member| zset.remove(member))
+                .count(),
+            Ok(None) => 0,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        drop_if_empty(&mut memory, &mut expiry_manager, &args[0]);
+
+        send_resp(&mut ctx.stream, Resp::Integer(removed as i64));
+    }
+}
+
+pub struct ZscoreCommand;
+
+impl Command for ZscoreCommand {
+    fn name(&self) -> &'static str {
+        "ZSCORE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_zset(&args[0]) {
+            Ok(zset) => {
+                let score = zset.and_then(|zset| zset.score(&args[1]));
+                send_resp(
+                    &mut ctx.stream,
+                    Resp::BulkString(score.map(format_score).map(String::into_bytes)),
+                );
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct ZmscoreCommand;
+
+impl Command for ZmscoreCommand {
+    fn name(&self) -> &'static str {
+        "ZMSCORE"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_zset(&args[0]) {
+            Ok(zset) => {
+                let scores = args[1..]
+                    .iter()
+                    .map(|member| {
+                        let score = zset.and_then(|zset| zset.score(member));
+                        Resp::BulkString(score.map(format_score).map(String::into_bytes))
+                    })
+                    .collect();
+                send_resp(&mut ctx.stream, Resp::Array(scores));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+pub struct ZcardCommand;
+
+impl Command for ZcardCommand {
+    fn name(&self) -> &'static str {
+        "ZCARD"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        match memory.get_zset(&args[0]) {
+            Ok(zset) => {
+                let len = zset.map_or(0, |zset| zset.len());
+                send_resp(&mut ctx.stream, Resp::Integer(len as i64));
+            }
+            Err(_) => send_wrongtype(&mut ctx.stream),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RangeBy {
+    Rank,
+    Score,
+    Lex,
+}
+
+// Counts members in a score (ZCOUNT) or lexicographical (ZLEXCOUNT) interval
+pub struct ZcountCommand {
+    pub by: RangeBy,
+}
+
+impl Command for ZcountCommand {
+    fn name(&self) -> &'static str {
+        match self.by {
+            RangeBy::Lex => "ZLEXCOUNT",
+            _ => "ZCOUNT",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let zset = match memory.get_zset(&args[0]) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let count = match self.by {
+            RangeBy::Lex => LexRange::parse(&args[1], &args[2])
+                .map(|range| zset.map_or(0, |zset| zset.count_by_lex(&range))),
+            _ => ScoreRange::parse(&args[1], &args[2])
+                .map(|range| zset.map_or(0, |zset| zset.count_by_score(&range))),
+        };
+        match count {
+            Ok(count) => send_resp(&mut ctx.stream, Resp::Integer(count as i64)),
+            Err(err) => send_error(&mut ctx.stream, err),
+        }
+    }
+}
+
+pub struct ZrankCommand {
+    pub reverse: bool,
+}
+
+impl Command for ZrankCommand {
+    fn name(&self) -> &'static str {
+        if self.reverse {
+            "ZREVRANK"
+        } else {
+            "ZRANK"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 2 || args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let withscore = match args.get(2) {
+            Some(option) if option.eq_ignore_ascii_case("WITHSCORE") => true,
+            Some(_) => return send_error(&mut ctx.stream, errors::SYNTAX),
+            None => false,
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let zset = match memory.get_zset(&args[0]) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let found = zset.and_then(|zset| {
+            let rank = zset.rank(&args[1], self.reverse)?;
+            Some((rank, zset.score(&args[1])?))
+        });
+        let response = match found {
+            Some((rank, score)) if withscore => Resp::Array(vec![
+                Resp::Integer(rank as i64),
+                Resp::BulkString(Some(format_score(score).into())),
+            ]),
+            Some((rank, _)) => Resp::Integer(rank as i64),
+            None if withscore => Resp::Array(vec![]),
+            None => Resp::BulkString(None),
+        };
+        send_resp(&mut ctx.stream, response);
+    }
+}
+
+// A parsed ZRANGE request, shared by the legacy range commands
+pub struct RangeQuery {
+    pub by: RangeBy,
+    pub start: String,
+    pub stop: String,
+    pub reverse: bool,
+    pub limit: Option<(i64, i64)>,
+    pub withscores: bool,
+}
+
+impl RangeQuery {
+    // Parses `start stop [BYSCORE|BYLEX] [REV] [LIMIT offset count] [WITHSCORES]`;
+    // `by`/`reverse` seed the defaults so legacy commands can reuse this
+    pub fn parse(args: &[String], by: RangeBy, reverse: bool) -> Result<Self, &'static str> {
+        let mut query = RangeQuery {
+            by,
+            start: args[0].clone(),
+            stop: args[1].clone(),
+            reverse,
+            limit: None,
+            withscores: false,
+        };
+
+        let mut index = 2;
+        while index < args.len() {
+            match args[index].to_ascii_uppercase().as_str() {
+                "WITHSCORES" => query.withscores = true,
+                "BYSCORE" => query.by = RangeBy::Score,
+                "BYLEX" => query.by = RangeBy::Lex,
+                "REV" => query.reverse = true,
+                "LIMIT" if index + 2 < args.len() => {
+                    let offset = parse_int_arg(&args[index + 1])?;
+                    let count = parse_int_arg(&args[index + 2])?;
+                    query.limit = Some((offset, count));
+                    index += 2;
+                }
+                _ => return Err(errors::SYNTAX),
+            }
+            index += 1;
+        }
+
+        if query.limit.is_some() && query.by == RangeBy::Rank {
+            return Err(
+                "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX",
+            );
+        }
+        if query.withscores && query.by == RangeBy::Lex {
+            return Err("syntax error, WITHSCORES not supported in combination with BYLEX");
+        }
+        Ok(query)
+    }
+
+    pub fn run(&self, zset: &SortedSet) -> Result<Vec<(String, f64)>, &'static str> {
+        // With REV the first bound is the upper one
+        let (min, max) = if self.reverse && self.by != RangeBy::Rank {
+            (&self.stop, &self.start)
+        } else {
+            (&self.start, &self.stop)
+        };
+        let (offset, limit) = match self.limit {
+            Some((offset, _)) if offset < 0 => return Ok(Vec::new()),
+            Some((offset, count)) => (offset as usize, usize::try_from(count).ok()),
+            None => (0, None),
+        };
+
+        match self.by {
+            RangeBy::Rank => {
+                let len = zset.len() as i64;
+                let mut start = parse_int_arg(&self.start)?;
+                let mut stop = parse_int_arg(&self.stop)?;
+                if start < 0 {
+                    start += len;
+                }
+                if stop < 0 {
+                    stop += len;
+                }
+                let start = start.max(0);
+                let stop = stop.min(len - 1);
+                if start > stop {
+                    return Ok(Vec::new());
+                }
+                Ok(zset.range_by_rank(start as usize, stop as usize, self.reverse))
+            }
+            RangeBy::Score => {
+                let range = ScoreRange::parse(min, max)?;
+                Ok(zset.range_by_score(&range, self.reverse, offset, limit))
+            }
+            RangeBy::Lex => {
+                let range = LexRange::parse(min, max)?;
+                Ok(zset.range_by_lex(&range, self.reverse, offset, limit))
+            }
+        }
+    }
+}
+
+// ZRANGE and the older ZREVRANGE/ZRANGEBYSCORE/ZRANGEBYLEX family
+pub struct ZrangeCommand {
+    pub name: &'static str,
+    pub by: RangeBy,
+    pub reverse: bool,
+}
+
+impl Command for ZrangeCommand {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() >= 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let query = match RangeQuery::parse(&args[1..], self.by, self.reverse) {
+            // Only ZRANGE itself may switch the range type or direction
+            Ok(query)
+                if self.name != "ZRANGE"
+                    && (query.by != self.by || query.reverse != self.reverse) =>
+            {
+                return send_error(&mut ctx.stream, errors::SYNTAX)
+            }
+            Ok(query) => query,
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let entries = match memory.get_zset(&args[0]) {
+            Ok(Some(zset)) => query.run(zset),
+            Ok(None) => Ok(Vec::new()),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        match entries {
+            Ok(entries) => send_resp(&mut ctx.stream, scored_array(entries, query.withscores)),
+            Err(err) => send_error(&mut ctx.stream, err),
+        }
+    }
+}
+
+pub struct ZremrangeCommand {
+    pub by: RangeBy,
+}
+
+impl Command for ZremrangeCommand {
+    fn name(&self) -> &'static str {
+        match self.by {
+            RangeBy::Rank => "ZREMRANGEBYRANK",
+            RangeBy::Score => "ZREMRANGEBYSCORE",
+            RangeBy::Lex => "ZREMRANGEBYLEX",
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 3 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let query = RangeQuery {
+            by: self.by,
+            start: args[1].clone(),
+            stop: args[2].clone(),
+            reverse: false,
+            limit: None,
+            withscores: false,
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let removed = match memory.get_zset_mut(&args[0]) {
+            Ok(Some(zset)) => query.run(zset).map(|entries| {
+                for (member, _) in &entries {
+                    zset.remove(member);
+                }
+                entries.len()
+            }),
+            Ok(None) => Ok(0),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        drop_if_empty(&mut memory, &mut expiry_manager, &args[0]);
+
+        match removed {
+            Ok(removed) => send_resp(&mut ctx.stream, Resp::Integer(removed as i64)),
+            Err(err) => send_error(&mut ctx.stream, err),
+        }
+    }
+}
+
+pub struct ZpopCommand {
+    pub highest: bool,
+}
+
+impl Command for ZpopCommand {
+    fn name(&self) -> &'static str {
+        if self.highest {
+            "ZPOPMAX"
+        } else {
+            "ZPOPMIN"
+        }
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if args.len() == 1 || args.len() == 2 => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let count = match args.get(1).map(|count| parse_int_arg(count)) {
+            Some(Ok(count)) if count < 0 => {
+                return send_error(&mut ctx.stream, "value is out of range, must be positive")
+            }
+            Some(Ok(count)) => count as usize,
+            Some(Err(err)) => return send_error(&mut ctx.stream, err),
+            None => 1,
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let popped = match memory.get_zset_mut(&args[0]) {
+            Ok(Some(zset)) => zset.pop(count, self.highest),
+            Ok(None) => Vec::new(),
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+        drop_if_empty(&mut memory, &mut expiry_manager, &args[0]);
+
+        send_resp(&mut ctx.stream, scored_array(popped, true));
+    }
+}
+
+pub struct ZrandmemberCommand;
+
+impl Command for ZrandmemberCommand {
+    fn name(&self) -> &'static str {
+        "ZRANDMEMBER"
+    }
+
+    fn execute(&self, args: &[Resp], ctx: &mut CommandContext) {
+        let args = match parse_bulk_string_args(args) {
+            Ok(args) if (1..=3).contains(&args.len()) => args,
+            Ok(_) => return send_arity_error(&mut ctx.stream, self.name()),
+            Err(err) => return send_error(&mut ctx.stream, err),
+        };
+        let count = match args.get(1).map(|count| parse_int_arg(count)) {
+            Some(Ok(count)) => Some(count),
+            Some(Err(err)) => return send_error(&mut ctx.stream, err),
+            None => None,
+        };
+        let withscores = match args.get(2) {
+            Some(option) if option.eq_ignore_ascii_case("WITHSCORES") => true,
+            Some(_) => return send_error(&mut ctx.stream, errors::SYNTAX),
+            None => false,
+        };
+
+        let mut expiry_manager = ctx.state.expiry_manager.lock().unwrap();
+        let mut memory = ctx.state.memory.lock().unwrap();
+        expire_if_needed(&mut memory, &mut expiry_manager, &args[0]);
+
+        let zset = match memory.get_zset(&args[0]) {
+            Ok(zset) => zset,
+            Err(_) => return send_wrongtype(&mut ctx.stream),
+        };
+
+        let response = match (zset, count) {
+            (None, Some(_)) => Resp::Array(vec![]),
+            (None, None) => Resp::BulkString(None),
+            (Some(zset), None) => {
+                let index = random::below(zset.len());
+                let entry = zset.range_by_rank(index, index, false).pop();
+                Resp::BulkString(entry.map(|(member, _)| member.into_bytes()))
+            }
+            // A negative count allows the same member to be returned several times
+            (Some(zset), Some(count)) if count < 0 => {
+                let entries = (0..count.unsigned_abs().min(MAX_RANDOM_REPLY))
+                    .filter_map(|_| {
+                        let index = random::below(zset.len());
+                        zset.range_by_rank(index, index, false).pop()
+                    })
+                    .collect();
+                scored_array(entries, withscores)
+            }
+            (Some(zset), Some(count)) => {
+                let entries = random::distinct(zset.len(), count as usize)
+                    .into_iter()
+                    .filter_map(|index| zset.range_by_rank(index, index, false).pop())
+                    .collect();
+                scored_array(entries, withscores)
+            }
+        };
+        send_resp(&mut ctx.stream, response);
+    }
+}
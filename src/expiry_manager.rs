@@ -1,24 +1,80 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 type Timestamp = u64;
 
+// Deadlines indexed both ways: by time for the sweep, and by entry for updates
+struct Deadlines<K> {
+    // Maps expiration timestamps to sets of entries
+    expires: BTreeMap<Timestamp, HashSet<K>>,
+    // Maps entries to their expiration timestamps
+    entry_to_expiry: HashMap<K, Timestamp>,
+}
+
+impl<K: Clone + Eq + Hash> Deadlines<K> {
+    fn new() -> Self {
+        Deadlines {
+            expires: BTreeMap::new(),
+            entry_to_expiry: HashMap::new(),
+        }
+    }
+
+    fn get(&self, entry: &K) -> Option<Timestamp> {
+        self.entry_to_expiry.get(entry).copied()
+    }
+
+    fn set(&mut self, entry: K, expiry_time: Timestamp) {
+        // Remove old expiry if it exists
+        self.remove(&entry);
+
+        // Add new expiry
+        self.entry_to_expiry.insert(entry.clone(), expiry_time);
+        self.expires.entry(expiry_time).or_default().insert(entry);
+    }
+
+    fn remove(&mut self, entry: &K) -> Option<Timestamp> {
+        let expiry_time = self.entry_to_expiry.remove(entry)?;
+        if let Some(entries) = self.expires.get_mut(&expiry_time) {
+            entries.remove(entry);
+            if entries.is_empty() {
+                self.expires.remove(&expiry_time);
+            }
+        }
+        Some(expiry_time)
+    }
+
+    // Removes and returns every entry whose deadline has passed
+    fn take_due(&mut self, now: Timestamp) -> Vec<K> {
+        let due: Vec<Timestamp> = self.expires.range(..=now).map(|(&time, _)| time).collect();
+        let mut entries = Vec::new();
+        for timestamp in due {
+            if let Some(expired) = self.expires.remove(&timestamp) {
+                for entry in expired {
+                    self.entry_to_expiry.remove(&entry);
+                    entries.push(entry);
+                }
+            }
+        }
+        entries
+    }
+}
+
 pub struct ExpiryManager {
-    // Maps expiration timestamps to sets of keys
-    expires: BTreeMap<Timestamp, HashSet<String>>,
-    // Maps keys to their expiration timestamps
-    key_to_expiry: HashMap<String, Timestamp>,
+    keys: Deadlines<String>,
+    // When part of a value is next due to expire, such as the soonest field TTL of a hash
+    parts: Deadlines<String>,
 }
 
 impl ExpiryManager {
     pub fn new() -> Self {
         ExpiryManager {
-            expires: BTreeMap::new(),
-            key_to_expiry: HashMap::new(),
+            keys: Deadlines::new(),
+            parts: Deadlines::new(),
         }
     }
 
-    fn get_now_ms() -> Timestamp {
+    pub fn get_now_ms() -> Timestamp {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or(Duration::from_secs(0))
@@ -26,64 +82,63 @@ impl ExpiryManager {
     }
 
     pub fn set_expiry(&mut self, key: &str, ttl_ms: u64) {
-        let expiry_time = Self::get_now_ms() + ttl_ms;
-
-        // Remove old expiry if it exists
-        if let Some(&old_expiry) = self.key_to_expiry.get(key) {
-            if let Some(keys) = self.expires.get_mut(&old_expiry) {
-                keys.remove(key);
-                if keys.is_empty() {
-                    self.expires.remove(&old_expiry);
-                }
-            }
-        }
+        self.set_expiry_at(key, Self::get_now_ms().saturating_add(ttl_ms));
+    }
 
-        // Add new expiry
-        self.key_to_expiry.insert(key.to_string(), expiry_time);
-        self.expires
-            .entry(expiry_time)
-            .or_default()
-            .insert(key.to_string());
+    // Sets an absolute deadline in ms since the epoch; a past one expires the key on access
+    pub fn set_expiry_at(&mut self, key: &str, expiry_time: Timestamp) {
+        self.keys.set(key.to_string(), expiry_time);
     }
 
     pub fn remove_expiry(&mut self, key: &str) {
-        if let Some(&expiry_time) = self.key_to_expiry.get(key) {
-            if let Some(keys) = self.expires.get_mut(&expiry_time) {
-                keys.remove(key);
-                if keys.is_empty() {
-                    self.expires.remove(&expiry_time);
-                }
-            }
-            self.key_to_expiry.remove(key);
-        }
+        self.keys.remove(&key.to_string());
+    }
+
+    // The key's deadline in ms since the epoch, if it has one
+    pub fn get_expiry(&self, key: &str) -> Option<Timestamp> {
+        self.keys.get(&key.to_string())
     }
 
     pub fn is_expired(&self, key: &str) -> bool {
-        if let Some(&expiry_time) = self.key_to_expiry.get(key) {
+        if let Some(expiry_time) = self.get_expiry(key) {
             Self::get_now_ms() >= expiry_time
         } else {
             false
         }
     }
 
+    // Schedules the value at `key` to have its expired parts dropped at `deadline`, or
+    // stops checking it when nothing in it can expire
+    pub fn set_parts_expiry(&mut self, key: &str, deadline: Option<Timestamp>) {
+        match deadline {
+            Some(deadline) => self.parts.set(key.to_string(), deadline),
+            None => {
+                self.parts.remove(&key.to_string());
+            }
+        }
+    }
+
+    pub fn get_parts_expiry(&self, key: &str) -> Option<Timestamp> {
+        self.parts.get(&key.to_string())
+    }
+
+    pub fn parts_expired(&self, key: &str) -> bool {
+        self.get_parts_expiry(key)
+            .is_some_and(|deadline| Self::get_now_ms() >= deadline)
+    }
+
     pub fn cleanup_expired_keys<F>(&mut self, mut remove_key: F)
     where
         F: FnMut(&str),
     {
-        let now = Self::get_now_ms();
-        let expired_keys: Vec<Timestamp> = self
-            .expires
-            .range(..=now)
-            .map(|(&timestamp, _)| timestamp)
-            .collect();
-
-        for timestamp in expired_keys {
-            if let Some(keys) = self.expires.remove(&timestamp) {
-                for key in keys {
-                    self.key_to_expiry.remove(&key);
-                    remove_key(&key); // Call the callback to remove the key from Memory
-                }
-            }
+        for key in self.keys.take_due(Self::get_now_ms()) {
+            remove_key(&key); // Call the callback to remove the key from Memory
         }
     }
+
+    // Unschedules and returns the keys whose values have parts due to expire; the caller
+    // drops those parts and schedules the keys again
+    pub fn take_expired_parts(&mut self) -> Vec<String> {
+        self.parts.take_due(Self::get_now_ms())
+    }
 }
@@ -1,8 +1,11 @@
+mod blocking;
 mod commands;
 mod expiry_manager;
 mod memory;
+mod random;
 mod resp;
 mod server;
+mod types;
 
 use std::{
     env,
@@ -19,12 +22,15 @@ fn main() {
     // Create expiry manager
     let expiry_manager = Arc::new(Mutex::new(expiry_manager::ExpiryManager::new()));
 
+    // Create registry of clients blocked on keys
+    let blocking = Arc::new(blocking::BlockingManager::new());
+
     let port = env::args().nth(2).unwrap_or("6379".to_string());
 
     let address = format!("127.0.0.1:{}", port);
 
     // Create and run server
-    let server = server::Server::new(&address, memory, registry, expiry_manager)
+    let server = server::Server::new(&address, memory, registry, expiry_manager, blocking)
         .expect("Failed to create server");
 
     server.run();
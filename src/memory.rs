@@ -1,25 +1,531 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+};
+
+use crate::expiry_manager::ExpiryManager;
+use crate::types::{
+    bloom::{self, BloomFilter},
+    cms::CountMinSketch,
+    cuckoo::{self, CuckooFilter},
+    hash::Hash,
+    scan::ScanIndex,
+    search::SearchIndex,
+    set::SetValue,
+    stream::Stream,
+    tdigest::TDigest,
+    timeseries::TimeSeries,
+    topk::TopK,
+    trie::SuggestionTrie,
+    vectorset::VectorSet,
+    zset::SortedSet,
+};
+
+#[derive(Clone)]
+pub enum Value {
+    // Strings are kept as raw bytes so bit-level commands can produce any byte value
+    String(Vec<u8>),
+    // Strings holding a canonical 64-bit integer, as Redis' int encoding, so counters
+    // aren't re-parsed on every increment
+    Integer(i64),
+    Hash(Hash),
+    Set(SetValue),
+    ZSet(SortedSet),
+    Stream(Stream),
+    Json(serde_json::Value),
+    Bloom(BloomFilter),
+    Cuckoo(CuckooFilter),
+    Cms(CountMinSketch),
+    TopK(TopK),
+    TDigest(TDigest),
+    TimeSeries(TimeSeries),
+    Suggestions(SuggestionTrie),
+    VectorSet(VectorSet),
+}
+
+impl Value {
+    // The name TYPE reports; module-backed types use the names their Redis modules register
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) | Value::Integer(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+            Value::Stream(_) => "stream",
+            Value::Json(_) => "ReJSON-RL",
+            Value::Bloom(_) => "MBbloom--",
+            Value::Cuckoo(_) => "MBbloomCF",
+            Value::Cms(_) => "CMSk-TYPE",
+            Value::TopK(_) => "TopK-TYPE",
+            Value::TDigest(_) => "TDIS-TYPE",
+            Value::TimeSeries(_) => "TSDB-TYPE",
+            Value::Suggestions(_) => "trietype0",
+            Value::VectorSet(_) => "vectorset",
+        }
+    }
+}
+
+// Returned when a key exists but holds a different data type
+pub struct WrongType;
+
+// Why a key can't be read as a number for INCR-style commands
+pub enum NumberError {
+    WrongType,
+    NotANumber,
+}
+
+// The integer a string spells, if it is in the exact form Redis would int-encode: no
+// sign other than a leading '-', no leading zeros and no surrounding whitespace
+pub fn parse_integer(bytes: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(bytes).ok()?;
+    let value = text.parse::<i64>().ok()?;
+    (value.to_string() == text).then_some(value)
+}
+
+// Writers need raw bytes, so an int-encoded string is expanded in place first
+fn decode_integer(value: &mut Value) -> &mut Value {
+    if let Value::Integer(integer) = value {
+        *value = Value::String(integer.to_string().into_bytes());
+    }
+    value
+}
+
+fn string_value(bytes: Vec<u8>) -> Value {
+    match parse_integer(&bytes) {
+        Some(value) => Value::Integer(value),
+        None => Value::String(bytes),
+    }
+}
 
 pub struct Memory {
-    pub data: HashMap<String, String>,
+    // Read freely, but only written through the methods below so `scan` stays in step
+    pub data: HashMap<String, Value>,
+    scan: ScanIndex,
+    // FT.* indexes by name. Writes through the methods below keep them current; code that
+    // changes a hash in place calls `reindex` once done.
+    indexes: BTreeMap<String, SearchIndex>,
 }
 
 impl Memory {
     pub fn new() -> Self {
         Memory {
             data: HashMap::new(),
+            scan: ScanIndex::default(),
+            indexes: BTreeMap::new(),
         }
     }
 
-    pub fn set(&mut self, key: String, value: String) {
-        self.data.insert(key, value);
+    pub fn set(&mut self, key: String, value: Vec<u8>) {
+        self.insert(key, string_value(value));
     }
 
-    pub fn get(&self, key: &str) -> Option<String> {
-        self.data.get(key).cloned()
+    pub fn insert(&mut self, key: String, value: Value) {
+        if !self.data.contains_key(&key) {
+            self.scan.insert(&key);
+        }
+        self.data.insert(key.clone(), value);
+        self.reindex(&key);
+    }
+
+    // The key's value, created by `create` when the key is missing
+    fn entry(&mut self, key: &str, create: impl FnOnce() -> Value) -> &mut Value {
+        if !self.data.contains_key(key) {
+            self.scan.insert(key);
+        }
+        self.data.entry(key.to_string()).or_insert_with(create)
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(Value::Integer(value)) => Ok(Some(value.to_string().into_bytes())),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete(&mut self, key: &str) -> Option<Value> {
+        let value = self.data.remove(key);
+        if value.is_some() {
+            self.scan.remove(key);
+            self.reindex(key);
+        }
+        value
+    }
+
+    // Drops the parts of the value at `key` that have expired, which for now means hash
+    // fields past their TTL, deleting a hash left empty. Returns when this is next due.
+    pub fn expire_parts(&mut self, key: &str) -> Option<u64> {
+        match self.data.get_mut(key) {
+            Some(Value::Hash(hash)) => {
+                hash.expire_fields(ExpiryManager::get_now_ms());
+                let next = hash.next_expiry();
+                if hash.is_empty() {
+                    self.delete(key);
+                } else {
+                    self.reindex(key);
+                }
+                next
+            }
+            _ => None,
+        }
+    }
+
+    // Brings every index covering `key` in line with what the key now holds
+    pub fn reindex(&mut self, key: &str) {
+        for index in self.indexes.values_mut().filter(|index| index.covers(key)) {
+            match self.data.get(key) {
+                Some(Value::Hash(hash)) => index.add(key, hash),
+                _ => {
+                    index.remove(key);
+                }
+            }
+        }
+    }
+
+    // Adds an index, filled from the hashes already under its prefixes; false if the name
+    // is taken
+    pub fn create_index(&mut self, mut index: SearchIndex) -> bool {
+        if self.indexes.contains_key(&index.name) {
+            return false;
+        }
+        for (key, value) in &self.data {
+            if let (true, Value::Hash(hash)) = (index.covers(key), value) {
+                index.add(key, hash);
+            }
+        }
+        self.indexes.insert(index.name.clone(), index);
+        true
+    }
+
+    pub fn get_index(&self, name: &str) -> Option<&SearchIndex> {
+        self.indexes.get(name)
+    }
+
+    pub fn drop_index(&mut self, name: &str) -> Option<SearchIndex> {
+        self.indexes.remove(name)
+    }
+
+    // One SCAN step over the keyspace; see ScanIndex for the cursor's guarantees
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<String>, u64) {
+        self.scan.scan(cursor, count)
+    }
+
+    pub fn get_bytes(&self, key: &str) -> Result<Option<Cow<'_, [u8]>>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::String(bytes)) => Ok(Some(Cow::Borrowed(bytes))),
+            Some(Value::Integer(value)) => Ok(Some(Cow::Owned(value.to_string().into_bytes()))),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_bytes_mut(&mut self, key: &str) -> Result<Option<&mut Vec<u8>>, WrongType> {
+        match self.data.get_mut(key).map(decode_integer) {
+            Some(Value::String(bytes)) => Ok(Some(bytes)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_bytes(&mut self, key: &str) -> Result<&mut Vec<u8>, WrongType> {
+        let value = self.entry(key, || Value::String(Vec::new()));
+        match decode_integer(value) {
+            Value::String(bytes) => Ok(bytes),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_integer(&self, key: &str) -> Result<Option<i64>, NumberError> {
+        match self.data.get(key) {
+            Some(Value::Integer(value)) => Ok(Some(*value)),
+            Some(Value::String(bytes)) => parse_integer(bytes)
+                .map(Some)
+                .ok_or(NumberError::NotANumber),
+            Some(_) => Err(NumberError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_float(&self, key: &str) -> Result<Option<f64>, NumberError> {
+        match self.data.get(key) {
+            Some(Value::Integer(value)) => Ok(Some(*value as f64)),
+            Some(Value::String(bytes)) => std::str::from_utf8(bytes)
+                .ok()
+                .filter(|text| !text.starts_with(char::is_whitespace))
+                .and_then(|text| text.parse::<f64>().ok())
+                .filter(|value| !value.is_nan())
+                .map(Some)
+                .ok_or(NumberError::NotANumber),
+            Some(_) => Err(NumberError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_hash(&self, key: &str) -> Result<Option<&Hash>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_hash_mut(&mut self, key: &str) -> Result<Option<&mut Hash>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_hash(&mut self, key: &str) -> Result<&mut Hash, WrongType> {
+        match self.entry(key, || Value::Hash(Hash::new())) {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_set(&self, key: &str) -> Result<Option<&SetValue>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_set_mut(&mut self, key: &str) -> Result<Option<&mut SetValue>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_set(&mut self, key: &str) -> Result<&mut SetValue, WrongType> {
+        match self.entry(key, || Value::Set(SetValue::new())) {
+            Value::Set(set) => Ok(set),
+            _ => Err(WrongType),
+        }
     }
 
-    pub fn delete(&mut self, key: &str) -> Option<String> {
-        self.data.remove(key)
+    pub fn get_zset(&self, key: &str) -> Result<Option<&SortedSet>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::ZSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_zset_mut(&mut self, key: &str) -> Result<Option<&mut SortedSet>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::ZSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_zset(&mut self, key: &str) -> Result<&mut SortedSet, WrongType> {
+        match self.entry(key, || Value::ZSet(SortedSet::new())) {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_stream(&self, key: &str) -> Result<Option<&Stream>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_stream_mut(&mut self, key: &str) -> Result<Option<&mut Stream>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_stream(&mut self, key: &str) -> Result<&mut Stream, WrongType> {
+        match self.entry(key, || Value::Stream(Stream::new())) {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_json(&self, key: &str) -> Result<Option<&serde_json::Value>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Json(document)) => Ok(Some(document)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_json_mut(&mut self, key: &str) -> Result<Option<&mut serde_json::Value>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Json(document)) => Ok(Some(document)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_bloom(&self, key: &str) -> Result<Option<&BloomFilter>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Bloom(filter)) => Ok(Some(filter)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    // Filters created implicitly by BF.ADD and BF.MADD use the default parameters
+    pub fn get_or_create_bloom(&mut self, key: &str) -> Result<&mut BloomFilter, WrongType> {
+        match self.entry(key, || {
+            Value::Bloom(
+                BloomFilter::new(
+                    bloom::DEFAULT_ERROR_RATE,
+                    bloom::DEFAULT_CAPACITY,
+                    Some(bloom::DEFAULT_EXPANSION),
+                )
+                .expect("the default parameters fit in one layer"),
+            )
+        }) {
+            Value::Bloom(filter) => Ok(filter),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_cuckoo(&self, key: &str) -> Result<Option<&CuckooFilter>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Cuckoo(filter)) => Ok(Some(filter)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_cuckoo_mut(&mut self, key: &str) -> Result<Option<&mut CuckooFilter>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Cuckoo(filter)) => Ok(Some(filter)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_cuckoo(&mut self, key: &str) -> Result<&mut CuckooFilter, WrongType> {
+        match self.entry(key, || {
+            Value::Cuckoo(CuckooFilter::new(cuckoo::DEFAULT_CAPACITY))
+        }) {
+            Value::Cuckoo(filter) => Ok(filter),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_cms(&self, key: &str) -> Result<Option<&CountMinSketch>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Cms(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_cms_mut(&mut self, key: &str) -> Result<Option<&mut CountMinSketch>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Cms(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_topk(&self, key: &str) -> Result<Option<&TopK>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::TopK(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_topk_mut(&mut self, key: &str) -> Result<Option<&mut TopK>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::TopK(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_tdigest(&self, key: &str) -> Result<Option<&TDigest>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::TDigest(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_tdigest_mut(&mut self, key: &str) -> Result<Option<&mut TDigest>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::TDigest(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_timeseries(&self, key: &str) -> Result<Option<&TimeSeries>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::TimeSeries(series)) => Ok(Some(series)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_timeseries_mut(&mut self, key: &str) -> Result<Option<&mut TimeSeries>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::TimeSeries(series)) => Ok(Some(series)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_suggestions(&self, key: &str) -> Result<Option<&SuggestionTrie>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::Suggestions(trie)) => Ok(Some(trie)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_suggestions_mut(
+        &mut self,
+        key: &str,
+    ) -> Result<Option<&mut SuggestionTrie>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::Suggestions(trie)) => Ok(Some(trie)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_suggestions(
+        &mut self,
+        key: &str,
+    ) -> Result<&mut SuggestionTrie, WrongType> {
+        match self.entry(key, || Value::Suggestions(SuggestionTrie::default())) {
+            Value::Suggestions(trie) => Ok(trie),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn get_vectorset(&self, key: &str) -> Result<Option<&VectorSet>, WrongType> {
+        match self.data.get(key) {
+            Some(Value::VectorSet(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_vectorset_mut(&mut self, key: &str) -> Result<Option<&mut VectorSet>, WrongType> {
+        match self.data.get_mut(key) {
+            Some(Value::VectorSet(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
     }
 }
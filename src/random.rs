@@ -0,0 +1,56 @@
+use std::{
+    cell::Cell,
+    collections::{hash_map::RandomState, HashSet},
+    hash::{BuildHasher, Hasher},
+};
+
+thread_local! {
+    // Per-thread xorshift state, seeded from the std hasher's random keys
+    static STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+}
+
+pub fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        x
+    })
+}
+
+// Uniform index in 0..n (n must be non-zero)
+pub fn below(n: usize) -> usize {
+    (next_u64() % n as u64) as usize
+}
+
+// Uniform float in [0, 1)
+pub fn unit() -> f64 {
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// `count` distinct indices in 0..n, in random order. Small counts are drawn until enough
+// distinct ones come up, as Redis does; larger ones shuffle 0..n instead, which costs no
+// more than the reply they go into.
+pub fn distinct(n: usize, count: usize) -> Vec<usize> {
+    let count = count.min(n);
+    if count.saturating_mul(3) <= n {
+        let mut picked = HashSet::with_capacity(count);
+        let mut indices = Vec::with_capacity(count);
+        while indices.len() < count {
+            let index = below(n);
+            if picked.insert(index) {
+                indices.push(index);
+            }
+        }
+        return indices;
+    }
+    let mut indices: Vec<usize> = (0..n).collect();
+    for i in 0..count {
+        let j = i + below(n - i);
+        indices.swap(i, j);
+    }
+    indices.truncate(count);
+    indices
+}
@@ -9,42 +9,53 @@ use nom::{
     sequence::{preceded, terminated},
     IResult, Parser,
 };
-use std::fmt;
 
 #[derive(Debug, Clone)]
 pub enum Resp {
     SimpleString(String),
     SimpleError(String),
     Integer(i64),
-    BulkString(Option<String>),
+    // Bulk strings are binary safe, so they carry raw bytes
+    BulkString(Option<Vec<u8>>),
     Array(Vec<Resp>),
+    // The null reply to commands whose answer is otherwise an array, such as a blocking
+    // pop that timed out
+    NullArray,
 }
 
-pub fn parse_simple_string(input: &str) -> IResult<&str, Resp> {
-    map(preceded(tag("+"), is_not("\r\n")), |s: &str| {
-        Resp::SimpleString(s.to_string())
+pub fn parse_simple_string(input: &[u8]) -> IResult<&[u8], Resp> {
+    map(preceded(tag("+"), is_not("\r\n")), |s: &[u8]| {
+        Resp::SimpleString(String::from_utf8_lossy(s).into_owned())
     })
     .parse(input)
 }
 
-pub fn parse_simple_error(input: &str) -> IResult<&str, Resp> {
-    map(preceded(tag("-"), is_not("\r\n")), |s: &str| {
-        Resp::SimpleError(s.to_string())
+pub fn parse_simple_error(input: &[u8]) -> IResult<&[u8], Resp> {
+    map(preceded(tag("-"), is_not("\r\n")), |s: &[u8]| {
+        Resp::SimpleError(String::from_utf8_lossy(s).into_owned())
     })
     .parse(input)
 }
 
-pub fn parse_integer(input: &str) -> IResult<&str, Resp> {
+pub fn parse_integer(input: &[u8]) -> IResult<&[u8], Resp> {
     map(preceded(tag(":"), terminated(i64, tag("\r\n"))), |s| {
         Resp::Integer(s)
     })
     .parse(input)
 }
 
-pub fn parse_array(input: &str) -> IResult<&str, Resp> {
+// Lengths that don't parse count as zero
+fn parse_length<T: std::str::FromStr + Default>(input: &[u8]) -> T {
+    std::str::from_utf8(input)
+        .ok()
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_default()
+}
+
+pub fn parse_array(input: &[u8]) -> IResult<&[u8], Resp> {
     let (input, _) = tag("*").parse(input)?;
     let (input, length_str) = take_until("\r\n").parse(input)?;
-    let length: i32 = length_str.parse().unwrap_or(0);
+    let length: i32 = parse_length(length_str);
     let (input, _) = tag("\r\n").parse(input)?;
     let mut remaining_input = input;
     let mut v: Vec<Resp> = Vec::new();
@@ -56,10 +67,10 @@ pub fn parse_array(input: &str) -> IResult<&str, Resp> {
     Ok((remaining_input, Resp::Array(v)))
 }
 
-pub fn parse_bulk_string(input: &str) -> IResult<&str, Resp> {
+pub fn parse_bulk_string(input: &[u8]) -> IResult<&[u8], Resp> {
     let (input, _) = tag("$")(input)?;
     let (input, length_str) = take_until("\r\n")(input)?;
-    let length: i64 = length_str.parse().unwrap_or(0);
+    let length: i64 = parse_length(length_str);
     let (input, _) = tag("\r\n")(input)?;
 
     if length == -1 {
@@ -69,11 +80,65 @@ pub fn parse_bulk_string(input: &str) -> IResult<&str, Resp> {
 
     let (input, _) = tag("\r\n")(input)?;
 
-    Ok((input, Resp::BulkString(Some(content.to_string()))))
+    Ok((input, Resp::BulkString(Some(content.to_vec()))))
 }
 
-pub fn parse_resp(input: &str) -> IResult<&str, Resp> {
-    let (input, _) = take_while(|c| c == ' ' || c == '\r' || c == '\n').parse(input)?;
+// Bulk strings longer than Redis' default proto-max-bulk-len are rejected outright
+const MAX_BULK_LENGTH: i64 = 512 * 1024 * 1024;
+
+// The end of the frame starting at `at`, or None while part of it has yet to arrive
+fn frame_end(input: &[u8], at: usize) -> Result<Option<usize>, ()> {
+    let Some(&kind) = input.get(at) else {
+        return Ok(None);
+    };
+    let Some(line_end) = input[at..].windows(2).position(|w| w == b"\r\n") else {
+        return Ok(None);
+    };
+    let header = &input[at + 1..at + line_end];
+    let next = at + line_end + 2;
+    let length = || {
+        std::str::from_utf8(header)
+            .ok()
+            .and_then(|length| length.parse::<i64>().ok())
+            .ok_or(())
+    };
+    match kind {
+        b'+' | b'-' | b':' => Ok(Some(next)),
+        b'$' => match length()? {
+            length if length > MAX_BULK_LENGTH => Err(()),
+            length if length < 0 => Ok(Some(next)),
+            length => {
+                let end = next + length as usize + 2;
+                Ok((input.len() >= end).then_some(end))
+            }
+        },
+        b'*' => {
+            let mut end = next;
+            for _ in 0..length()?.max(0) {
+                match frame_end(input, end)? {
+                    Some(next) => end = next,
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some(end))
+        }
+        _ => Err(()),
+    }
+}
+
+// How many bytes of `input` the first frame spans, counting the separators before it
+// that parse_resp skips. A frame may arrive over several reads, so Ok(None) means more
+// input is needed; Err means the input isn't RESP at all.
+pub fn frame_length(input: &[u8]) -> Result<Option<usize>, ()> {
+    let start = input
+        .iter()
+        .position(|c| !matches!(c, b' ' | b'\r' | b'\n'))
+        .unwrap_or(input.len());
+    frame_end(input, start)
+}
+
+pub fn parse_resp(input: &[u8]) -> IResult<&[u8], Resp> {
+    let (input, _) = take_while(|c| c == b' ' || c == b'\r' || c == b'\n').parse(input)?;
 
     let (input, result) = alt((
         parse_simple_string,
@@ -87,21 +152,26 @@ pub fn parse_resp(input: &str) -> IResult<&str, Resp> {
     Ok((input, result))
 }
 
-impl fmt::Display for Resp {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Resp {
+    // Appends the wire encoding of this value to `out`
+    pub fn encode(&self, out: &mut Vec<u8>) {
         match self {
-            Resp::SimpleString(s) => write!(f, "+{}\r\n", s),
-            Resp::SimpleError(s) => write!(f, "-{}\r\n", s),
-            Resp::Integer(i) => write!(f, ":{}\r\n", i),
-            Resp::BulkString(Some(s)) => write!(f, "${}\r\n{}\r\n", s.len(), s),
-            Resp::BulkString(None) => write!(f, "$-1\r\n"),
+            Resp::SimpleString(s) => out.extend_from_slice(format!("+{}\r\n", s).as_bytes()),
+            Resp::SimpleError(s) => out.extend_from_slice(format!("-{}\r\n", s).as_bytes()),
+            Resp::Integer(i) => out.extend_from_slice(format!(":{}\r\n", i).as_bytes()),
+            Resp::BulkString(Some(s)) => {
+                out.extend_from_slice(format!("${}\r\n", s.len()).as_bytes());
+                out.extend_from_slice(s);
+                out.extend_from_slice(b"\r\n");
+            }
+            Resp::BulkString(None) => out.extend_from_slice(b"$-1\r\n"),
             Resp::Array(arr) => {
-                write!(f, "*{}\r\n", arr.len())?;
+                out.extend_from_slice(format!("*{}\r\n", arr.len()).as_bytes());
                 for item in arr {
-                    write!(f, "{}", item)?;
+                    item.encode(out);
                 }
-                Ok(())
             }
+            Resp::NullArray => out.extend_from_slice(b"*-1\r\n"),
         }
     }
 }
@@ -7,11 +7,12 @@ use std::{
 };
 
 use crate::{
-    commands::command::SharedState,
+    blocking::BlockingManager,
+    commands::command::{expire_parts, SharedState},
     commands::{send_error, CommandContext, SharedRegistry},
     expiry_manager,
     memory::Memory,
-    resp::{parse_resp, Resp},
+    resp::{frame_length, parse_resp, Resp},
 };
 
 pub struct Server {
@@ -19,6 +20,7 @@ pub struct Server {
     memory: Arc<Mutex<Memory>>,
     command_registry: SharedRegistry,
     expiry_manager: Arc<Mutex<expiry_manager::ExpiryManager>>,
+    blocking: Arc<BlockingManager>,
 }
 
 impl Server {
@@ -27,6 +29,7 @@ impl Server {
         memory: Arc<Mutex<Memory>>,
         command_registry: SharedRegistry,
         expiry_manager: Arc<Mutex<expiry_manager::ExpiryManager>>,
+        blocking: Arc<BlockingManager>,
     ) -> Result<Self, std::io::Error> {
         let listener = TcpListener::bind(address)?;
         Ok(Server {
@@ -34,6 +37,7 @@ impl Server {
             memory,
             command_registry,
             expiry_manager,
+            blocking,
         })
     }
 
@@ -54,6 +58,9 @@ impl Server {
                         println!("deleted key: {}", key);
                         memory.delete(key);
                     });
+                    for key in expiry_manager.take_expired_parts() {
+                        expire_parts(&mut memory, &mut expiry_manager, &key);
+                    }
                 }
                 thread::sleep(Duration::from_millis(100)); // Run cleanup every 100ms
             }
@@ -65,8 +72,9 @@ impl Server {
                     let memory = self.memory.clone();
                     let registry = self.command_registry.clone();
                     let expiry_manager = self.expiry_manager.clone();
+                    let blocking = self.blocking.clone();
                     std::thread::spawn(move || {
-                        handle_client(memory, registry, tcp_stream, expiry_manager)
+                        handle_client(memory, registry, tcp_stream, expiry_manager, blocking)
                     });
                 }
                 Err(e) => eprintln!("Connection error: {}", e),
@@ -80,15 +88,20 @@ fn handle_client(
     registry: SharedRegistry,
     mut stream: TcpStream,
     expiry_manager: Arc<Mutex<crate::expiry_manager::ExpiryManager>>,
+    blocking: Arc<BlockingManager>,
 ) {
     let state = Arc::new(SharedState {
         memory,
         expiry_manager,
+        blocking,
     });
 
     println!("Accepted new connection");
 
-    let mut buf = [0; 1024];
+    let mut buf = [0; 4096];
+    // Bytes read but not yet handled: the tail of a frame still arriving, or frames
+    // pipelined behind the one being run
+    let mut pending: Vec<u8> = Vec::new();
     let client_addr = stream
         .peer_addr()
         .unwrap_or_else(|_| "unknown".parse().unwrap());
@@ -106,37 +119,53 @@ fn handle_client(
                 return;
             }
         };
+        pending.extend_from_slice(&buf[..bytes_read]);
 
-        let input = String::from_utf8_lossy(&buf[..bytes_read]);
-        let command = parse_resp(&input);
-
-        match command {
-            Ok((_, Resp::Array(arr))) => {
-                if let Some(Resp::BulkString(Some(cmd))) = arr.first() {
-                    let registry = registry.lock().unwrap();
-                    if let Some(command) = registry.get_command(cmd) {
-                        let mut context = CommandContext {
-                            stream: stream.try_clone().unwrap(),
-                            state: state.clone(), // Use Arc to share the state
-                        };
-                        command.execute(&arr[1..], &mut context);
-                    } else {
-                        send_error(&mut stream, &format!("unknown command '{}'", cmd));
-                    }
-                } else {
-                    send_error(&mut stream, "invalid command format");
+        loop {
+            match frame_length(&pending) {
+                Ok(Some(length)) => {
+                    let frame: Vec<u8> = pending.drain(..length).collect();
+                    run_frame(&frame, &registry, &mut stream, &state);
                 }
-            }
-            Ok((remaining, _)) => {
-                if !remaining.is_empty() {
-                    eprintln!("Unprocessed input remaining: {:?}", remaining);
-                    send_error(&mut stream, "unprocessed input remaining");
+                Ok(None) => break,
+                Err(()) => {
+                    eprintln!("Error parsing input: {:?}", pending);
+                    send_error(&mut stream, "invalid input");
+                    pending.clear();
+                    break;
                 }
             }
-            Err(e) => {
-                eprintln!("Error parsing input: {:?}", e);
-                send_error(&mut stream, "invalid input");
+        }
+    }
+}
+
+fn run_frame(
+    frame: &[u8],
+    registry: &SharedRegistry,
+    stream: &mut TcpStream,
+    state: &Arc<SharedState>,
+) {
+    match parse_resp(frame) {
+        Ok((_, Resp::Array(arr))) => {
+            if let Some(Resp::BulkString(Some(cmd))) = arr.first() {
+                let cmd = String::from_utf8_lossy(cmd);
+                if let Some(command) = registry.get_command(&cmd) {
+                    let mut context = CommandContext {
+                        stream: stream.try_clone().unwrap(),
+                        state: state.clone(), // Use Arc to share the state
+                    };
+                    command.execute(&arr[1..], &mut context);
+                } else {
+                    send_error(stream, &format!("unknown command '{}'", cmd));
+                }
+            } else {
+                send_error(stream, "invalid command format");
             }
         }
+        Ok(_) => send_error(stream, "invalid command format"),
+        Err(e) => {
+            eprintln!("Error parsing input: {:?}", e);
+            send_error(stream, "invalid input");
+        }
     }
 }
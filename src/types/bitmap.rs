@@ -0,0 +1,180 @@
+// Bit addressing follows Redis: bit 0 is the most significant bit of the first byte
+
+pub const INVALID_OFFSET: &str = "bit offset is not an integer or out of range";
+
+// Strings are capped at 512MB, so bit offsets must fit in 32 bits
+pub const MAX_BIT_OFFSET: u64 = (512 * 1024 * 1024 * 8) - 1;
+
+pub fn parse_offset(input: &str) -> Result<u64, &'static str> {
+    input
+        .parse::<u64>()
+        .ok()
+        .filter(|&offset| offset <= MAX_BIT_OFFSET)
+        .ok_or(INVALID_OFFSET)
+}
+
+pub fn get_bit(bytes: &[u8], offset: u64) -> u8 {
+    match bytes.get((offset / 8) as usize) {
+        Some(byte) => (byte >> (7 - offset % 8)) & 1,
+        None => 0,
+    }
+}
+
+// Sets a bit, zero-padding the string as needed, and returns the previous value
+pub fn set_bit(bytes: &mut Vec<u8>, offset: u64, bit: u8) -> u8 {
+    let index = (offset / 8) as usize;
+    if index >= bytes.len() {
+        bytes.resize(index + 1, 0);
+    }
+    let mask = 1 << (7 - offset % 8);
+    let previous = (bytes[index] & mask != 0) as u8;
+    if bit == 1 {
+        bytes[index] |= mask;
+    } else {
+        bytes[index] &= !mask;
+    }
+    previous
+}
+
+// Population count a word at a time
+pub fn count_ones(bytes: &[u8]) -> u64 {
+    let mut words = bytes.chunks_exact(8);
+    let count: u64 = words
+        .by_ref()
+        .map(|word| u64::from_ne_bytes(word.try_into().unwrap()).count_ones() as u64)
+        .sum();
+    count
+        + words
+            .remainder()
+            .iter()
+            .map(|byte| byte.count_ones() as u64)
+            .sum::<u64>()
+}
+
+// Set bits within the inclusive bit range `start..=end`, which must lie inside `bytes`
+pub fn count_bits(bytes: &[u8], start: u64, end: u64) -> u64 {
+    let (first, last) = ((start / 8) as usize, (end / 8) as usize);
+    // Discount the bits of the edge bytes that fall outside the range
+    let head = bytes[first] & !(0xFF >> (start % 8));
+    let tail = bytes[last] & (0xFFu16 >> (end % 8 + 1)) as u8;
+    count_ones(&bytes[first..=last]) - head.count_ones() as u64 - tail.count_ones() as u64
+}
+
+// First bit equal to `bit` within the inclusive bit range `start..=end`
+pub fn find_bit(bytes: &[u8], bit: u8, start: u64, end: u64) -> Option<u64> {
+    let skip = if bit == 1 { 0x00 } else { 0xFF };
+    let mut offset = start;
+    while offset <= end {
+        let byte = bytes[(offset / 8) as usize];
+        // Whole bytes that can't contain a match are stepped over at once
+        if offset.is_multiple_of(8) && offset + 7 <= end && byte == skip {
+            offset += 8;
+            continue;
+        }
+        if (byte >> (7 - offset % 8)) & 1 == bit {
+            return Some(offset);
+        }
+        offset += 1;
+    }
+    None
+}
+
+#[derive(Clone, Copy)]
+pub enum Overflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+impl Overflow {
+    pub fn parse(input: &str) -> Result<Self, &'static str> {
+        match input.to_ascii_uppercase().as_str() {
+            "WRAP" => Ok(Overflow::Wrap),
+            "SAT" => Ok(Overflow::Sat),
+            "FAIL" => Ok(Overflow::Fail),
+            _ => Err("Invalid OVERFLOW type specified"),
+        }
+    }
+}
+
+// A BITFIELD integer type such as `i16` or `u8`
+#[derive(Clone, Copy)]
+pub struct BitfieldType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+impl BitfieldType {
+    pub fn parse(input: &str) -> Result<Self, &'static str> {
+        let invalid =
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.";
+        let signed = match input.as_bytes().first() {
+            Some(b'i' | b'I') => true,
+            Some(b'u' | b'U') => false,
+            _ => return Err(invalid),
+        };
+        let max_bits = if signed { 64 } else { 63 };
+        let bits = input[1..]
+            .parse::<u32>()
+            .ok()
+            .filter(|bits| (1..=max_bits).contains(bits))
+            .ok_or(invalid)?;
+        Ok(BitfieldType { signed, bits })
+    }
+
+    fn min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    fn max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    pub fn read(&self, bytes: &[u8], offset: u64) -> i64 {
+        let raw = (0..self.bits as u64).fold(0u64, |value, i| {
+            (value << 1) | get_bit(bytes, offset + i) as u64
+        });
+        // Sign-extend negative values of narrow signed types
+        if self.signed && self.bits < 64 && (raw >> (self.bits - 1)) & 1 == 1 {
+            (raw | (u64::MAX << self.bits)) as i64
+        } else {
+            raw as i64
+        }
+    }
+
+    // Stores the low `bits` bits of `value`
+    pub fn write(&self, bytes: &mut Vec<u8>, offset: u64, value: i64) {
+        let bits = self.bits as u64;
+        for i in 0..bits {
+            let bit = ((value as u64) >> (bits - 1 - i)) & 1;
+            set_bit(bytes, offset + i, bit as u8);
+        }
+    }
+
+    // Brings `value` into range according to `overflow`; None when FAIL rejects it
+    pub fn fit(&self, value: i128, overflow: Overflow) -> Option<i64> {
+        if (self.min()..=self.max()).contains(&value) {
+            return Some(value as i64);
+        }
+        match overflow {
+            Overflow::Wrap => {
+                let wrapped = value & ((1 << self.bits) - 1);
+                if wrapped > self.max() {
+                    Some((wrapped - (1 << self.bits)) as i64)
+                } else {
+                    Some(wrapped as i64)
+                }
+            }
+            Overflow::Sat => Some(value.clamp(self.min(), self.max()) as i64),
+            Overflow::Fail => None,
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Scalable Bloom filter in the style of RedisBloom: a stack of plain Bloom filters where
+// each new layer is `expansion` times larger and twice as strict, so the overall error
+// rate stays within the requested bound however many items are added.
+
+use crate::types::hyperloglog::murmurhash64a;
+
+pub const DEFAULT_ERROR_RATE: f64 = 0.01;
+pub const DEFAULT_CAPACITY: u64 = 100;
+pub const DEFAULT_EXPANSION: u64 = 2;
+// The bounds RedisBloom puts on BF.RESERVE
+pub const MAX_CAPACITY: u64 = 1 << 30;
+pub const MAX_EXPANSION: u64 = 32768;
+
+// No layer gets a bit array past 512MB, however large or strict it is asked to be
+const MAX_LAYER_BITS: u64 = 1 << 32;
+
+// Each layer halves the error rate of the one before it
+const TIGHTENING_RATIO: f64 = 0.5;
+
+#[derive(Clone)]
+struct Layer {
+    bits: Vec<u64>,
+    num_bits: u64,
+    hashes: u32,
+    capacity: u64,
+    count: u64,
+}
+
+impl Layer {
+    // None when the bit array would be larger than MAX_LAYER_BITS
+    fn new(capacity: u64, error_rate: f64) -> Option<Self> {
+        let ln2 = std::f64::consts::LN_2;
+        let bits_per_entry = -error_rate.ln() / (ln2 * ln2);
+        let num_bits = (capacity as f64 * bits_per_entry).ceil();
+        if num_bits > MAX_LAYER_BITS as f64 {
+            return None;
+        }
+        let num_bits = (num_bits as u64).max(64);
+        Some(Layer {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            hashes: (ln2 * bits_per_entry).ceil() as u32,
+            capacity,
+            count: 0,
+        })
+    }
+
+    // Double hashing: the i-th position is h1 + i * h2
+    fn positions(&self, (h1, h2): (u64, u64)) -> impl Iterator<Item = u64> + '_ {
+        (0..self.hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits)
+    }
+
+    fn contains(&self, hash: (u64, u64)) -> bool {
+        self.positions(hash)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, hash: (u64, u64)) {
+        let positions: Vec<u64> = self.positions(hash).collect();
+        for bit in positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.count += 1;
+    }
+}
+
+fn hash(item: &[u8]) -> (u64, u64) {
+    let h1 = murmurhash64a(item, 0xc6a4a7935bd1e995);
+    (h1, murmurhash64a(item, h1))
+}
+
+#[derive(Clone)]
+pub struct BloomFilter {
+    layers: Vec<Layer>,
+    error_rate: f64,
+    // None for non-scaling filters, which refuse items once full
+    pub expansion: Option<u64>,
+}
+
+// Returned by `add` when the filter can't take another item
+pub enum Full {
+    // A non-scaling filter has no room left
+    NonScaling,
+    // The next layer would be larger than MAX_LAYER_BITS
+    MaxExpansions,
+}
+
+impl BloomFilter {
+    // None when the first layer alone would be larger than MAX_LAYER_BITS
+    pub fn new(error_rate: f64, capacity: u64, expansion: Option<u64>) -> Option<Self> {
+        Some(BloomFilter {
+            layers: vec![Layer::new(capacity, error_rate * TIGHTENING_RATIO)?],
+            error_rate,
+            expansion,
+        })
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        let hash = hash(item);
+        self.layers.iter().any(|layer| layer.contains(hash))
+    }
+
+    // Adds an item, returning false if it was (probably) already present
+    pub fn add(&mut self, item: &[u8]) -> Result<bool, Full> {
+        let hash = hash(item);
+        if self.layers.iter().any(|layer| layer.contains(hash)) {
+            return Ok(false);
+        }
+        let last = self.layers.last().unwrap();
+        if last.count >= last.capacity {
+            let expansion = self.expansion.ok_or(Full::NonScaling)?;
+            let error_rate = self.error_rate * TIGHTENING_RATIO.powi(self.layers.len() as i32 + 1);
+            let layer = last
+                .capacity
+                .checked_mul(expansion)
+                .and_then(|capacity| Layer::new(capacity, error_rate))
+                .ok_or(Full::MaxExpansions)?;
+            self.layers.push(layer);
+        }
+        self.layers.last_mut().unwrap().insert(hash);
+        Ok(true)
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.layers.iter().map(|layer| layer.capacity).sum()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.layers.iter().map(|layer| layer.count).sum()
+    }
+
+    pub fn layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    // Bytes used by the bit arrays
+    pub fn size(&self) -> usize {
+        self.layers.iter().map(|layer| layer.bits.len() * 8).sum()
+    }
+}
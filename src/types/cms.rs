@@ -0,0 +1,86 @@
+// Count-Min Sketch: `depth` rows of `width` counters, one hash per row. An item's count
+// is the smallest of its counters, which can overestimate but never underestimate.
+
+use crate::types::hyperloglog::murmurhash64a;
+
+// Keeps a sketch's counters within 512MB
+const MAX_COUNTERS: usize = 1 << 26;
+
+// Counters saturate at the largest count a RESP integer can carry
+const MAX_COUNT: u64 = i64::MAX as u64;
+
+#[derive(Clone)]
+pub struct CountMinSketch {
+    pub width: usize,
+    pub depth: usize,
+    pub total: u64,
+    counters: Vec<u64>,
+}
+
+impl CountMinSketch {
+    // None when width * depth counters would exceed MAX_COUNTERS
+    pub fn new(width: usize, depth: usize) -> Option<Self> {
+        let cells = width
+            .checked_mul(depth)
+            .filter(|&cells| cells <= MAX_COUNTERS)?;
+        Some(CountMinSketch {
+            width,
+            depth,
+            total: 0,
+            counters: vec![0; cells],
+        })
+    }
+
+    // Dimensions giving estimates within `error * total` with probability `1 - probability`
+    pub fn dimensions_for(error: f64, probability: f64) -> (usize, usize) {
+        let width = (2.0 / error).ceil() as usize;
+        let depth = (probability.ln() / 0.5f64.ln()).ceil() as usize;
+        (width, depth.max(1))
+    }
+
+    // One counter index per row
+    fn cells(&self, item: &[u8]) -> Vec<usize> {
+        (0..self.depth)
+            .map(|row| {
+                row * self.width + (murmurhash64a(item, row as u64) % self.width as u64) as usize
+            })
+            .collect()
+    }
+
+    // Adds `increment` to the item's counters and returns its new estimate
+    pub fn increment(&mut self, item: &[u8], increment: u64) -> u64 {
+        let cells = self.cells(item);
+        for &cell in &cells {
+            self.counters[cell] = self.counters[cell].saturating_add(increment).min(MAX_COUNT);
+        }
+        self.total = self.total.saturating_add(increment).min(MAX_COUNT);
+        cells
+            .iter()
+            .map(|&cell| self.counters[cell])
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn query(&self, item: &[u8]) -> u64 {
+        self.cells(item)
+            .into_iter()
+            .map(|cell| self.counters[cell])
+            .min()
+            .unwrap_or(0)
+    }
+
+    // Overwrites this sketch with the weighted sum of `sources`, which share its dimensions.
+    // Sums are taken in i128 and clamped, so saturated counters stay saturated.
+    pub fn merge(&mut self, sources: &[(&CountMinSketch, i64)]) {
+        let weighted_sum = |count: &dyn Fn(&CountMinSketch) -> u64| {
+            let sum = sources.iter().fold(0i128, |sum, (source, weight)| {
+                sum.saturating_add((count(source) as i128).saturating_mul(*weight as i128))
+            });
+            sum.clamp(0, MAX_COUNT as i128) as u64
+        };
+        for i in 0..self.counters.len() {
+            self.counters[i] = weighted_sum(&|source| source.counters[i]);
+        }
+        self.total = weighted_sum(&|source| source.total);
+    }
+}
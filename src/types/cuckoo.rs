@@ -0,0 +1,179 @@
+// Cuckoo filter in the style of RedisBloom: 8-bit fingerprints in buckets of two slots,
+// where each item may live in one of two buckets. Unlike a Bloom filter, items can be
+// deleted and counted. When evictions can't make room, a new layer is added.
+
+use crate::types::hyperloglog::murmurhash64a;
+
+pub const DEFAULT_CAPACITY: u64 = 1024;
+const BUCKET_SIZE: usize = 2;
+const MAX_ITERATIONS: usize = 20;
+const EMPTY: u8 = 0;
+// Layers a filter may grow to before CF.ADD reports it full
+const MAX_LAYERS: usize = 32;
+
+// An item's fingerprint and the unreduced hashes of its two candidate buckets
+#[derive(Clone, Copy)]
+struct Lookup {
+    fingerprint: u8,
+    h1: u64,
+    h2: u64,
+}
+
+fn alt_hash(fingerprint: u8, hash: u64) -> u64 {
+    hash ^ (fingerprint as u64).wrapping_mul(0x5bd1e995)
+}
+
+fn lookup(item: &[u8]) -> Lookup {
+    let hash = murmurhash64a(item, 0);
+    let fingerprint = (hash % 255 + 1) as u8;
+    Lookup {
+        fingerprint,
+        h1: hash,
+        h2: alt_hash(fingerprint, hash),
+    }
+}
+
+#[derive(Clone)]
+struct Layer {
+    // Always a power of two, so `hash & mask` agrees with the XOR used to find alternates
+    buckets: Vec<[u8; BUCKET_SIZE]>,
+}
+
+impl Layer {
+    fn new(num_buckets: usize) -> Self {
+        Layer {
+            buckets: vec![[EMPTY; BUCKET_SIZE]; num_buckets],
+        }
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash & (self.buckets.len() as u64 - 1)) as usize
+    }
+
+    fn candidates(&self, lookup: Lookup) -> [usize; 2] {
+        [self.index(lookup.h1), self.index(lookup.h2)]
+    }
+
+    fn insert_free(&mut self, lookup: Lookup) -> bool {
+        for index in self.candidates(lookup) {
+            if let Some(slot) = self.buckets[index].iter_mut().find(|slot| **slot == EMPTY) {
+                *slot = lookup.fingerprint;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Evicts fingerprints to their alternate buckets until one lands in a free slot. If
+    // none does, the swaps are replayed in reverse so the layer is left as it was.
+    fn insert_evicting(&mut self, lookup: Lookup) -> bool {
+        let mut fingerprint = lookup.fingerprint;
+        let mut index = self.index(lookup.h2);
+        let mut swaps = Vec::with_capacity(MAX_ITERATIONS);
+        for iteration in 0..MAX_ITERATIONS {
+            let slot = iteration % BUCKET_SIZE;
+            std::mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+            swaps.push((index, slot));
+            index = self.index(alt_hash(fingerprint, index as u64));
+            if let Some(free) = self.buckets[index].iter_mut().find(|slot| **slot == EMPTY) {
+                *free = fingerprint;
+                return true;
+            }
+        }
+        for (index, slot) in swaps.into_iter().rev() {
+            std::mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+        }
+        false
+    }
+
+    fn count(&self, lookup: Lookup) -> usize {
+        let [i1, i2] = self.candidates(lookup);
+        let occurrences = |index: usize| {
+            self.buckets[index]
+                .iter()
+                .filter(|&&slot| slot == lookup.fingerprint)
+                .count()
+        };
+        // Both hashes can land in the same bucket
+        if i1 == i2 {
+            occurrences(i1)
+        } else {
+            occurrences(i1) + occurrences(i2)
+        }
+    }
+
+    fn remove(&mut self, lookup: Lookup) -> bool {
+        for index in self.candidates(lookup) {
+            if let Some(slot) = self.buckets[index]
+                .iter_mut()
+                .find(|slot| **slot == lookup.fingerprint)
+            {
+                *slot = EMPTY;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[derive(Clone)]
+pub struct CuckooFilter {
+    layers: Vec<Layer>,
+}
+
+// Returned by `add` when no layer can take the item
+pub struct Full;
+
+impl CuckooFilter {
+    pub fn new(capacity: u64) -> Self {
+        let num_buckets = (capacity as usize)
+            .div_ceil(BUCKET_SIZE)
+            .next_power_of_two();
+        CuckooFilter {
+            layers: vec![Layer::new(num_buckets)],
+        }
+    }
+
+    pub fn add(&mut self, item: &[u8]) -> Result<(), Full> {
+        let lookup = lookup(item);
+        if self
+            .layers
+            .iter_mut()
+            .rev()
+            .any(|layer| layer.insert_free(lookup))
+        {
+            return Ok(());
+        }
+
+        // Evict within the newest layer, which is left untouched if that fails
+        if self.layers.last_mut().unwrap().insert_evicting(lookup) {
+            return Ok(());
+        }
+
+        if self.layers.len() >= MAX_LAYERS {
+            return Err(Full);
+        }
+        let mut layer = Layer::new(self.layers[0].buckets.len());
+        layer.insert_free(lookup);
+        self.layers.push(layer);
+        Ok(())
+    }
+
+    pub fn count(&self, item: &[u8]) -> usize {
+        let lookup = lookup(item);
+        self.layers.iter().map(|layer| layer.count(lookup)).sum()
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.count(item) > 0
+    }
+
+    // Removes one occurrence of `item`, newest layers first
+    pub fn remove(&mut self, item: &[u8]) -> bool {
+        let lookup = lookup(item);
+        self.layers
+            .iter_mut()
+            .rev()
+            .any(|layer| layer.remove(lookup))
+    }
+}
@@ -0,0 +1,305 @@
+// Geohash encoding and search areas, ported from Redis' geohash.c and geohash_helper.c.
+// Coordinates are stored as 52-bit interleaved geohashes used as sorted set scores.
+
+const GEO_STEP_MAX: u32 = 26;
+const GEO_LAT_MIN: f64 = -85.05112878;
+const GEO_LAT_MAX: f64 = 85.05112878;
+const GEO_LONG_MIN: f64 = -180.0;
+const GEO_LONG_MAX: f64 = 180.0;
+
+// Earth's quadratic mean radius for WGS-84, the value Redis uses
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct GeoHash {
+    pub bits: u64,
+    pub step: u32,
+}
+
+pub struct Area {
+    pub long_min: f64,
+    pub long_max: f64,
+    pub lat_min: f64,
+    pub lat_max: f64,
+}
+
+// Spreads latitude bits over even positions and longitude bits over odd ones
+fn interleave(lat: u32, long: u32) -> u64 {
+    (0..32).fold(0, |bits, i| {
+        bits | (((lat as u64 >> i) & 1) << (2 * i)) | (((long as u64 >> i) & 1) << (2 * i + 1))
+    })
+}
+
+fn deinterleave(bits: u64) -> (u32, u32) {
+    (0..32).fold((0, 0), |(lat, long), i| {
+        (
+            lat | (((bits >> (2 * i)) & 1) as u32) << i,
+            long | (((bits >> (2 * i + 1)) & 1) as u32) << i,
+        )
+    })
+}
+
+pub fn valid_coordinates(long: f64, lat: f64) -> bool {
+    (GEO_LONG_MIN..=GEO_LONG_MAX).contains(&long) && (GEO_LAT_MIN..=GEO_LAT_MAX).contains(&lat)
+}
+
+fn encode_in(long: f64, lat: f64, step: u32, lat_range: (f64, f64)) -> GeoHash {
+    let scale = (1u64 << step) as f64;
+    let lat_offset = (lat - lat_range.0) / (lat_range.1 - lat_range.0) * scale;
+    let long_offset = (long - GEO_LONG_MIN) / (GEO_LONG_MAX - GEO_LONG_MIN) * scale;
+    GeoHash {
+        bits: interleave(lat_offset as u32, long_offset as u32),
+        step,
+    }
+}
+
+// The caller checks the coordinates with `valid_coordinates` first
+pub fn encode(long: f64, lat: f64, step: u32) -> GeoHash {
+    encode_in(long, lat, step, (GEO_LAT_MIN, GEO_LAT_MAX))
+}
+
+// The sorted set score for a position
+pub fn score(long: f64, lat: f64) -> f64 {
+    encode(long, lat, GEO_STEP_MAX).bits as f64
+}
+
+pub fn decode(hash: GeoHash) -> Area {
+    let (lat, long) = deinterleave(hash.bits);
+    let scale = (1u64 << hash.step) as f64;
+    let lat_scale = GEO_LAT_MAX - GEO_LAT_MIN;
+    let long_scale = GEO_LONG_MAX - GEO_LONG_MIN;
+    Area {
+        lat_min: GEO_LAT_MIN + (lat as f64 / scale) * lat_scale,
+        lat_max: GEO_LAT_MIN + ((lat as f64 + 1.0) / scale) * lat_scale,
+        long_min: GEO_LONG_MIN + (long as f64 / scale) * long_scale,
+        long_max: GEO_LONG_MIN + ((long as f64 + 1.0) / scale) * long_scale,
+    }
+}
+
+// The centre of the cell a score encodes, as (longitude, latitude)
+pub fn position(score: f64) -> (f64, f64) {
+    let area = decode(GeoHash {
+        bits: score as u64,
+        step: GEO_STEP_MAX,
+    });
+    let long = ((area.long_min + area.long_max) / 2.0).clamp(GEO_LONG_MIN, GEO_LONG_MAX);
+    let lat = ((area.lat_min + area.lat_max) / 2.0).clamp(GEO_LAT_MIN, GEO_LAT_MAX);
+    (long, lat)
+}
+
+// The standard 11 character geohash, which uses the full [-90, 90] latitude range
+pub fn geohash_string(score: f64) -> String {
+    const ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+    let (long, lat) = position(score);
+    let hash = encode_in(long, lat, GEO_STEP_MAX, (-90.0, 90.0));
+    (0..11)
+        .map(|i| {
+            // 52 bits only fill ten characters; the last one is always '0'
+            let index = if i == 10 {
+                0
+            } else {
+                (hash.bits >> (52 - (i + 1) * 5)) & 0x1f
+            };
+            ALPHABET[index as usize] as char
+        })
+        .collect()
+}
+
+fn lat_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS_IN_METERS * (lat2.to_radians() - lat1.to_radians()).abs()
+}
+
+// Great-circle distance in meters using the haversine formula
+pub fn distance(long1: f64, lat1: f64, long2: f64, lat2: f64) -> f64 {
+    let (lat1r, lat2r) = (lat1.to_radians(), lat2.to_radians());
+    let v = ((long2.to_radians() - long1.to_radians()) / 2.0).sin();
+    // Same longitude: the distance is along the meridian
+    if v == 0.0 {
+        return lat_distance(lat1, lat2);
+    }
+    let u = ((lat2r - lat1r) / 2.0).sin();
+    let a = u * u + lat1r.cos() * lat2r.cos() * v * v;
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+#[derive(Clone, Copy)]
+pub enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+impl Shape {
+    // Distance from `center` to `point` if the point lies within the shape
+    pub fn contains(&self, center: (f64, f64), point: (f64, f64)) -> Option<f64> {
+        match *self {
+            Shape::Radius(radius) => {
+                let distance = distance(center.0, center.1, point.0, point.1);
+                (distance <= radius).then_some(distance)
+            }
+            Shape::Box { width, height } => {
+                // Latitude distance is cheaper, so check it first
+                if lat_distance(point.1, center.1) > height / 2.0 {
+                    return None;
+                }
+                if distance(point.0, point.1, center.0, point.1) > width / 2.0 {
+                    return None;
+                }
+                Some(distance(center.0, center.1, point.0, point.1))
+            }
+        }
+    }
+
+    // (long_min, lat_min, long_max, lat_max) around `center`
+    fn bounding_box(&self, center: (f64, f64)) -> (f64, f64, f64, f64) {
+        let (long, lat) = center;
+        let (width, height) = match *self {
+            Shape::Radius(radius) => (radius * 2.0, radius * 2.0),
+            Shape::Box { width, height } => (width, height),
+        };
+        let lat_delta = (height / 2.0 / EARTH_RADIUS_IN_METERS).to_degrees();
+        let long_delta_top =
+            (width / 2.0 / EARTH_RADIUS_IN_METERS / (lat + lat_delta).to_radians().cos())
+                .to_degrees();
+        let long_delta_bottom =
+            (width / 2.0 / EARTH_RADIUS_IN_METERS / (lat - lat_delta).to_radians().cos())
+                .to_degrees();
+        // The box is widest at the edge nearest the equator
+        let long_delta = if lat < 0.0 {
+            long_delta_bottom
+        } else {
+            long_delta_top
+        };
+        (
+            long - long_delta,
+            lat - lat_delta,
+            long + long_delta,
+            lat + lat_delta,
+        )
+    }
+
+    fn radius(&self) -> f64 {
+        match *self {
+            Shape::Radius(radius) => radius,
+            Shape::Box { width, height } => ((width / 2.0).powi(2) + (height / 2.0).powi(2)).sqrt(),
+        }
+    }
+}
+
+// The coarsest step whose cells are still larger than the search radius
+fn estimate_step(mut range: f64, lat: f64) -> u32 {
+    if range == 0.0 {
+        return GEO_STEP_MAX;
+    }
+    let mut step: i32 = 1;
+    while range < MERCATOR_MAX {
+        range *= 2.0;
+        step += 1;
+    }
+    // Cells shrink towards the poles, so use coarser ones there
+    step -= 2;
+    if !(-66.0..=66.0).contains(&lat) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&lat) {
+            step -= 1;
+        }
+    }
+    step.clamp(1, GEO_STEP_MAX as i32) as u32
+}
+
+fn moved(hash: GeoHash, dx: i8, dy: i8) -> GeoHash {
+    // Longitude lives in the odd bits and latitude in the even ones
+    fn shift(bits: u64, step: u32, mask: u64, other: u64, d: i8) -> u64 {
+        if d == 0 {
+            return bits;
+        }
+        let mut x = bits & mask;
+        let y = bits & other;
+        let zz = other >> (64 - step * 2);
+        if d > 0 {
+            x = x.wrapping_add(zz + 1);
+        } else {
+            x |= zz;
+            x = x.wrapping_sub(zz + 1);
+        }
+        x &= mask >> (64 - step * 2);
+        x | y
+    }
+    const ODD: u64 = 0xaaaaaaaaaaaaaaaa;
+    const EVEN: u64 = 0x5555555555555555;
+    let bits = shift(hash.bits, hash.step, ODD, EVEN, dx);
+    let bits = shift(bits, hash.step, EVEN, ODD, dy);
+    GeoHash {
+        bits,
+        step: hash.step,
+    }
+}
+
+// Score ranges, as [min, max) pairs, of the cells that may hold matches for `shape`
+pub fn search_ranges(shape: Shape, center: (f64, f64)) -> Vec<(f64, f64)> {
+    let (long_min, lat_min, long_max, lat_max) = shape.bounding_box(center);
+    let mut step = estimate_step(shape.radius(), center.1);
+    let mut hash = encode(center.0, center.1, step);
+    let neighbors = |hash: GeoHash| {
+        // Centre first, then N, S, E, W, NE, NW, SE, SW
+        [
+            (0, 0),
+            (0, 1),
+            (0, -1),
+            (1, 0),
+            (-1, 0),
+            (1, 1),
+            (-1, 1),
+            (1, -1),
+            (-1, -1),
+        ]
+        .map(|(dx, dy)| Some(moved(hash, dx, dy)))
+    };
+    let mut cells = neighbors(hash);
+
+    // If the neighbours don't reach the bounding box edges, use one step coarser
+    let reach_too_short = decode(cells[1].unwrap()).lat_max < lat_max
+        || decode(cells[2].unwrap()).lat_min > lat_min
+        || decode(cells[3].unwrap()).long_max < long_max
+        || decode(cells[4].unwrap()).long_min > long_min;
+    if step > 1 && reach_too_short {
+        step -= 1;
+        hash = encode(center.0, center.1, step);
+        cells = neighbors(hash);
+    }
+
+    // Skip neighbours lying entirely outside the bounding box
+    if step >= 2 {
+        let area = decode(hash);
+        let mut excluded = Vec::new();
+        if area.lat_min < lat_min {
+            excluded.extend([2, 7, 8]);
+        }
+        if area.lat_max > lat_max {
+            excluded.extend([1, 5, 6]);
+        }
+        if area.long_min < long_min {
+            excluded.extend([4, 6, 8]);
+        }
+        if area.long_max > long_max {
+            excluded.extend([3, 5, 7]);
+        }
+        for index in excluded {
+            cells[index] = None;
+        }
+    }
+
+    let mut ranges: Vec<(f64, f64)> = Vec::new();
+    for cell in cells.into_iter().flatten() {
+        // Low steps can make neighbours wrap onto one another
+        let shift = 52 - cell.step * 2;
+        let range = (
+            (cell.bits << shift) as f64,
+            ((cell.bits + 1) << shift) as f64,
+        );
+        if !ranges.contains(&range) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
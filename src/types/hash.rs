@@ -0,0 +1,102 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::types::scan::ScanIndex;
+
+// Hash: fields mapped to binary-safe values. A field may carry its own deadline, in ms
+// since the epoch, after which it is dropped as if deleted.
+#[derive(Clone, Default)]
+pub struct Hash {
+    fields: HashMap<String, Vec<u8>>,
+    expiries: HashMap<String, u64>,
+    // The same deadlines ordered by time, so the soonest is found directly
+    deadlines: BTreeSet<(u64, String)>,
+    order: ScanIndex,
+}
+
+impl Hash {
+    pub fn new() -> Self {
+        Hash::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get(&self, field: &str) -> Option<&[u8]> {
+        self.fields.get(field).map(Vec::as_slice)
+    }
+
+    pub fn contains(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Vec<u8>)> {
+        self.fields.iter()
+    }
+
+    // Sets a field, returning whether it is new; as in Redis, overwriting a field clears
+    // its TTL
+    pub fn insert(&mut self, field: String, value: Vec<u8>) -> bool {
+        self.persist(&field);
+        if !self.fields.contains_key(&field) {
+            self.order.insert(&field);
+        }
+        self.fields.insert(field, value).is_none()
+    }
+
+    pub fn remove(&mut self, field: &str) -> bool {
+        self.persist(field);
+        self.order.remove(field);
+        self.fields.remove(field).is_some()
+    }
+
+    pub fn expiry(&self, field: &str) -> Option<u64> {
+        self.expiries.get(field).copied()
+    }
+
+    pub fn set_expiry(&mut self, field: &str, deadline: u64) {
+        self.persist(field);
+        self.expiries.insert(field.to_string(), deadline);
+        self.deadlines.insert((deadline, field.to_string()));
+    }
+
+    // Clears a field's TTL, returning whether it had one
+    pub fn persist(&mut self, field: &str) -> bool {
+        match self.expiries.remove(field) {
+            Some(deadline) => self.deadlines.remove(&(deadline, field.to_string())),
+            None => false,
+        }
+    }
+
+    // The soonest field deadline
+    pub fn next_expiry(&self) -> Option<u64> {
+        self.deadlines.first().map(|(deadline, _)| *deadline)
+    }
+
+    // Drops the fields whose deadline is at or before `now`
+    pub fn expire_fields(&mut self, now: u64) {
+        while let Some((deadline, field)) = self.deadlines.first().cloned() {
+            if deadline > now {
+                break;
+            }
+            self.remove(&field);
+        }
+    }
+
+    // One HSCAN step, with each field's value
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<(String, Vec<u8>)>, u64) {
+        let (fields, cursor) = self.order.scan(cursor, count);
+        let entries = fields
+            .into_iter()
+            .map(|field| {
+                let value = self.fields[&field].clone();
+                (field, value)
+            })
+            .collect();
+        (entries, cursor)
+    }
+}
@@ -0,0 +1,388 @@
+// HyperLogLog port of Redis' hyperloglog.c, byte-compatible with its string layout:
+//
+//   +------+---+-----+----------+
+//   | HYLL | E | N/U | Cardin.  |  16 byte header, then the registers
+//   +------+---+-----+----------+
+//
+// E is the encoding (dense or sparse) and the cardinality is a little-endian cache
+// whose most significant bit marks it stale. Dense registers are 6 bits each, packed
+// LSB first; the sparse encoding run-length compresses them with ZERO, XZERO and VAL
+// opcodes.
+
+const HLL_P: u32 = 14;
+const HLL_Q: u32 = 64 - HLL_P;
+const HLL_REGISTERS: usize = 1 << HLL_P;
+const HLL_P_MASK: u64 = HLL_REGISTERS as u64 - 1;
+const HLL_BITS: usize = 6;
+const HLL_REGISTER_MAX: u8 = (1 << HLL_BITS) - 1;
+const HLL_HDR_SIZE: usize = 16;
+const HLL_DENSE_SIZE: usize = HLL_HDR_SIZE + (HLL_REGISTERS * HLL_BITS).div_ceil(8);
+const HLL_DENSE: u8 = 0;
+const HLL_SPARSE: u8 = 1;
+const HLL_ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+
+// Sparse values above this size are promoted to dense, as in Redis' default config
+const HLL_SPARSE_MAX_BYTES: usize = 3000;
+const HLL_SPARSE_VAL_MAX_VALUE: u8 = 32;
+const HLL_SPARSE_VAL_MAX_LEN: usize = 4;
+const HLL_SPARSE_ZERO_MAX_LEN: usize = 64;
+const HLL_SPARSE_XZERO_MAX_LEN: usize = 16384;
+
+pub const INVALID_HLL: &str = "WRONGTYPE Key is not a valid HyperLogLog string value.";
+pub const CORRUPTED_HLL: &str = "INVALIDOBJ Corrupted HLL object detected";
+
+// Returned when a sparse value doesn't decode to exactly the register count
+pub struct Corrupted;
+
+pub fn murmurhash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+
+    let mut chunks = key.chunks_exact(8);
+    for chunk in chunks.by_ref() {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, &byte) in tail.iter().enumerate() {
+            h ^= (byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+// The register an element maps to, and the length of its 000..1 pattern
+fn pattern(element: &[u8]) -> (usize, u8) {
+    let hash = murmurhash64a(element, 0xadc83b19);
+    let index = (hash & HLL_P_MASK) as usize;
+    // The sentinel bit caps the count at Q + 1
+    let hash = (hash >> HLL_P) | (1 << HLL_Q);
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+fn dense_get(registers: &[u8], index: usize) -> u8 {
+    let byte = index * HLL_BITS / 8;
+    let shift = (index * HLL_BITS) & 7;
+    let low = registers[byte] as u16;
+    let high = registers.get(byte + 1).copied().unwrap_or(0) as u16;
+    (((low | (high << 8)) >> shift) as u8) & HLL_REGISTER_MAX
+}
+
+fn dense_set(registers: &mut [u8], index: usize, value: u8) {
+    let byte = index * HLL_BITS / 8;
+    let shift = (index * HLL_BITS) & 7;
+    registers[byte] &= !(HLL_REGISTER_MAX << shift);
+    registers[byte] |= value << shift;
+    if shift > 8 - HLL_BITS {
+        let spill = 8 - shift;
+        registers[byte + 1] &= !(HLL_REGISTER_MAX >> spill);
+        registers[byte + 1] |= value >> spill;
+    }
+}
+
+fn header(encoding: u8) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HLL_DENSE_SIZE);
+    bytes.extend_from_slice(b"HYLL");
+    bytes.push(encoding);
+    bytes.extend_from_slice(&[0; 11]);
+    bytes
+}
+
+// A fresh, empty HyperLogLog: sparse with a single XZERO covering every register
+pub fn new() -> Vec<u8> {
+    let mut bytes = header(HLL_SPARSE);
+    let len = HLL_REGISTERS - 1;
+    bytes.extend_from_slice(&[0x40 | (len >> 8) as u8, (len & 0xff) as u8]);
+    bytes
+}
+
+pub fn is_valid(bytes: &[u8]) -> bool {
+    bytes.len() >= HLL_HDR_SIZE
+        && &bytes[..4] == b"HYLL"
+        && match bytes[4] {
+            HLL_DENSE => bytes.len() == HLL_DENSE_SIZE,
+            HLL_SPARSE => true,
+            _ => false,
+        }
+}
+
+// The opcode at `pos`: the value it holds, how many registers it covers and its size
+fn sparse_opcode(body: &[u8], pos: usize) -> Result<(u8, usize, usize), Corrupted> {
+    let opcode = *body.get(pos).ok_or(Corrupted)?;
+    Ok(match opcode >> 6 {
+        0b00 => (0, (opcode & 0x3f) as usize + 1, 1),
+        0b01 => {
+            let low = *body.get(pos + 1).ok_or(Corrupted)?;
+            (0, (((opcode & 0x3f) as usize) << 8 | low as usize) + 1, 2)
+        }
+        _ => (((opcode >> 2) & 0x1f) + 1, (opcode & 0x3) as usize + 1, 1),
+    })
+}
+
+fn is_val(opcode: u8) -> bool {
+    opcode & 0x80 != 0
+}
+
+fn val_opcode(value: u8, len: usize) -> u8 {
+    0x80 | ((value - 1) << 2) | (len - 1) as u8
+}
+
+fn decode_sparse(body: &[u8]) -> Result<Vec<u8>, Corrupted> {
+    let mut registers = Vec::with_capacity(HLL_REGISTERS);
+    let mut pos = 0;
+    while pos < body.len() {
+        let (value, len, size) = sparse_opcode(body, pos)?;
+        if registers.len() + len > HLL_REGISTERS {
+            return Err(Corrupted);
+        }
+        registers.resize(registers.len() + len, value);
+        pos += size;
+    }
+    if registers.len() != HLL_REGISTERS {
+        return Err(Corrupted);
+    }
+    Ok(registers)
+}
+
+// Appends the opcodes for `len` registers holding `value`, which must fit a VAL opcode
+fn encode_run(body: &mut Vec<u8>, value: u8, mut len: usize) {
+    while len > 0 {
+        if value == 0 && len > HLL_SPARSE_ZERO_MAX_LEN {
+            let run = len.min(HLL_SPARSE_XZERO_MAX_LEN);
+            body.extend_from_slice(&[0x40 | ((run - 1) >> 8) as u8, ((run - 1) & 0xff) as u8]);
+            len -= run;
+        } else if value == 0 {
+            body.push((len - 1) as u8);
+            len = 0;
+        } else {
+            let run = len.min(HLL_SPARSE_VAL_MAX_LEN);
+            body.push(val_opcode(value, run));
+            len -= run;
+        }
+    }
+}
+
+// Run-length encodes registers, or returns None if a value is too large for VAL opcodes
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut index = 0;
+    while index < registers.len() {
+        let value = registers[index];
+        if value > HLL_SPARSE_VAL_MAX_VALUE {
+            return None;
+        }
+        let run = registers[index..]
+            .iter()
+            .take_while(|&&register| register == value)
+            .count();
+        encode_run(&mut body, value, run);
+        index += run;
+    }
+    Some(body)
+}
+
+// Raises register `index` of a sparse value to `count` in place, as Redis' hllSparseSet
+// does: the opcode covering the register is split around it, and VAL opcodes left next
+// to one another with the same value are merged back. Returns None when `count` needs
+// the dense encoding.
+fn sparse_set(bytes: &mut Vec<u8>, index: usize, count: u8) -> Result<Option<bool>, Corrupted> {
+    let (mut previous, mut pos, mut first) = (HLL_HDR_SIZE, HLL_HDR_SIZE, 0);
+    let (value, len, size) = loop {
+        let (value, len, size) = sparse_opcode(bytes, pos)?;
+        if index < first + len {
+            break (value, len, size);
+        }
+        previous = pos;
+        pos += size;
+        first += len;
+    };
+    if value >= count {
+        return Ok(Some(false));
+    }
+    if count > HLL_SPARSE_VAL_MAX_VALUE {
+        return Ok(None);
+    }
+
+    let mut replacement = Vec::with_capacity(5);
+    encode_run(&mut replacement, value, index - first);
+    encode_run(&mut replacement, count, 1);
+    encode_run(&mut replacement, value, first + len - index - 1);
+    bytes.splice(pos..pos + size, replacement);
+
+    // Only the opcodes around the split can have become mergeable
+    let mut pos = previous;
+    for _ in 0..5 {
+        let Some(&opcode) = bytes.get(pos) else { break };
+        if is_val(opcode) && bytes.get(pos + 1).is_some_and(|&next| is_val(next)) {
+            let (value, len, _) = sparse_opcode(bytes, pos)?;
+            let (next_value, next_len, _) = sparse_opcode(bytes, pos + 1)?;
+            if value == next_value && len + next_len <= HLL_SPARSE_VAL_MAX_LEN {
+                bytes[pos] = val_opcode(value, len + next_len);
+                bytes.remove(pos + 1);
+                continue;
+            }
+        }
+        pos += sparse_opcode(bytes, pos)?.2;
+    }
+    Ok(Some(true))
+}
+
+// One register value per byte, whatever the encoding
+pub fn registers(bytes: &[u8]) -> Result<Vec<u8>, Corrupted> {
+    let body = &bytes[HLL_HDR_SIZE..];
+    if bytes[4] == HLL_DENSE {
+        Ok((0..HLL_REGISTERS).map(|i| dense_get(body, i)).collect())
+    } else {
+        decode_sparse(body)
+    }
+}
+
+// Rewrites the value from registers, keeping it sparse only while that stays compact
+pub fn store(bytes: &mut Vec<u8>, registers: &[u8], dense: bool) {
+    let sparse = if dense {
+        None
+    } else {
+        encode_sparse(registers)
+    };
+    *bytes = match sparse {
+        Some(body) if HLL_HDR_SIZE + body.len() <= HLL_SPARSE_MAX_BYTES => {
+            let mut bytes = header(HLL_SPARSE);
+            bytes.extend_from_slice(&body);
+            bytes
+        }
+        _ => {
+            let mut bytes = header(HLL_DENSE);
+            bytes.resize(HLL_DENSE_SIZE, 0);
+            for (index, &value) in registers.iter().enumerate() {
+                dense_set(&mut bytes[HLL_HDR_SIZE..], index, value);
+            }
+            bytes
+        }
+    };
+    invalidate_cache(bytes);
+}
+
+fn invalidate_cache(bytes: &mut [u8]) {
+    bytes[15] |= 0x80;
+}
+
+// Adds elements, returning whether any register changed. A sparse value is updated in
+// place until a count or its size calls for the dense encoding.
+pub fn add<'a>(
+    bytes: &mut Vec<u8>,
+    elements: impl Iterator<Item = &'a [u8]>,
+) -> Result<bool, Corrupted> {
+    let mut changed = false;
+    for element in elements {
+        let (index, count) = pattern(element);
+        if bytes[4] == HLL_SPARSE {
+            if let Some(set) = sparse_set(bytes, index, count)? {
+                changed |= set;
+                if bytes.len() <= HLL_SPARSE_MAX_BYTES {
+                    continue;
+                }
+            }
+            let registers = registers(bytes)?;
+            store(bytes, &registers, true);
+        }
+        let body = &mut bytes[HLL_HDR_SIZE..];
+        if count > dense_get(body, index) {
+            dense_set(body, index, count);
+            changed = true;
+        }
+    }
+    if changed {
+        invalidate_cache(bytes);
+    }
+    Ok(changed)
+}
+
+// Raises each register of `max` to at least the matching register of `bytes`
+pub fn merge(max: &mut [u8], bytes: &[u8]) -> Result<(), Corrupted> {
+    for (max, value) in max.iter_mut().zip(registers(bytes)?) {
+        *max = (*max).max(value);
+    }
+    Ok(())
+}
+
+pub fn is_dense(bytes: &[u8]) -> bool {
+    bytes[4] == HLL_DENSE
+}
+
+pub fn empty_registers() -> Vec<u8> {
+    vec![0; HLL_REGISTERS]
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+// Ertl's improved estimator, as used by Redis since 5.0
+pub fn estimate(registers: &[u8]) -> u64 {
+    let m = HLL_REGISTERS as f64;
+    let mut histogram = [0u32; HLL_Q as usize + 2];
+    for &value in registers {
+        histogram[value as usize] += 1;
+    }
+
+    let mut z = m * tau((m - histogram[HLL_Q as usize + 1] as f64) / m);
+    for j in (1..=HLL_Q as usize).rev() {
+        z += histogram[j] as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+    (HLL_ALPHA_INF * m * m / z).round() as u64
+}
+
+// The cardinality, served from the header cache when it is still valid
+pub fn count(bytes: &mut [u8]) -> Result<u64, Corrupted> {
+    let cache: [u8; 8] = bytes[8..16].try_into().unwrap();
+    if cache[7] & 0x80 == 0 {
+        return Ok(u64::from_le_bytes(cache));
+    }
+    let cardinality = estimate(&registers(bytes)?);
+    bytes[8..16].copy_from_slice(&cardinality.to_le_bytes());
+    Ok(cardinality)
+}
@@ -0,0 +1,114 @@
+// Sorted array of integers stored little-endian at the smallest width (2, 4 or
+// 8 bytes) that fits every member, modelled after Redis' intset encoding.
+#[derive(Clone)]
+pub struct IntSet {
+    width: usize,
+    contents: Vec<u8>,
+}
+
+impl IntSet {
+    pub fn new() -> Self {
+        IntSet {
+            width: 2,
+            contents: Vec::new(),
+        }
+    }
+
+    fn width_for(value: i64) -> usize {
+        if i16::try_from(value).is_ok() {
+            2
+        } else if i32::try_from(value).is_ok() {
+            4
+        } else {
+            8
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.contents.len() / self.width
+    }
+
+    pub fn get(&self, index: usize) -> i64 {
+        let start = index * self.width;
+        let bytes = &self.contents[start..start + self.width];
+        match self.width {
+            2 => i16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+            4 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64,
+            _ => i64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    fn encode(value: i64, width: usize) -> Vec<u8> {
+        match width {
+            2 => (value as i16).to_le_bytes().to_vec(),
+            4 => (value as i32).to_le_bytes().to_vec(),
+            _ => value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn search(&self, value: i64) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let current = self.get(mid);
+            if current == value {
+                return Ok(mid);
+            } else if current < value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Err(low)
+    }
+
+    // Re-encode every member at a wider width
+    fn upgrade(&mut self, width: usize) {
+        let values: Vec<i64> = self.iter().collect();
+        self.width = width;
+        self.contents = values
+            .into_iter()
+            .flat_map(|value| Self::encode(value, width))
+            .collect();
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        Self::width_for(value) <= self.width && self.search(value).is_ok()
+    }
+
+    pub fn insert(&mut self, value: i64) -> bool {
+        let width = Self::width_for(value);
+        if width > self.width {
+            self.upgrade(width);
+        }
+
+        match self.search(value) {
+            Ok(_) => false,
+            Err(index) => {
+                let start = index * self.width;
+                self.contents
+                    .splice(start..start, Self::encode(value, self.width));
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, value: i64) -> bool {
+        if Self::width_for(value) > self.width {
+            return false;
+        }
+
+        match self.search(value) {
+            Ok(index) => {
+                let start = index * self.width;
+                self.contents.drain(start..start + self.width);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+}
@@ -0,0 +1,663 @@
+// JSON documents and a JSONPath subset for addressing parts of them. Paths starting with
+// `$` are JSONPath and may match many values; anything else is the legacy dotted syntax
+// (`.a.b[0]`), which addresses a single value.
+//
+// Supported: `.name`, `['name']`, `.*`, `[*]`, `..` descent, indices (negative count from
+// the end), `[start:end:step]` slices, unions like `[0,2]`, and filters such as
+// `[?(@.price < 10 && @.tags)]`.
+
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+// One step from a value to one of its children
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+    Key(String),
+    Index(usize),
+}
+
+// Where a match lives inside the document, as the steps from the root
+pub type Location = Vec<Step>;
+
+#[derive(Clone)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, i64),
+    Filter(Filter),
+}
+
+#[derive(Clone)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Exists(Operand),
+    Compare(Operand, Comparison, Operand),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone)]
+enum Operand {
+    Current(Vec<Segment>),
+    Root(Vec<Segment>),
+    Literal(Value),
+}
+
+#[derive(Clone)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+    pub legacy: bool,
+}
+
+impl JsonPath {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let legacy = !input.starts_with('$');
+        let normalized = if !legacy {
+            input.to_string()
+        } else if input == "." || input.is_empty() {
+            "$".to_string()
+        } else if input.starts_with('.') || input.starts_with('[') {
+            format!("${}", input)
+        } else {
+            format!("$.{}", input)
+        };
+
+        let chars: Vec<char> = normalized.chars().collect();
+        let mut parser = Parser { chars, pos: 1 };
+        let segments = parser
+            .segments()
+            .ok()
+            .filter(|_| parser.pos == parser.chars.len())
+            .ok_or_else(|| format!("invalid JSONPath '{}'", input))?;
+        Ok(JsonPath { segments, legacy })
+    }
+
+    pub fn root() -> Self {
+        JsonPath {
+            segments: Vec::new(),
+            legacy: true,
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    // Locations of every value the path matches, in document order
+    pub fn select(&self, root: &Value) -> Vec<Location> {
+        select(root, root, Vec::new(), &self.segments)
+    }
+
+    // The parent path and key of a `.name` path, so that JSON.SET can create the member
+    pub fn split_last_key(&self) -> Option<(JsonPath, String)> {
+        match self.segments.split_last()? {
+            (Segment::Child(selectors), parent) => match selectors.as_slice() {
+                [Selector::Name(name)] => Some((
+                    JsonPath {
+                        segments: parent.to_vec(),
+                        legacy: self.legacy,
+                    },
+                    name.clone(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+pub fn resolve<'a>(root: &'a Value, location: &[Step]) -> Option<&'a Value> {
+    location.iter().try_fold(root, |value, step| match step {
+        Step::Key(key) => value.get(key),
+        Step::Index(index) => value.get(index),
+    })
+}
+
+pub fn resolve_mut<'a>(root: &'a mut Value, location: &[Step]) -> Option<&'a mut Value> {
+    location.iter().try_fold(root, |value, step| match step {
+        Step::Key(key) => value.get_mut(key),
+        Step::Index(index) => value.get_mut(index),
+    })
+}
+
+// Removes the value at `location`, returning whether it existed
+pub fn remove(root: &mut Value, location: &[Step]) -> bool {
+    let Some((last, parent)) = location.split_last() else {
+        return false;
+    };
+    match (resolve_mut(root, parent), last) {
+        (Some(Value::Object(map)), Step::Key(key)) => map.shift_remove(key).is_some(),
+        (Some(Value::Array(array)), Step::Index(index)) if *index < array.len() => {
+            array.remove(*index);
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+// Adds two numbers, staying integral while both are integers and the sum fits
+pub fn add_numbers(a: &serde_json::Number, b: &serde_json::Number) -> Option<Value> {
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        if let Some(sum) = a.checked_add(b) {
+            return Some(Value::from(sum));
+        }
+    }
+    let sum = a.as_f64()? + b.as_f64()?;
+    serde_json::Number::from_f64(sum).map(Value::Number)
+}
+
+// Whitespace used when serializing, as set by JSON.GET's INDENT, NEWLINE and SPACE
+#[derive(Default)]
+pub struct Format {
+    pub indent: String,
+    pub newline: String,
+    pub space: String,
+}
+
+pub fn serialize(value: &Value, format: &Format) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, format, 0);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, format: &Format, depth: usize) {
+    let open_line = |out: &mut String, depth: usize| {
+        out.push_str(&format.newline);
+        out.push_str(&format.indent.repeat(depth));
+    };
+    match value {
+        Value::Array(array) if !array.is_empty() => {
+            out.push('[');
+            for (i, item) in array.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                open_line(out, depth + 1);
+                write_value(out, item, format, depth + 1);
+            }
+            open_line(out, depth);
+            out.push(']');
+        }
+        Value::Object(map) if !map.is_empty() => {
+            out.push('{');
+            for (i, (key, item)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                open_line(out, depth + 1);
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                out.push_str(&format.space);
+                write_value(out, item, format, depth + 1);
+            }
+            open_line(out, depth);
+            out.push('}');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+fn select(root: &Value, value: &Value, location: Location, segments: &[Segment]) -> Vec<Location> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return vec![location];
+    };
+    let mut matches = Vec::new();
+    match segment {
+        Segment::Child(selectors) => {
+            for (child, child_value) in children(root, value, &location, selectors) {
+                matches.extend(select(root, child_value, child, rest));
+            }
+        }
+        Segment::Descendant(selectors) => {
+            let mut nodes = Vec::new();
+            descendants(value, location, &mut nodes);
+            for (node, node_value) in nodes {
+                for (child, child_value) in children(root, node_value, &node, selectors) {
+                    matches.extend(select(root, child_value, child, rest));
+                }
+            }
+        }
+    }
+    matches
+}
+
+// `value` and everything beneath it, parents before children
+fn descendants<'a>(value: &'a Value, location: Location, out: &mut Vec<(Location, &'a Value)>) {
+    out.push((location.clone(), value));
+    for (step, child) in entries(value) {
+        let mut child_location = location.clone();
+        child_location.push(step);
+        descendants(child, child_location, out);
+    }
+}
+
+fn entries(value: &Value) -> Vec<(Step, &Value)> {
+    match value {
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (Step::Index(i), item))
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, item)| (Step::Key(key.clone()), item))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn children<'a>(
+    root: &Value,
+    value: &'a Value,
+    location: &Location,
+    selectors: &[Selector],
+) -> Vec<(Location, &'a Value)> {
+    let mut steps = Vec::new();
+    for selector in selectors {
+        match (selector, value) {
+            // Steps that lead nowhere are dropped below
+            (Selector::Name(name), _) => steps.push(Step::Key(name.clone())),
+            (Selector::Wildcard, _) => {
+                steps.extend(entries(value).into_iter().map(|(step, _)| step))
+            }
+            (Selector::Index(index), Value::Array(array)) => {
+                if let Some(index) = normalize(*index, array.len()) {
+                    steps.push(Step::Index(index));
+                }
+            }
+            (Selector::Slice(start, end, step), Value::Array(array)) => {
+                let len = array.len() as i64;
+                let bound = |bound: i64| {
+                    if bound < 0 {
+                        (len + bound).max(0)
+                    } else {
+                        bound.min(len)
+                    }
+                };
+                if *step > 0 {
+                    let start = start.map_or(0, bound);
+                    let end = end.map_or(len, bound);
+                    steps.extend(
+                        (start..end)
+                            .step_by(*step as usize)
+                            .map(|i| Step::Index(i as usize)),
+                    );
+                }
+            }
+            (Selector::Filter(filter), _) => {
+                for (step, child) in entries(value) {
+                    if filter.matches(root, child) {
+                        steps.push(step);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    steps
+        .into_iter()
+        .filter_map(|step| {
+            let child = match &step {
+                Step::Key(key) => value.get(key)?,
+                Step::Index(index) => value.get(index)?,
+            };
+            let mut child_location = location.clone();
+            child_location.push(step);
+            Some((child_location, child))
+        })
+        .collect()
+}
+
+// A standalone filter expression such as `@.year > 1950 && @.genre == 'action'`, for
+// matching JSON attached to values of other types
+pub struct JsonFilter(Filter);
+
+impl JsonFilter {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut parser = Parser { chars, pos: 0 };
+        parser
+            .or()
+            .ok()
+            .filter(|_| parser.pos == parser.chars.len())
+            .map(JsonFilter)
+            .ok_or_else(|| format!("invalid filter expression '{}'", input))
+    }
+
+    pub fn matches(&self, value: &Value) -> bool {
+        self.0.matches(value, value)
+    }
+}
+
+fn normalize(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+impl Filter {
+    fn matches(&self, root: &Value, current: &Value) -> bool {
+        match self {
+            Filter::Or(a, b) => a.matches(root, current) || b.matches(root, current),
+            Filter::And(a, b) => a.matches(root, current) && b.matches(root, current),
+            Filter::Not(filter) => !filter.matches(root, current),
+            Filter::Exists(operand) => match operand {
+                Operand::Literal(value) => !matches!(value, Value::Null | Value::Bool(false)),
+                _ => operand.evaluate(root, current).is_some(),
+            },
+            Filter::Compare(a, comparison, b) => {
+                let (a, b) = (a.evaluate(root, current), b.evaluate(root, current));
+                let ordering = match (&a, &b) {
+                    (Some(Value::Number(a)), Some(Value::Number(b))) => {
+                        a.as_f64().partial_cmp(&b.as_f64())
+                    }
+                    (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+                    _ if a == b => Some(Ordering::Equal),
+                    _ => None,
+                };
+                match comparison {
+                    Comparison::Eq => ordering == Some(Ordering::Equal),
+                    Comparison::Ne => ordering != Some(Ordering::Equal),
+                    Comparison::Lt => ordering == Some(Ordering::Less),
+                    Comparison::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    Comparison::Gt => ordering == Some(Ordering::Greater),
+                    Comparison::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                }
+            }
+        }
+    }
+}
+
+impl Operand {
+    // Paths inside filters stand for their first match
+    fn evaluate<'a>(&'a self, root: &'a Value, current: &'a Value) -> Option<&'a Value> {
+        let (base, segments) = match self {
+            Operand::Literal(value) => return Some(value),
+            Operand::Current(segments) => (current, segments),
+            Operand::Root(segments) => (root, segments),
+        };
+        let location = select(root, base, Vec::new(), segments)
+            .into_iter()
+            .next()?;
+        resolve(base, &location)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        let matches = self.chars.len() >= self.pos + len
+            && self.chars[self.pos..self.pos + len]
+                .iter()
+                .copied()
+                .eq(s.chars());
+        if matches {
+            self.pos += len;
+        }
+        matches
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, ()> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat_str("..") {
+                let selectors = match self.peek() {
+                    Some('[') => self.bracket()?,
+                    _ => vec![self.dot_selector()?],
+                };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat('.') {
+                segments.push(Segment::Child(vec![self.dot_selector()?]));
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn dot_selector(&mut self) -> Result<Selector, ()> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !".[]()<>=!&|,'\"".contains(c))
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(());
+        }
+        Ok(Selector::Name(self.chars[start..self.pos].iter().collect()))
+    }
+
+    fn bracket(&mut self) -> Result<Vec<Selector>, ()> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.bracket_selector()?);
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn bracket_selector(&mut self) -> Result<Selector, ()> {
+        match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('\'' | '"') => Ok(Selector::Name(self.quoted()?)),
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.or()?))
+            }
+            _ => {
+                let start = self.integer();
+                self.skip_whitespace();
+                if !self.eat(':') {
+                    return start.map(Selector::Index).ok_or(());
+                }
+                self.skip_whitespace();
+                let end = self.integer();
+                self.skip_whitespace();
+                let step = if self.eat(':') {
+                    self.skip_whitespace();
+                    self.integer().unwrap_or(1)
+                } else {
+                    1
+                };
+                Ok(Selector::Slice(start, end, step))
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let parsed = self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok();
+        if parsed.is_none() {
+            self.pos = start;
+        }
+        parsed
+    }
+
+    fn quoted(&mut self) -> Result<String, ()> {
+        let quote = self.peek().ok_or(())?;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.peek().ok_or(())? {
+                c if c == quote => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    out.push(self.peek().ok_or(())?);
+                }
+                c => out.push(c),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn or(&mut self) -> Result<Filter, ()> {
+        let mut filter = self.and()?;
+        while self.eat_str("||") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, ()> {
+        let mut filter = self.unary()?;
+        while self.eat_str("&&") {
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter, ()> {
+        self.skip_whitespace();
+        let filter = if self.peek() == Some('!') && self.chars.get(self.pos + 1) != Some(&'=') {
+            self.pos += 1;
+            Filter::Not(Box::new(self.unary()?))
+        } else if self.eat('(') {
+            let filter = self.or()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            filter
+        } else {
+            let left = self.operand()?;
+            self.skip_whitespace();
+            match self.comparison() {
+                Some(comparison) => {
+                    self.skip_whitespace();
+                    Filter::Compare(left, comparison, self.operand()?)
+                }
+                None => Filter::Exists(left),
+            }
+        };
+        self.skip_whitespace();
+        Ok(filter)
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        let comparisons = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+        comparisons
+            .into_iter()
+            .find(|(token, _)| self.eat_str(token))
+            .map(|(_, comparison)| comparison)
+    }
+
+    fn operand(&mut self) -> Result<Operand, ()> {
+        match self.peek().ok_or(())? {
+            '@' => {
+                self.pos += 1;
+                Ok(Operand::Current(self.segments()?))
+            }
+            '$' => {
+                self.pos += 1;
+                Ok(Operand::Root(self.segments()?))
+            }
+            '\'' | '"' => Ok(Operand::Literal(Value::String(self.quoted()?))),
+            _ => {
+                // Numbers, true, false and null read as JSON
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                {
+                    self.pos += 1;
+                }
+                let literal: String = self.chars[start..self.pos].iter().collect();
+                serde_json::from_str(&literal)
+                    .map(Operand::Literal)
+                    .map_err(|_| ())
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+pub mod bitmap;
+pub mod bloom;
+pub mod cms;
+pub mod cuckoo;
+pub mod geo;
+pub mod hash;
+pub mod hyperloglog;
+pub mod intset;
+pub mod json;
+pub mod scan;
+pub mod search;
+pub mod set;
+pub mod skiplist;
+pub mod stream;
+pub mod tdigest;
+pub mod timeseries;
+pub mod topk;
+pub mod trie;
+pub mod vectorset;
+pub mod zset;
@@ -0,0 +1,51 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+};
+
+// Members ordered by their bit-reversed hash: the order Redis' reverse-binary SCAN cursor
+// visits buckets in, taken to a table of 2^64 buckets. The cursor only moves forward
+// through it, so a member present for the whole scan is always returned, and never
+// twice, however the table actually holding the members resizes in between.
+#[derive(Clone, Default)]
+pub struct ScanIndex {
+    order: BTreeSet<(u64, String)>,
+}
+
+// DefaultHasher::new() is unkeyed, so a cursor handed out stays valid for later calls
+fn position(member: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    member.hash(&mut hasher);
+    hasher.finish().reverse_bits()
+}
+
+impl ScanIndex {
+    pub fn insert(&mut self, member: &str) {
+        self.order.insert((position(member), member.to_string()));
+    }
+
+    pub fn remove(&mut self, member: &str) {
+        self.order.remove(&(position(member), member.to_string()));
+    }
+
+    // Up to `count` members from `cursor` on, and the cursor to resume from (0 once the
+    // walk is done). Members sharing a hash always come back together, as the cursor
+    // can't point between them.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<String>, u64) {
+        let mut members = Vec::new();
+        let mut entries = self
+            .order
+            .range((cursor.reverse_bits(), String::new())..)
+            .peekable();
+        while let Some((position, member)) = entries.next() {
+            members.push(member.clone());
+            match entries.peek() {
+                Some((next, _)) if members.len() >= count && next != position => {
+                    return (members, next.reverse_bits());
+                }
+                _ => {}
+            }
+        }
+        (members, 0)
+    }
+}
@@ -0,0 +1,708 @@
+// Secondary indexes over hashes, as RediSearch's FT.* commands build them. An index
+// covers the hashes under its key prefixes and keeps, per schema field, an inverted
+// index of words (TEXT), values in order (NUMERIC) or exact tags (TAG).
+//
+// Queries: words (`hello`, prefixes as `hel*`), "exact phrases", `*` for every document,
+// `@field:word` to restrict text to fields, `@field:[min max]` numeric ranges (`(` for
+// an exclusive bound, `-inf`/`+inf`), `@field:{a | b}` tags, juxtaposition for AND, `|`
+// for OR (binding loosest), `-` for NOT and parentheses for grouping.
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+};
+
+use crate::types::hash::Hash;
+
+#[derive(Clone)]
+pub enum FieldKind {
+    Text {
+        weight: f64,
+    },
+    Numeric,
+    Tag {
+        separator: char,
+        case_sensitive: bool,
+    },
+}
+
+impl FieldKind {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            FieldKind::Text { .. } => "TEXT",
+            FieldKind::Numeric => "NUMERIC",
+            FieldKind::Tag { .. } => "TAG",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Field {
+    // The hash field read, and the name queries use for it; they differ with AS
+    pub identifier: String,
+    pub attribute: String,
+    pub kind: FieldKind,
+    pub sortable: bool,
+}
+
+enum FieldValue {
+    Text { raw: String, words: Vec<String> },
+    Numeric(f64),
+    Tag { raw: String, tags: Vec<String> },
+}
+
+// What SORTBY compares a document on
+#[derive(PartialEq, PartialOrd)]
+pub enum SortValue {
+    Number(f64),
+    Text(String),
+}
+
+// f64 ordered totally, so numeric values can key a BTreeSet
+#[derive(Clone, Copy, PartialEq)]
+struct Number(f64);
+
+impl Eq for Number {}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Lowercased runs of letters, digits and underscores
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn normalize_tag(tag: &str, case_sensitive: bool) -> String {
+    if case_sensitive {
+        tag.to_string()
+    } else {
+        tag.to_lowercase()
+    }
+}
+
+pub struct SearchIndex {
+    pub name: String,
+    pub prefixes: Vec<String>,
+    pub fields: Vec<Field>,
+    // Each indexed key's values, aligned with `fields`
+    docs: HashMap<String, Vec<Option<FieldValue>>>,
+    // Word -> key -> (field, occurrences) for each text field the word appears in
+    terms: BTreeMap<String, HashMap<String, Vec<(usize, u32)>>>,
+    tags: HashMap<(usize, String), HashSet<String>>,
+    numbers: HashMap<usize, BTreeSet<(Number, String)>>,
+    // Hashes left out because a NUMERIC field didn't hold a number
+    pub failures: usize,
+}
+
+impl SearchIndex {
+    pub fn new(name: String, prefixes: Vec<String>, fields: Vec<Field>) -> Self {
+        SearchIndex {
+            name,
+            prefixes,
+            fields,
+            docs: HashMap::new(),
+            terms: BTreeMap::new(),
+            tags: HashMap::new(),
+            numbers: HashMap::new(),
+            failures: 0,
+        }
+    }
+
+    pub fn covers(&self, key: &str) -> bool {
+        self.prefixes.iter().any(|prefix| key.starts_with(prefix))
+    }
+
+    pub fn field(&self, attribute: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|field| field.attribute == attribute)
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    // Entries across the inverted indexes, as FT.INFO's num_records
+    pub fn record_count(&self) -> usize {
+        let terms: usize = self.terms.values().map(HashMap::len).sum();
+        let tags: usize = self.tags.values().map(HashSet::len).sum();
+        let numbers: usize = self.numbers.values().map(BTreeSet::len).sum();
+        terms + tags + numbers
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.docs.keys()
+    }
+
+    // (Re)indexes the hash at `key`
+    pub fn add(&mut self, key: &str, hash: &Hash) {
+        self.remove(key);
+        let mut values = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let Some(raw) = hash.get(&field.identifier) else {
+                values.push(None);
+                continue;
+            };
+            let raw = String::from_utf8_lossy(raw).into_owned();
+            let value = match field.kind {
+                FieldKind::Text { .. } => FieldValue::Text {
+                    words: words(&raw),
+                    raw,
+                },
+                FieldKind::Numeric => match raw.trim().parse::<f64>() {
+                    // Adding 0.0 turns -0.0 into 0.0, so the two sort as one value
+                    Ok(number) if !number.is_nan() => FieldValue::Numeric(number + 0.0),
+                    _ => {
+                        self.failures += 1;
+                        return;
+                    }
+                },
+                FieldKind::Tag {
+                    separator,
+                    case_sensitive,
+                } => FieldValue::Tag {
+                    tags: raw
+                        .split(separator)
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(|tag| normalize_tag(tag, case_sensitive))
+                        .collect(),
+                    raw,
+                },
+            };
+            values.push(Some(value));
+        }
+
+        for (field, value) in values.iter().enumerate() {
+            match value {
+                Some(FieldValue::Text { words, .. }) => {
+                    for word in words {
+                        let hits = self
+                            .terms
+                            .entry(word.clone())
+                            .or_default()
+                            .entry(key.to_string())
+                            .or_default();
+                        match hits.iter_mut().find(|(hit, _)| *hit == field) {
+                            Some((_, count)) => *count += 1,
+                            None => hits.push((field, 1)),
+                        }
+                    }
+                }
+                Some(FieldValue::Numeric(number)) => {
+                    self.numbers
+                        .entry(field)
+                        .or_default()
+                        .insert((Number(*number), key.to_string()));
+                }
+                Some(FieldValue::Tag { tags, .. }) => {
+                    for tag in tags {
+                        self.tags
+                            .entry((field, tag.clone()))
+                            .or_default()
+                            .insert(key.to_string());
+                    }
+                }
+                None => {}
+            }
+        }
+        self.docs.insert(key.to_string(), values);
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        let Some(values) = self.docs.remove(key) else {
+            return false;
+        };
+        for (field, value) in values.into_iter().enumerate() {
+            match value {
+                Some(FieldValue::Text { words, .. }) => {
+                    for word in words {
+                        if let Some(postings) = self.terms.get_mut(&word) {
+                            postings.remove(key);
+                            if postings.is_empty() {
+                                self.terms.remove(&word);
+                            }
+                        }
+                    }
+                }
+                Some(FieldValue::Numeric(number)) => {
+                    if let Some(numbers) = self.numbers.get_mut(&field) {
+                        numbers.remove(&(Number(number), key.to_string()));
+                    }
+                }
+                Some(FieldValue::Tag { tags, .. }) => {
+                    for tag in tags {
+                        let entry = (field, tag);
+                        if let Some(keys) = self.tags.get_mut(&entry) {
+                            keys.remove(key);
+                            if keys.is_empty() {
+                                self.tags.remove(&entry);
+                            }
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+        true
+    }
+
+    pub fn sort_value(&self, key: &str, field: usize) -> Option<SortValue> {
+        match self.docs.get(key)?.get(field)?.as_ref()? {
+            FieldValue::Numeric(number) => Some(SortValue::Number(*number)),
+            FieldValue::Text { raw, .. } | FieldValue::Tag { raw, .. } => {
+                Some(SortValue::Text(raw.to_lowercase()))
+            }
+        }
+    }
+
+    pub fn parse_query(&self, input: &str) -> Result<Query, String> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            pos: 0,
+            index: self,
+            scope: None,
+        };
+        let root = parser.union()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.syntax_error());
+        }
+        Ok(Query { root })
+    }
+
+    // The keys of the documents matching the query, in no particular order
+    pub fn search(&self, query: &Query) -> Vec<String> {
+        self.matching(&query.root)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    // TF-IDF over the words the query looks for, weighted by field
+    pub fn score(&self, query: &Query, key: &str) -> f64 {
+        let mut words = Vec::new();
+        query.root.words(false, &mut words);
+        let total = self.docs.len() as f64;
+        let mut score = 0.0;
+        for (word, prefix, scope) in words {
+            for postings in self.expand(word, prefix) {
+                let Some(hits) = postings.get(key) else {
+                    continue;
+                };
+                let idf = (1.0 + total / postings.len() as f64).ln();
+                for &(field, count) in hits {
+                    if let (true, FieldKind::Text { weight }) =
+                        (in_scope(scope, field), &self.fields[field].kind)
+                    {
+                        score += count as f64 * weight * idf;
+                    }
+                }
+            }
+        }
+        score
+    }
+
+    // The postings of the word, or of every word it prefixes
+    fn expand(&self, word: &str, prefix: bool) -> Vec<&HashMap<String, Vec<(usize, u32)>>> {
+        if prefix {
+            self.terms
+                .range(word.to_string()..)
+                .take_while(|(term, _)| term.starts_with(word))
+                .map(|(_, postings)| postings)
+                .collect()
+        } else {
+            self.terms.get(word).into_iter().collect()
+        }
+    }
+
+    fn matching(&self, node: &Node) -> HashSet<&str> {
+        match node {
+            Node::All => self.docs.keys().map(String::as_str).collect(),
+            Node::Word {
+                word,
+                prefix,
+                scope,
+            } => self
+                .expand(word, *prefix)
+                .into_iter()
+                .flat_map(|postings| postings.iter())
+                .filter(|(_, hits)| hits.iter().any(|(field, _)| in_scope(scope, *field)))
+                .map(|(key, _)| key.as_str())
+                .collect(),
+            Node::Phrase { words, scope } => {
+                let mut keys = self.docs.keys().map(String::as_str).collect::<HashSet<_>>();
+                for word in words {
+                    let node = Node::Word {
+                        word: word.clone(),
+                        prefix: false,
+                        scope: scope.clone(),
+                    };
+                    let found = self.matching(&node);
+                    keys.retain(|key| found.contains(key));
+                }
+                keys.retain(|key| {
+                    self.docs[*key].iter().enumerate().any(|(field, value)| {
+                        matches!(value, Some(FieldValue::Text { words: text, .. })
+                            if in_scope(scope, field)
+                                && text.windows(words.len()).any(|window| window == words))
+                    })
+                });
+                keys
+            }
+            Node::Numeric { field, min, max } => {
+                let Some(numbers) = self.numbers.get(field) else {
+                    return HashSet::new();
+                };
+                numbers
+                    .range((Number(min.0), String::new())..)
+                    .take_while(|(number, _)| number.0 <= max.0)
+                    .filter(|(number, _)| {
+                        (!min.1 || number.0 > min.0) && (!max.1 || number.0 < max.0)
+                    })
+                    .map(|(_, key)| key.as_str())
+                    .collect()
+            }
+            Node::Tag { field, tags } => tags
+                .iter()
+                .filter_map(|tag| self.tags.get(&(*field, tag.clone())))
+                .flatten()
+                .map(String::as_str)
+                .collect(),
+            Node::And(nodes) => {
+                let mut keys = self.matching(&nodes[0]);
+                for node in &nodes[1..] {
+                    let found = self.matching(node);
+                    keys.retain(|key| found.contains(key));
+                }
+                keys
+            }
+            Node::Or(nodes) => nodes.iter().flat_map(|node| self.matching(node)).collect(),
+            Node::Not(node) => {
+                let excluded = self.matching(node);
+                self.docs
+                    .keys()
+                    .map(String::as_str)
+                    .filter(|key| !excluded.contains(key))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn in_scope(scope: &Option<Vec<usize>>, field: usize) -> bool {
+    scope.as_ref().is_none_or(|fields| fields.contains(&field))
+}
+
+// A numeric bound and whether it is exclusive
+type Bound = (f64, bool);
+
+enum Node {
+    All,
+    Word {
+        word: String,
+        prefix: bool,
+        scope: Option<Vec<usize>>,
+    },
+    Phrase {
+        words: Vec<String>,
+        scope: Option<Vec<usize>>,
+    },
+    Numeric {
+        field: usize,
+        min: Bound,
+        max: Bound,
+    },
+    Tag {
+        field: usize,
+        tags: Vec<String>,
+    },
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+}
+
+impl Node {
+    // The words a match is scored on: those not under a NOT
+    fn words<'a>(&'a self, negated: bool, out: &mut Vec<(&'a str, bool, &'a Option<Vec<usize>>)>) {
+        match self {
+            Node::Word {
+                word,
+                prefix,
+                scope,
+            } if !negated => out.push((word, *prefix, scope)),
+            Node::Phrase { words, scope } if !negated => {
+                out.extend(words.iter().map(|word| (word.as_str(), false, scope)))
+            }
+            Node::And(nodes) | Node::Or(nodes) => {
+                nodes.iter().for_each(|node| node.words(negated, out))
+            }
+            Node::Not(node) => node.words(!negated, out),
+            _ => {}
+        }
+    }
+}
+
+pub struct Query {
+    root: Node,
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    index: &'a SearchIndex,
+    // The text fields an `@field:` modifier restricts the words being parsed to
+    scope: Option<Vec<usize>>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.syntax_error())
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn syntax_error(&self) -> String {
+        let near: String = self.chars[self.pos.min(self.chars.len())..]
+            .iter()
+            .collect();
+        format!("Syntax error at offset {} near {}", self.pos, near)
+    }
+
+    fn union(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.intersection()?];
+        while self.eat('|') {
+            nodes.push(self.intersection()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            Node::Or(nodes)
+        })
+    }
+
+    fn intersection(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some(')') | Some('|') => break,
+                _ => nodes.push(self.unary()?),
+            }
+        }
+        match nodes.len() {
+            0 => Err(self.syntax_error()),
+            1 => Ok(nodes.remove(0)),
+            _ => Ok(Node::And(nodes)),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat('-') {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let node = self.union()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                Ok(node)
+            }
+            Some('@') => self.modifier(),
+            Some('"') => {
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '"') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                self.expect('"')?;
+                let mut words = words(&text);
+                let scope = self.scope.clone();
+                match words.len() {
+                    0 => Err(self.syntax_error()),
+                    1 => Ok(Node::Word {
+                        word: words.remove(0),
+                        prefix: false,
+                        scope,
+                    }),
+                    _ => Ok(Node::Phrase { words, scope }),
+                }
+            }
+            Some('*') => {
+                self.pos += 1;
+                Ok(Node::All)
+            }
+            _ => {
+                let word = self.word();
+                if word.is_empty() {
+                    return Err(self.syntax_error());
+                }
+                Ok(Node::Word {
+                    word: word.to_lowercase(),
+                    prefix: self.eat('*'),
+                    scope: self.scope.clone(),
+                })
+            }
+        }
+    }
+
+    // A run of word characters; a backslash takes the next character literally
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' && self.pos + 1 < self.chars.len() {
+                word.push(self.chars[self.pos + 1]);
+                self.pos += 2;
+            } else if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        word
+    }
+
+    // @field:... or @field1|field2:... for text
+    fn modifier(&mut self) -> Result<Node, String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut fields = Vec::new();
+        loop {
+            let name_at = self.pos;
+            let name = self.word();
+            match self.index.field(&name) {
+                Some(field) if !name.is_empty() => fields.push(field),
+                _ => return Err(format!("Unknown field at offset {} near {}", name_at, name)),
+            }
+            if !self.eat('|') {
+                break;
+            }
+        }
+        self.expect(':')?;
+        self.skip_whitespace();
+
+        let kinds = fields
+            .iter()
+            .map(|&field| &self.index.fields[field].kind)
+            .collect::<Vec<_>>();
+        match (kinds.as_slice(), self.peek()) {
+            ([FieldKind::Numeric], Some('[')) => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let min = self.bound()?;
+                self.skip_whitespace();
+                self.eat(',');
+                self.skip_whitespace();
+                let max = self.bound()?;
+                self.skip_whitespace();
+                self.expect(']')?;
+                Ok(Node::Numeric {
+                    field: fields[0],
+                    min,
+                    max,
+                })
+            }
+            ([FieldKind::Tag { case_sensitive, .. }], Some('{')) => {
+                let case_sensitive = *case_sensitive;
+                self.pos += 1;
+                let mut tags = Vec::new();
+                loop {
+                    let mut tag = String::new();
+                    while let Some(c) = self.peek().filter(|c| *c != '|' && *c != '}') {
+                        if c == '\\' && self.pos + 1 < self.chars.len() {
+                            self.pos += 1;
+                        }
+                        tag.push(self.chars[self.pos]);
+                        self.pos += 1;
+                    }
+                    tags.push(normalize_tag(tag.trim(), case_sensitive));
+                    if !self.eat('|') {
+                        break;
+                    }
+                }
+                self.expect('}')?;
+                Ok(Node::Tag {
+                    field: fields[0],
+                    tags,
+                })
+            }
+            _ if kinds
+                .iter()
+                .all(|kind| matches!(kind, FieldKind::Text { .. })) =>
+            {
+                let outer = self.scope.replace(fields);
+                let node = self.unary();
+                self.scope = outer;
+                node
+            }
+            _ => {
+                self.pos = start;
+                Err(self.syntax_error())
+            }
+        }
+    }
+
+    fn bound(&mut self) -> Result<Bound, String> {
+        let exclusive = self.eat('(');
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && c != ',' && c != ']')
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let number = match text.to_ascii_lowercase().as_str() {
+            "inf" | "+inf" => f64::INFINITY,
+            "-inf" => f64::NEG_INFINITY,
+            text => match text.parse::<f64>() {
+                Ok(number) if number.is_finite() => number,
+                _ => {
+                    self.pos = start;
+                    return Err(self.syntax_error());
+                }
+            },
+        };
+        Ok((number, exclusive))
+    }
+}
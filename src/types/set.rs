@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use crate::{
+    random,
+    types::{intset::IntSet, scan::ScanIndex},
+};
+
+// Integer-only sets stay in the compact encoding until they grow past this size
+const MAX_INTSET_ENTRIES: usize = 512;
+
+// Members kept in a Vec so SRANDMEMBER and SPOP can pick one by index, with each member's
+// slot alongside for O(1) lookups and swap-removes
+#[derive(Clone, Default)]
+pub struct Members {
+    slots: HashMap<String, usize>,
+    list: Vec<String>,
+}
+
+impl Members {
+    fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    fn contains(&self, member: &str) -> bool {
+        self.slots.contains_key(member)
+    }
+
+    fn insert(&mut self, member: String) -> bool {
+        if self.slots.contains_key(&member) {
+            return false;
+        }
+        self.slots.insert(member.clone(), self.list.len());
+        self.list.push(member);
+        true
+    }
+
+    fn remove(&mut self, member: &str) -> bool {
+        let Some(slot) = self.slots.remove(member) else {
+            return false;
+        };
+        self.list.swap_remove(slot);
+        if let Some(moved) = self.list.get(slot) {
+            self.slots.insert(moved.clone(), slot);
+        }
+        true
+    }
+}
+
+#[derive(Clone)]
+pub enum SetValue {
+    Ints(IntSet),
+    // The index orders members for SSCAN
+    Strings(Members, ScanIndex),
+}
+
+// Members only qualify for the intset encoding when they round-trip exactly
+fn as_int(member: &str) -> Option<i64> {
+    member
+        .parse::<i64>()
+        .ok()
+        .filter(|value| value.to_string() == member)
+}
+
+impl SetValue {
+    pub fn new() -> Self {
+        SetValue::Ints(IntSet::new())
+    }
+
+    pub fn from_members<I: IntoIterator<Item = String>>(members: I) -> Self {
+        let mut set = SetValue::new();
+        for member in members {
+            set.insert(member);
+        }
+        set
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            SetValue::Ints(ints) => ints.len(),
+            SetValue::Strings(strings, _) => strings.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, member: &str) -> bool {
+        match self {
+            SetValue::Ints(ints) => as_int(member).is_some_and(|value| ints.contains(value)),
+            SetValue::Strings(strings, _) => strings.contains(member),
+        }
+    }
+
+    // Switch to the hashtable encoding; never converts back
+    fn convert_to_strings(&mut self) {
+        if let SetValue::Ints(ints) = self {
+            let mut strings = Members::default();
+            let mut order = ScanIndex::default();
+            for value in ints.iter() {
+                let member = value.to_string();
+                order.insert(&member);
+                strings.insert(member);
+            }
+            *self = SetValue::Strings(strings, order);
+        }
+    }
+
+    pub fn insert(&mut self, member: String) -> bool {
+        if let SetValue::Ints(ints) = self {
+            match as_int(&member) {
+                Some(value) if ints.contains(value) => return false,
+                Some(value) if ints.len() < MAX_INTSET_ENTRIES => return ints.insert(value),
+                _ => self.convert_to_strings(),
+            }
+        }
+
+        match self {
+            SetValue::Strings(strings, order) => {
+                order.insert(&member);
+                strings.insert(member)
+            }
+            SetValue::Ints(_) => unreachable!(),
+        }
+    }
+
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self {
+            SetValue::Ints(ints) => as_int(member).is_some_and(|value| ints.remove(value)),
+            SetValue::Strings(strings, order) => {
+                order.remove(member);
+                strings.remove(member)
+            }
+        }
+    }
+
+    pub fn members(&self) -> Vec<String> {
+        match self {
+            SetValue::Ints(ints) => ints.iter().map(|value| value.to_string()).collect(),
+            SetValue::Strings(strings, _) => strings.list.clone(),
+        }
+    }
+
+    // Members one at a time, for callers that may stop before the end
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.len()).map(|index| self.member_at(index))
+    }
+
+    fn member_at(&self, index: usize) -> String {
+        match self {
+            SetValue::Ints(ints) => ints.get(index).to_string(),
+            SetValue::Strings(strings, _) => strings.list[index].clone(),
+        }
+    }
+
+    pub fn random_member(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.member_at(random::below(self.len())))
+    }
+
+    // One SSCAN step; an intset is small enough to come back whole, as Redis does for its
+    // compact encodings
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<String>, u64) {
+        match self {
+            SetValue::Ints(_) => (self.members(), 0),
+            SetValue::Strings(_, order) => order.scan(cursor, count),
+        }
+    }
+
+    // Up to `count` distinct members, picked by index rather than by copying the set
+    pub fn random_distinct(&self, count: usize) -> Vec<String> {
+        random::distinct(self.len(), count)
+            .into_iter()
+            .map(|index| self.member_at(index))
+            .collect()
+    }
+}
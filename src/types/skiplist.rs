@@ -0,0 +1,292 @@
+use std::cmp::Ordering;
+
+use crate::random;
+
+const MAX_LEVEL: usize = 32;
+const HEAD: usize = 0;
+
+#[derive(Clone)]
+struct Level {
+    forward: Option<usize>,
+    // Number of level-0 links this pointer jumps over, used to compute ranks
+    span: usize,
+}
+
+#[derive(Clone)]
+struct Node {
+    member: String,
+    score: f64,
+    backward: Option<usize>,
+    levels: Vec<Level>,
+}
+
+// Skiplist ordered by (score, member), following the layout of Redis' zskiplist.
+// Nodes live in an arena and link to each other by index; index 0 is the header.
+#[derive(Clone)]
+pub struct SkipList {
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    tail: Option<usize>,
+    length: usize,
+    level: usize,
+}
+
+fn compare(score_a: f64, member_a: &str, score_b: f64, member_b: &str) -> Ordering {
+    score_a
+        .partial_cmp(&score_b)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| member_a.cmp(member_b))
+}
+
+// Same odds as Redis: each extra level is kept with probability 1/4
+fn random_level() -> usize {
+    let mut level = 1;
+    while level < MAX_LEVEL && random::next_u64() & 0xFFFF < 0xFFFF / 4 {
+        level += 1;
+    }
+    level
+}
+
+impl SkipList {
+    pub fn new() -> Self {
+        let head = Node {
+            member: String::new(),
+            score: 0.0,
+            backward: None,
+            levels: (0..MAX_LEVEL)
+                .map(|_| Level {
+                    forward: None,
+                    span: 0,
+                })
+                .collect(),
+        };
+        SkipList {
+            nodes: vec![Some(head)],
+            free: Vec::new(),
+            tail: None,
+            length: 0,
+            level: 1,
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    fn forward(&self, index: usize, level: usize) -> Option<usize> {
+        self.node(index).levels[level].forward
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn entry(&self, index: usize) -> (&str, f64) {
+        let node = self.node(index);
+        (&node.member, node.score)
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.forward(HEAD, 0)
+    }
+
+    pub fn last(&self) -> Option<usize> {
+        self.tail
+    }
+
+    pub fn next(&self, index: usize) -> Option<usize> {
+        self.forward(index, 0)
+    }
+
+    pub fn prev(&self, index: usize) -> Option<usize> {
+        self.node(index).backward
+    }
+
+    // For each level, finds the last node satisfying `is_before` along with its rank
+    fn find_update<F>(&self, is_before: F) -> ([usize; MAX_LEVEL], [usize; MAX_LEVEL])
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+            while let Some(next) = self.forward(x, i) {
+                if !is_before(self.node(next)) {
+                    break;
+                }
+                rank[i] += self.node(x).levels[i].span;
+                x = next;
+            }
+            update[i] = x;
+        }
+        (update, rank)
+    }
+
+    // The caller guarantees the member is not already present
+    pub fn insert(&mut self, score: f64, member: String) {
+        let (mut update, mut rank) = self.find_update(|node| {
+            compare(node.score, &node.member, score, &member) == Ordering::Less
+        });
+
+        let level = random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.node_mut(HEAD).levels[i].span = self.length;
+            }
+            self.level = level;
+        }
+
+        let node = Node {
+            member,
+            score,
+            backward: if update[0] == HEAD {
+                None
+            } else {
+                Some(update[0])
+            },
+            levels: (0..level)
+                .map(|_| Level {
+                    forward: None,
+                    span: 0,
+                })
+                .collect(),
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+
+        for i in 0..level {
+            let prev = update[i];
+            let forward = self.forward(prev, i);
+            let prev_span = self.node(prev).levels[i].span;
+            let new_level = &mut self.node_mut(index).levels[i];
+            new_level.forward = forward;
+            new_level.span = prev_span - (rank[0] - rank[i]);
+            let prev_level = &mut self.node_mut(prev).levels[i];
+            prev_level.forward = Some(index);
+            prev_level.span = (rank[0] - rank[i]) + 1;
+        }
+        for (i, &prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.node_mut(prev).levels[i].span += 1;
+        }
+
+        match self.forward(index, 0) {
+            Some(next) => self.node_mut(next).backward = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.length += 1;
+    }
+
+    fn unlink(&mut self, index: usize, update: &[usize; MAX_LEVEL]) {
+        for (i, &prev) in update.iter().enumerate().take(self.level) {
+            if self.forward(prev, i) == Some(index) {
+                let removed = &self.node(index).levels[i];
+                let (span, forward) = (removed.span, removed.forward);
+                let prev_level = &mut self.node_mut(prev).levels[i];
+                prev_level.span += span;
+                prev_level.span -= 1;
+                prev_level.forward = forward;
+            } else {
+                self.node_mut(prev).levels[i].span -= 1;
+            }
+        }
+
+        let backward = self.node(index).backward;
+        match self.forward(index, 0) {
+            Some(next) => self.node_mut(next).backward = backward,
+            None => self.tail = backward,
+        }
+        while self.level > 1 && self.forward(HEAD, self.level - 1).is_none() {
+            self.level -= 1;
+        }
+
+        self.nodes[index] = None;
+        self.free.push(index);
+        self.length -= 1;
+    }
+
+    pub fn remove(&mut self, score: f64, member: &str) -> bool {
+        let (update, _) = self
+            .find_update(|node| compare(node.score, &node.member, score, member) == Ordering::Less);
+
+        match self.forward(update[0], 0) {
+            Some(index)
+                if {
+                    let node = self.node(index);
+                    node.score == score && node.member == member
+                } =>
+            {
+                self.unlink(index, &update);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // 0-based rank of an element known to be in the list
+    pub fn rank(&self, score: f64, member: &str) -> Option<usize> {
+        let (update, rank) = self.find_update(|node| {
+            compare(node.score, &node.member, score, member) != Ordering::Greater
+        });
+        let node = self.node(update[0]);
+        if update[0] != HEAD && node.member == member {
+            Some(rank[0] - 1)
+        } else {
+            None
+        }
+    }
+
+    // Node at the given 0-based rank
+    pub fn by_rank(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut traversed = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                let span = self.node(x).levels[i].span;
+                if traversed + span > target {
+                    break;
+                }
+                traversed += span;
+                x = next;
+            }
+            if traversed == target {
+                return Some(x);
+            }
+        }
+        None
+    }
+
+    // First node for which `reached` holds, given that `reached` is monotonic
+    pub fn first_where<F>(&self, reached: F) -> Option<usize>
+    where
+        F: Fn(f64, &str) -> bool,
+    {
+        let (update, _) = self.find_update(|node| !reached(node.score, &node.member));
+        self.forward(update[0], 0)
+    }
+
+    // Last node for which `within` holds, given that `within` is monotonic
+    pub fn last_where<F>(&self, within: F) -> Option<usize>
+    where
+        F: Fn(f64, &str) -> bool,
+    {
+        let (update, _) = self.find_update(|node| within(node.score, &node.member));
+        Some(update[0]).filter(|&index| index != HEAD)
+    }
+}
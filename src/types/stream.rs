@@ -0,0 +1,380 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+// Entries are packed into nodes of this size, like Redis packs listpacks into its rax
+const NODE_MAX_ENTRIES: usize = 100;
+
+pub const INVALID_ID: &str = "Invalid stream ID specified as stream command argument";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    // Parses `ms-seq`, or a bare `ms` with the sequence filled in by `default_seq`
+    pub fn parse(input: &str, default_seq: u64) -> Result<Self, &'static str> {
+        let (ms, seq) = match input.split_once('-') {
+            Some((ms, seq)) => (ms, seq.parse::<u64>().map_err(|_| INVALID_ID)?),
+            None => (input, default_seq),
+        };
+        let ms = ms.parse::<u64>().map_err(|_| INVALID_ID)?;
+        Ok(StreamId { ms, seq })
+    }
+
+    pub fn next(self) -> Option<Self> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self.ms.checked_add(1).map(|ms| StreamId { ms, seq: 0 }),
+        }
+    }
+
+    pub fn prev(self) -> Option<Self> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self
+                .ms
+                .checked_sub(1)
+                .map(|ms| StreamId { ms, seq: u64::MAX }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+#[derive(Clone)]
+pub struct StreamEntry {
+    pub id: StreamId,
+    pub fields: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+#[derive(Clone, Copy)]
+pub enum TrimStrategy {
+    MaxLen(usize),
+    MinId(StreamId),
+}
+
+// A delivered but not yet acknowledged entry
+#[derive(Clone)]
+pub struct PendingEntry {
+    pub consumer: String,
+    pub delivery_time: u64,
+    pub delivery_count: u64,
+}
+
+#[derive(Clone)]
+pub struct Consumer {
+    // Last time the consumer attempted an interaction
+    pub seen_time: u64,
+    // Last time the consumer actually read or claimed something
+    pub active_time: Option<u64>,
+    pub pending: BTreeSet<StreamId>,
+}
+
+#[derive(Clone)]
+pub struct ConsumerGroup {
+    pub last_delivered_id: StreamId,
+    pub entries_read: Option<u64>,
+    pub pending: BTreeMap<StreamId, PendingEntry>,
+    pub consumers: BTreeMap<String, Consumer>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_delivered_id: StreamId, entries_read: Option<u64>) -> Self {
+        ConsumerGroup {
+            last_delivered_id,
+            entries_read,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    // Looks up or creates a consumer, marking it as seen
+    pub fn consumer(&mut self, name: &str, now: u64) -> &mut Consumer {
+        let consumer = self
+            .consumers
+            .entry(name.to_string())
+            .or_insert_with(|| Consumer {
+                seen_time: now,
+                active_time: None,
+                pending: BTreeSet::new(),
+            });
+        consumer.seen_time = now;
+        consumer
+    }
+
+    // Records `id` as delivered to `consumer`, taking it from any previous owner
+    pub fn assign(
+        &mut self,
+        id: StreamId,
+        consumer: &str,
+        delivery_time: u64,
+        delivery_count: u64,
+    ) {
+        if let Some(previous) = self.pending.get(&id) {
+            if let Some(owner) = self.consumers.get_mut(&previous.consumer) {
+                owner.pending.remove(&id);
+            }
+        }
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.to_string(),
+                delivery_time,
+                delivery_count,
+            },
+        );
+        if let Some(owner) = self.consumers.get_mut(consumer) {
+            owner.pending.insert(id);
+        }
+    }
+
+    pub fn ack(&mut self, id: StreamId) -> bool {
+        match self.pending.remove(&id) {
+            Some(entry) => {
+                if let Some(owner) = self.consumers.get_mut(&entry.consumer) {
+                    owner.pending.remove(&id);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Drops a consumer along with its pending entries, returning how many it had
+    pub fn delete_consumer(&mut self, name: &str) -> Option<usize> {
+        let consumer = self.consumers.remove(name)?;
+        for id in &consumer.pending {
+            self.pending.remove(id);
+        }
+        Some(consumer.pending.len())
+    }
+}
+
+#[derive(Clone)]
+pub struct Stream {
+    // Nodes keyed by the ID of their first entry
+    nodes: BTreeMap<StreamId, Vec<StreamEntry>>,
+    length: usize,
+    pub last_id: StreamId,
+    pub max_deleted_id: StreamId,
+    pub entries_added: u64,
+    pub groups: BTreeMap<String, ConsumerGroup>,
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Stream {
+            nodes: BTreeMap::new(),
+            length: 0,
+            last_id: StreamId::MIN,
+            max_deleted_id: StreamId::MIN,
+            entries_added: 0,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Resolves the ID for XADD: `*`, `ms-*` or an explicit `ms-seq`
+    pub fn next_id(&self, requested: &str, now_ms: u64) -> Result<StreamId, &'static str> {
+        let too_small =
+            "The ID specified in XADD is equal or smaller than the target stream top item";
+
+        if requested == "*" {
+            let id = if now_ms > self.last_id.ms {
+                StreamId { ms: now_ms, seq: 0 }
+            } else {
+                self.last_id.next().ok_or(
+                    "The stream has exhausted the last possible ID, unable to add more items",
+                )?
+            };
+            return Ok(id);
+        }
+
+        let id = match requested.strip_suffix("-*") {
+            Some(ms) => {
+                let ms = ms.parse::<u64>().map_err(|_| INVALID_ID)?;
+                if ms == self.last_id.ms {
+                    let seq = self.last_id.seq.checked_add(1).ok_or(too_small)?;
+                    StreamId { ms, seq }
+                } else {
+                    StreamId { ms, seq: 0 }
+                }
+            }
+            None => StreamId::parse(requested, 0)?,
+        };
+
+        if id == StreamId::MIN {
+            return Err("The ID specified in XADD must be greater than 0-0");
+        }
+        if id <= self.last_id {
+            return Err(too_small);
+        }
+        Ok(id)
+    }
+
+    // The caller guarantees `id` is greater than `last_id`
+    pub fn append(&mut self, id: StreamId, fields: Vec<(Vec<u8>, Vec<u8>)>) {
+        let entry = StreamEntry { id, fields };
+        match self.nodes.last_entry() {
+            Some(mut node) if node.get().len() < NODE_MAX_ENTRIES => node.get_mut().push(entry),
+            _ => {
+                self.nodes.insert(id, vec![entry]);
+            }
+        }
+        self.length += 1;
+        self.entries_added += 1;
+        self.last_id = id;
+    }
+
+    pub fn first_entry(&self) -> Option<&StreamEntry> {
+        self.nodes.values().next().and_then(|node| node.first())
+    }
+
+    pub fn last_entry(&self) -> Option<&StreamEntry> {
+        self.nodes.values().next_back().and_then(|node| node.last())
+    }
+
+    // Entries with IDs in `start..=end`, newest first when `reverse` is set
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        reverse: bool,
+    ) -> Vec<&StreamEntry> {
+        if start > end {
+            return Vec::new();
+        }
+        // The node holding `start` is keyed at or before it
+        let first_node = self
+            .nodes
+            .range(..=start)
+            .next_back()
+            .map_or(start, |(&key, _)| key);
+        let nodes = self.nodes.range(first_node..=end);
+        let in_range = |entry: &&StreamEntry| entry.id >= start && entry.id <= end;
+        let limit = count.unwrap_or(usize::MAX);
+
+        if reverse {
+            nodes
+                .rev()
+                .flat_map(|(_, node)| node.iter().rev())
+                .filter(in_range)
+                .take(limit)
+                .collect()
+        } else {
+            nodes
+                .flat_map(|(_, node)| node.iter())
+                .filter(in_range)
+                .take(limit)
+                .collect()
+        }
+    }
+
+    pub fn get(&self, id: StreamId) -> Option<&StreamEntry> {
+        self.range(id, id, Some(1), false).pop()
+    }
+
+    // Number of entries a group has yet to be delivered
+    pub fn lag(&self, group: &ConsumerGroup) -> usize {
+        match group.last_delivered_id.next() {
+            Some(start) => self.range(start, StreamId::MAX, None, false).len(),
+            None => 0,
+        }
+    }
+
+    pub fn delete(&mut self, id: StreamId) -> bool {
+        let Some((&key, node)) = self.nodes.range_mut(..=id).next_back() else {
+            return false;
+        };
+        let Ok(index) = node.binary_search_by_key(&id, |entry| entry.id) else {
+            return false;
+        };
+
+        node.remove(index);
+        if node.is_empty() {
+            self.nodes.remove(&key);
+        } else if index == 0 {
+            // Re-key the node by its new first entry
+            let node = self.nodes.remove(&key).unwrap();
+            self.nodes.insert(node[0].id, node);
+        }
+        self.length -= 1;
+        self.max_deleted_id = self.max_deleted_id.max(id);
+        true
+    }
+
+    // Evicts old entries; approximate trimming only drops whole nodes, at most `limit`
+    // entries' worth. Returns the number of entries removed.
+    pub fn trim(
+        &mut self,
+        strategy: TrimStrategy,
+        approximate: bool,
+        limit: Option<usize>,
+    ) -> usize {
+        let mut removed = 0;
+        let limit = limit.unwrap_or(usize::MAX);
+
+        while let Some(node) = self.nodes.first_entry() {
+            let node_len = node.get().len();
+            let whole_node_goes = match strategy {
+                TrimStrategy::MaxLen(max_len) => self.length - node_len >= max_len,
+                TrimStrategy::MinId(min_id) => node.get()[node_len - 1].id < min_id,
+            };
+
+            if whole_node_goes {
+                if removed + node_len > limit {
+                    break;
+                }
+                node.remove();
+                self.length -= node_len;
+                removed += node_len;
+                continue;
+            }
+            if approximate {
+                break;
+            }
+
+            // Exact trimming removes the remaining entries one by one
+            let drop = match strategy {
+                TrimStrategy::MaxLen(max_len) => self.length.saturating_sub(max_len),
+                TrimStrategy::MinId(min_id) => node
+                    .get()
+                    .iter()
+                    .take_while(|entry| entry.id < min_id)
+                    .count(),
+            };
+            if drop > 0 {
+                let mut entries = node.remove();
+                entries.drain(..drop);
+                self.nodes.insert(entries[0].id, entries);
+                self.length -= drop;
+                removed += drop;
+            }
+            break;
+        }
+
+        removed
+    }
+}
@@ -0,0 +1,188 @@
+// Merging t-digest: values are buffered and periodically folded into centroids whose
+// sizes are bounded by the k1 scale function, which keeps the centroids near the tails
+// small so extreme quantiles stay accurate.
+
+use std::f64::consts::PI;
+
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+#[derive(Clone, Copy)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+#[derive(Clone)]
+pub struct TDigest {
+    pub compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<Centroid>,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> Self {
+        TDigest {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.push(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+    }
+
+    fn push(&mut self, centroid: Centroid) {
+        self.min = self.min.min(centroid.mean);
+        self.max = self.max.max(centroid.mean);
+        self.buffer.push(centroid);
+        if self.buffer.len() >= (self.compression as usize * 5).max(100) {
+            self.compress();
+        }
+    }
+
+    // Folds another digest's data into this one
+    pub fn merge(&mut self, other: &TDigest) {
+        for &centroid in other.centroids.iter().chain(&other.buffer) {
+            self.push(centroid);
+        }
+        // Centroid means lie inside the other digest's range, so carry its extremes over
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    // The k1 scale function, spread so a digest holds up to about `compression` centroids
+    fn k(&self, q: f64) -> f64 {
+        self.compression / PI * (2.0 * q - 1.0).asin()
+    }
+
+    // Clamped at q = 1, past which the sine would turn back down
+    fn k_inverse(&self, k: f64) -> f64 {
+        ((k * PI / self.compression).min(PI / 2.0).sin() + 1.0) / 2.0
+    }
+
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut all: Vec<Centroid> = self
+            .centroids
+            .drain(..)
+            .chain(self.buffer.drain(..))
+            .collect();
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total: f64 = all.iter().map(|centroid| centroid.weight).sum();
+
+        let mut merged = Vec::new();
+        let mut current = all[0];
+        let mut weight_so_far = 0.0;
+        let mut limit = total * self.k_inverse(self.k(0.0) + 1.0);
+        for &next in &all[1..] {
+            if weight_so_far + current.weight + next.weight <= limit {
+                let weight = current.weight + next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_so_far += current.weight;
+                limit = total * self.k_inverse(self.k(weight_so_far / total) + 1.0);
+                merged.push(current);
+                current = next;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    fn compressed(&self) -> TDigest {
+        let mut digest = self.clone();
+        digest.compress();
+        digest
+    }
+
+    pub fn count(&self) -> f64 {
+        self.centroids
+            .iter()
+            .chain(&self.buffer)
+            .map(|centroid| centroid.weight)
+            .sum()
+    }
+
+    // Estimated value at quantile `q` in [0, 1]; NaN when empty
+    pub fn quantile(&self, q: f64) -> f64 {
+        let digest = self.compressed();
+        let centroids = &digest.centroids;
+        let Some((first, last)) = centroids.first().zip(centroids.last()) else {
+            return f64::NAN;
+        };
+        if q <= 0.0 {
+            return self.min;
+        }
+        if q >= 1.0 {
+            return self.max;
+        }
+
+        let total = digest.count();
+        let index = q * total;
+        // Below the first centroid's centre, interpolate from the minimum
+        if index < first.weight / 2.0 {
+            return self.min + (first.mean - self.min) * index / (first.weight / 2.0);
+        }
+        let mut weight_so_far = first.weight / 2.0;
+        for pair in centroids.windows(2) {
+            let step = (pair[0].weight + pair[1].weight) / 2.0;
+            if weight_so_far + step > index {
+                let fraction = (index - weight_so_far) / step;
+                return pair[0].mean + (pair[1].mean - pair[0].mean) * fraction;
+            }
+            weight_so_far += step;
+        }
+        // Above the last centroid's centre, interpolate towards the maximum
+        let fraction = (index - weight_so_far) / (last.weight / 2.0);
+        last.mean + (self.max - last.mean) * fraction.min(1.0)
+    }
+
+    // Estimated fraction of values at or below `value`; NaN when empty
+    pub fn cdf(&self, value: f64) -> f64 {
+        let digest = self.compressed();
+        let centroids = &digest.centroids;
+        if centroids.is_empty() {
+            return f64::NAN;
+        }
+        if value < self.min {
+            return 0.0;
+        }
+        if value >= self.max {
+            return 1.0;
+        }
+
+        // Cumulative weight at each centroid's centre, with the extremes pinned to 0 and 1
+        let total = digest.count();
+        let mut points = vec![(self.min, 0.0)];
+        let mut weight_so_far = 0.0;
+        for centroid in centroids {
+            points.push((centroid.mean, weight_so_far + centroid.weight / 2.0));
+            weight_so_far += centroid.weight;
+        }
+        points.push((self.max, total));
+
+        for pair in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            if value < x1 {
+                let fraction = if x1 > x0 {
+                    (value - x0) / (x1 - x0)
+                } else {
+                    1.0
+                };
+                return (y0 + (y1 - y0) * fraction) / total;
+            }
+        }
+        1.0
+    }
+}
@@ -0,0 +1,258 @@
+// Time series: float samples keyed by millisecond timestamp, with the labels, retention
+// window and compaction rules of RedisTimeSeries. Timestamps are ms since the epoch, and
+// as in RedisTimeSeries retention is measured back from the newest sample, not from the
+// clock: samples fall out of the window as newer ones are added, so historical data can
+// be backfilled into a series whatever the current time.
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DuplicatePolicy {
+    Block,
+    First,
+    Last,
+    Min,
+    Max,
+    Sum,
+}
+
+impl DuplicatePolicy {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.to_ascii_uppercase().as_str() {
+            "BLOCK" => Some(DuplicatePolicy::Block),
+            "FIRST" => Some(DuplicatePolicy::First),
+            "LAST" => Some(DuplicatePolicy::Last),
+            "MIN" => Some(DuplicatePolicy::Min),
+            "MAX" => Some(DuplicatePolicy::Max),
+            "SUM" => Some(DuplicatePolicy::Sum),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+}
+
+impl Aggregation {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.to_ascii_uppercase().as_str() {
+            "AVG" => Some(Aggregation::Avg),
+            "SUM" => Some(Aggregation::Sum),
+            "MIN" => Some(Aggregation::Min),
+            "MAX" => Some(Aggregation::Max),
+            "COUNT" => Some(Aggregation::Count),
+            _ => None,
+        }
+    }
+
+    fn apply(self, values: &[f64]) -> f64 {
+        let sum = || values.iter().sum::<f64>();
+        match self {
+            Aggregation::Avg => sum() / values.len() as f64,
+            Aggregation::Sum => sum(),
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Count => values.len() as f64,
+        }
+    }
+}
+
+// Start of the bucket holding `timestamp`, with buckets aligned to the epoch
+pub fn bucket_start(timestamp: u64, bucket: u64) -> u64 {
+    timestamp - timestamp % bucket
+}
+
+// Folds ascending samples into one (bucket start, value) pair per non-empty bucket
+pub fn aggregate(
+    samples: impl Iterator<Item = (u64, f64)>,
+    aggregation: Aggregation,
+    bucket: u64,
+) -> Vec<(u64, f64)> {
+    let mut result = Vec::new();
+    let mut current: Option<(u64, Vec<f64>)> = None;
+    for (timestamp, value) in samples {
+        let start = bucket_start(timestamp, bucket);
+        match &mut current {
+            Some((open, values)) if *open == start => values.push(value),
+            _ => {
+                if let Some((open, values)) = current.take() {
+                    result.push((open, aggregation.apply(&values)));
+                }
+                current = Some((start, vec![value]));
+            }
+        }
+    }
+    if let Some((open, values)) = current {
+        result.push((open, aggregation.apply(&values)));
+    }
+    result
+}
+
+// Downsamples every sample of the source into `destination` using buckets of `bucket` ms
+#[derive(Clone)]
+pub struct Rule {
+    pub destination: String,
+    pub aggregation: Aggregation,
+    pub bucket: u64,
+}
+
+// One TS.MRANGE filter term: `label=value`, `label=(a,b)`, `label!=value` and so on.
+// An empty value list matches series without the label.
+pub struct LabelFilter {
+    label: String,
+    values: Vec<String>,
+    negated: bool,
+}
+
+impl LabelFilter {
+    pub fn parse(input: &str) -> Option<Self> {
+        let (label, value, negated) = match input.split_once("!=") {
+            Some((label, value)) => (label, value, true),
+            None => input
+                .split_once('=')
+                .map(|(label, value)| (label, value, false))?,
+        };
+        if label.is_empty() {
+            return None;
+        }
+        let values = match value.strip_prefix('(') {
+            Some(list) => list
+                .strip_suffix(')')?
+                .split(',')
+                .map(|value| value.trim().to_string())
+                .collect(),
+            None if value.is_empty() => Vec::new(),
+            None => vec![value.to_string()],
+        };
+        Some(LabelFilter {
+            label: label.to_string(),
+            values,
+            negated,
+        })
+    }
+
+    pub fn matches(&self, labels: &[(String, String)]) -> bool {
+        let value = labels
+            .iter()
+            .find(|(label, _)| *label == self.label)
+            .map(|(_, value)| value);
+        let matched = match value {
+            Some(value) => self.values.contains(value),
+            None => self.values.is_empty(),
+        };
+        // `label!=` means the label is present, which the negation above gives for free
+        matched != self.negated
+    }
+}
+
+#[derive(Clone)]
+pub struct TimeSeries {
+    // Window in ms kept behind the current time; 0 keeps everything
+    pub retention: u64,
+    pub duplicate_policy: DuplicatePolicy,
+    pub labels: Vec<(String, String)>,
+    pub rules: Vec<Rule>,
+    // The series this one is compacted from, if any
+    pub source: Option<String>,
+    samples: BTreeMap<u64, f64>,
+}
+
+impl TimeSeries {
+    pub fn new(
+        retention: u64,
+        duplicate_policy: DuplicatePolicy,
+        labels: Vec<(String, String)>,
+    ) -> Self {
+        TimeSeries {
+            retention,
+            duplicate_policy,
+            labels,
+            rules: Vec::new(),
+            source: None,
+            samples: BTreeMap::new(),
+        }
+    }
+
+    pub fn last(&self) -> Option<(u64, f64)> {
+        let cutoff = self.cutoff();
+        self.samples
+            .last_key_value()
+            .filter(|(&timestamp, _)| timestamp >= cutoff)
+            .map(|(&timestamp, &value)| (timestamp, value))
+    }
+
+    // Oldest timestamp still inside the retention window
+    fn cutoff(&self) -> u64 {
+        if self.retention == 0 {
+            return 0;
+        }
+        let newest = self
+            .samples
+            .last_key_value()
+            .map_or(0, |(&newest, _)| newest);
+        newest.saturating_sub(self.retention)
+    }
+
+    // Stores a sample, resolving an existing one at the same timestamp with `policy` (or
+    // the series' own policy), and returns the value that ends up stored
+    pub fn add(
+        &mut self,
+        timestamp: u64,
+        value: f64,
+        policy: Option<DuplicatePolicy>,
+    ) -> Result<f64, &'static str> {
+        if timestamp < self.cutoff() {
+            return Err("TSDB: Timestamp is older than retention");
+        }
+        let stored = match self.samples.get(&timestamp) {
+            None => value,
+            Some(&existing) => match policy.unwrap_or(self.duplicate_policy) {
+                DuplicatePolicy::Block => {
+                    return Err("TSDB: Error at upsert, update is not supported when DUPLICATE_POLICY is set to BLOCK mode")
+                }
+                DuplicatePolicy::First => existing,
+                DuplicatePolicy::Last => value,
+                DuplicatePolicy::Min => existing.min(value),
+                DuplicatePolicy::Max => existing.max(value),
+                DuplicatePolicy::Sum => existing + value,
+            },
+        };
+        self.samples.insert(timestamp, stored);
+        self.trim();
+        Ok(stored)
+    }
+
+    // Overwrites a compacted bucket, which is not subject to the duplicate policy
+    pub fn upsert(&mut self, timestamp: u64, value: f64) {
+        self.samples.insert(timestamp, value);
+        self.trim();
+    }
+
+    // Drops samples that fell out of the retention window
+    pub fn trim(&mut self) {
+        let cutoff = self.cutoff();
+        while self
+            .samples
+            .first_key_value()
+            .is_some_and(|(&oldest, _)| oldest < cutoff)
+        {
+            self.samples.pop_first();
+        }
+    }
+
+    // Samples with timestamps in [from, to], oldest first
+    pub fn range(&self, from: u64, to: u64) -> impl DoubleEndedIterator<Item = (u64, f64)> + '_ {
+        // BTreeMap::range panics on an inverted range, which simply selects nothing here
+        let from = from.max(self.cutoff());
+        self.samples
+            .range(from..=to.max(from))
+            .filter(move |(&timestamp, _)| timestamp <= to)
+            .map(|(&timestamp, &value)| (timestamp, value))
+    }
+}
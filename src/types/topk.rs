@@ -0,0 +1,102 @@
+// Top-K heavy hitters using HeavyKeeper, as RedisBloom does: a grid of fingerprinted
+// counters where colliding items decay each other's counts with probability
+// `decay^count`, so only frequent items hold on to their buckets. The k heaviest items
+// seen so far are kept alongside the grid.
+
+use std::cmp::Reverse;
+
+use crate::{random, types::hyperloglog::murmurhash64a};
+
+pub const DEFAULT_WIDTH: usize = 8;
+pub const DEFAULT_DEPTH: usize = 7;
+pub const DEFAULT_DECAY: f64 = 0.9;
+const MAX_K: usize = 1 << 20;
+
+// Keeps the counter grid within 512MB
+const MAX_BUCKETS: usize = 1 << 25;
+
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    fingerprint: u64,
+    count: u64,
+}
+
+#[derive(Clone)]
+pub struct TopK {
+    pub k: usize,
+    pub width: usize,
+    pub depth: usize,
+    pub decay: f64,
+    buckets: Vec<Bucket>,
+    // (item, count), kept sorted by descending count
+    heap: Vec<(String, u64)>,
+}
+
+impl TopK {
+    // None when k is over MAX_K or the grid would hold more than MAX_BUCKETS counters
+    pub fn new(k: usize, width: usize, depth: usize, decay: f64) -> Option<Self> {
+        let cells = width
+            .checked_mul(depth)
+            .filter(|&cells| cells <= MAX_BUCKETS && k <= MAX_K)?;
+        Some(TopK {
+            k,
+            width,
+            depth,
+            decay,
+            buckets: vec![Bucket::default(); cells],
+            heap: Vec::with_capacity(k),
+        })
+    }
+
+    // Counts one occurrence of `item`, returning the item it pushed out of the top k
+    pub fn add(&mut self, item: &str) -> Option<String> {
+        let fingerprint = murmurhash64a(item.as_bytes(), 0);
+        let mut count = 0;
+        for row in 0..self.depth {
+            let column = murmurhash64a(item.as_bytes(), row as u64 + 1) % self.width as u64;
+            let bucket = &mut self.buckets[row * self.width + column as usize];
+            if bucket.count == 0 {
+                *bucket = Bucket {
+                    fingerprint,
+                    count: 1,
+                };
+            } else if bucket.fingerprint == fingerprint {
+                bucket.count += 1;
+            } else if random::unit() < self.decay.powf(bucket.count as f64) {
+                bucket.count -= 1;
+                if bucket.count == 0 {
+                    *bucket = Bucket {
+                        fingerprint,
+                        count: 1,
+                    };
+                }
+            }
+            if bucket.fingerprint == fingerprint {
+                count = count.max(bucket.count);
+            }
+        }
+
+        if let Some(index) = self.heap.iter().position(|(member, _)| member == item) {
+            self.heap[index].1 = count;
+            self.heap.sort_by_key(|(_, count)| Reverse(*count));
+            return None;
+        }
+        if self.heap.len() < self.k {
+            self.heap.push((item.to_string(), count));
+            self.heap.sort_by_key(|(_, count)| Reverse(*count));
+            return None;
+        }
+        if self.heap.last().is_some_and(|(_, min)| count > *min) {
+            let expelled = self.heap.pop().map(|(member, _)| member);
+            self.heap.push((item.to_string(), count));
+            self.heap.sort_by_key(|(_, count)| Reverse(*count));
+            return expelled;
+        }
+        None
+    }
+
+    // The current top items, heaviest first
+    pub fn list(&self) -> &[(String, u64)] {
+        &self.heap
+    }
+}
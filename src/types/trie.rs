@@ -0,0 +1,140 @@
+// Suggestion dictionary for FT.SUGADD/FT.SUGGET: a character trie whose nodes may end a
+// weighted entry. Lookups are case-insensitive, as in RediSearch, and the original
+// spelling of each entry is kept for replies.
+
+use std::collections::BTreeMap;
+
+#[derive(Clone)]
+pub struct Suggestion {
+    pub string: String,
+    pub score: f64,
+    pub payload: Option<String>,
+}
+
+#[derive(Clone, Default)]
+struct Node {
+    children: BTreeMap<char, Node>,
+    entry: Option<Suggestion>,
+}
+
+impl Node {
+    fn collect<'a>(&'a self, found: &mut Vec<&'a Suggestion>) {
+        found.extend(&self.entry);
+        for child in self.children.values() {
+            child.collect(found);
+        }
+    }
+
+    // Removes the entry at `path`, pruning nodes left without entries or children
+    fn remove(&mut self, path: &[char]) -> bool {
+        let Some((first, rest)) = path.split_first() else {
+            return self.entry.take().is_some();
+        };
+        let Some(child) = self.children.get_mut(first) else {
+            return false;
+        };
+        let removed = child.remove(rest);
+        if child.entry.is_none() && child.children.is_empty() {
+            self.children.remove(first);
+        }
+        removed
+    }
+
+    // Walks the trie keeping the Levenshtein row of `query` against the path so far, and
+    // collects every subtree whose path starts within `distance` edits of the query
+    fn fuzzy<'a>(
+        &'a self,
+        query: &[char],
+        row: &[usize],
+        distance: usize,
+        found: &mut Vec<&'a Suggestion>,
+    ) {
+        if row[query.len()] <= distance {
+            self.collect(found);
+            return;
+        }
+        for (&c, child) in &self.children {
+            let mut next = vec![row[0] + 1];
+            for (i, &q) in query.iter().enumerate() {
+                let substitution = row[i] + usize::from(q != c);
+                next.push(substitution.min(row[i + 1] + 1).min(next[i] + 1));
+            }
+            if next.iter().min().is_some_and(|&best| best <= distance) {
+                child.fuzzy(query, &next, distance, found);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SuggestionTrie {
+    root: Node,
+    len: usize,
+}
+
+fn fold(string: &str) -> Vec<char> {
+    string.chars().flat_map(char::to_lowercase).collect()
+}
+
+impl SuggestionTrie {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Adds an entry, or with `incr` raises an existing entry's score by `score`
+    pub fn add(&mut self, string: &str, score: f64, incr: bool, payload: Option<String>) {
+        let mut node = &mut self.root;
+        for c in fold(string) {
+            node = node.children.entry(c).or_default();
+        }
+        match &mut node.entry {
+            Some(entry) => {
+                entry.string = string.to_string();
+                entry.score = if incr { entry.score + score } else { score };
+                if payload.is_some() {
+                    entry.payload = payload;
+                }
+            }
+            None => {
+                node.entry = Some(Suggestion {
+                    string: string.to_string(),
+                    score,
+                    payload,
+                });
+                self.len += 1;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, string: &str) -> bool {
+        let removed = self.root.remove(&fold(string));
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    // Entries starting with `prefix` (or within one edit of it when `fuzzy`), best first
+    pub fn complete(&self, prefix: &str, fuzzy: bool) -> Vec<&Suggestion> {
+        let query = fold(prefix);
+        let mut found = Vec::new();
+        if fuzzy {
+            let row: Vec<usize> = (0..=query.len()).collect();
+            self.root.fuzzy(&query, &row, 1, &mut found);
+        } else {
+            let mut node = Some(&self.root);
+            for c in &query {
+                node = node.and_then(|node| node.children.get(c));
+            }
+            if let Some(node) = node {
+                node.collect(&mut found);
+            }
+        }
+        found.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.string.cmp(&b.string))
+        });
+        found
+    }
+}
@@ -0,0 +1,410 @@
+// Vector sets: named float32 vectors with optional JSON attributes, indexed by an HNSW
+// graph (a hierarchy of proximity graphs searched greedily from the sparse top layer
+// down). Queries can also bypass the graph and scan every element exactly.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use serde_json::Value;
+
+use crate::{random, types::json::JsonFilter};
+
+pub const DEFAULT_M: usize = 16;
+pub const DEFAULT_EF: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Metric {
+    Cosine,
+    L2,
+}
+
+impl Metric {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.to_ascii_uppercase().as_str() {
+            "COSINE" => Some(Metric::Cosine),
+            "L2" => Some(Metric::L2),
+            _ => None,
+        }
+    }
+
+    // Cosine distances in [0, 2] are reported as similarities in [0, 1], as Redis does;
+    // L2 distances are reported as they are
+    pub fn score(self, distance: f32) -> f64 {
+        match self {
+            Metric::Cosine => 1.0 - distance as f64 / 2.0,
+            Metric::L2 => distance as f64,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Node {
+    name: String,
+    vector: Vec<f32>,
+    norm: f32,
+    attributes: Option<Value>,
+    // Neighbour ids on each layer the node lives in, layer 0 first
+    links: Vec<Vec<usize>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+#[derive(Clone)]
+pub struct VectorSet {
+    pub dim: usize,
+    pub metric: Metric,
+    pub m: usize,
+    // Slots freed by removals are reused so ids stay small
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    ids: HashMap<String, usize>,
+    entry: Option<usize>,
+}
+
+impl VectorSet {
+    pub fn new(dim: usize, metric: Metric, m: usize) -> Self {
+        VectorSet {
+            dim,
+            metric,
+            m,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            ids: HashMap::new(),
+            entry: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn node(&self, id: usize) -> &Node {
+        self.nodes[id].as_ref().expect("linked node exists")
+    }
+
+    fn node_mut(&mut self, id: usize) -> &mut Node {
+        self.nodes[id].as_mut().expect("linked node exists")
+    }
+
+    fn get(&self, name: &str) -> Option<&Node> {
+        self.ids.get(name).map(|&id| self.node(id))
+    }
+
+    pub fn vector(&self, name: &str) -> Option<&[f32]> {
+        self.get(name).map(|node| node.vector.as_slice())
+    }
+
+    pub fn attributes(&self, name: &str) -> Option<&Value> {
+        self.get(name)?.attributes.as_ref()
+    }
+
+    // Replaces an element's attributes, returning false when the element is missing
+    pub fn set_attributes(&mut self, name: &str, attributes: Option<Value>) -> bool {
+        let Some(&id) = self.ids.get(name) else {
+            return false;
+        };
+        self.node_mut(id).attributes = attributes;
+        true
+    }
+
+    fn distance(&self, vector: &[f32], norm: f32, id: usize) -> f32 {
+        let node = self.node(id);
+        match self.metric {
+            Metric::Cosine if norm == 0.0 || node.norm == 0.0 => 1.0,
+            Metric::Cosine => {
+                let dot: f32 = vector.iter().zip(&node.vector).map(|(a, b)| a * b).sum();
+                1.0 - dot / (norm * node.norm)
+            }
+            Metric::L2 => vector
+                .iter()
+                .zip(&node.vector)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f32>()
+                .sqrt(),
+        }
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.m * 2
+        } else {
+            self.m
+        }
+    }
+
+    // Best-first search of one layer from `entries`, keeping the `ef` closest accepted
+    // nodes. Rejected nodes are still walked through, so filtered searches widen until
+    // enough matches turn up. Returns candidates closest first.
+    fn search_layer(
+        &self,
+        vector: &[f32],
+        norm: f32,
+        entries: &[usize],
+        ef: usize,
+        layer: usize,
+        accept: &dyn Fn(&Node) -> bool,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+        for &id in entries {
+            let candidate = Candidate {
+                distance: self.distance(vector, norm, id),
+                id,
+            };
+            candidates.push(Reverse(candidate));
+            if accept(self.node(id)) {
+                results.push(candidate);
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(closest)) = candidates.pop() {
+            let worst = results.peek().map(|worst: &Candidate| worst.distance);
+            if results.len() >= ef && worst.is_some_and(|worst| closest.distance > worst) {
+                break;
+            }
+            for &id in &self.node(closest.id).links[layer] {
+                if !visited.insert(id) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance(vector, norm, id),
+                    id,
+                };
+                let worst = results.peek().map(|worst: &Candidate| worst.distance);
+                if results.len() < ef || worst.is_some_and(|worst| candidate.distance < worst) {
+                    candidates.push(Reverse(candidate));
+                    if accept(self.node(id)) {
+                        results.push(candidate);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    // Greedy descent from the entry point to `layer`, one closest node per layer
+    fn descend(&self, vector: &[f32], norm: f32, layer: usize) -> Vec<usize> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        let mut entries = vec![entry];
+        for upper in (layer + 1..self.node(entry).links.len()).rev() {
+            let found = self.search_layer(vector, norm, &entries, 1, upper, &|_| true);
+            entries = found.iter().map(|candidate| candidate.id).take(1).collect();
+        }
+        entries
+    }
+
+    // Keeps only the closest `max_links` neighbours of `id` on `layer`
+    fn prune(&mut self, id: usize, layer: usize, links: Vec<usize>) {
+        let node = self.node(id);
+        let mut candidates: Vec<Candidate> = links
+            .into_iter()
+            .map(|other| Candidate {
+                distance: self.distance(&node.vector, node.norm, other),
+                id: other,
+            })
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        let links = candidates
+            .into_iter()
+            .map(|candidate| candidate.id)
+            .take(self.max_links(layer))
+            .collect();
+        self.node_mut(id).links[layer] = links;
+    }
+
+    // Adds or replaces an element, returning true when it is new. Replacing keeps the
+    // existing attributes unless new ones are given.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        vector: Vec<f32>,
+        attributes: Option<Value>,
+        ef: usize,
+    ) -> bool {
+        let mut attributes = attributes;
+        let existed = match self.ids.get(name) {
+            Some(&id) => {
+                attributes = attributes.or(self.node_mut(id).attributes.take());
+                self.remove(name)
+            }
+            None => false,
+        };
+
+        // Layer drawn from a geometric distribution with ratio 1/M
+        let multiplier = 1.0 / (self.m.max(2) as f64).ln();
+        let level = (-random::unit().max(f64::MIN_POSITIVE).ln() * multiplier) as usize;
+        let norm = norm(&vector);
+        let node = Node {
+            name: name.to_string(),
+            vector,
+            norm,
+            attributes,
+            links: vec![Vec::new(); level + 1],
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Some(node);
+                id
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.ids.insert(name.to_string(), id);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(id);
+            return !existed;
+        };
+        let top = self.node(entry).links.len() - 1;
+        let vector = self.node(id).vector.clone();
+        let mut entries = self.descend(&vector, norm, level.min(top));
+        for layer in (0..=level.min(top)).rev() {
+            let found =
+                self.search_layer(&vector, norm, &entries, ef.max(self.m), layer, &|_| true);
+            let neighbours: Vec<usize> = found
+                .iter()
+                .map(|candidate| candidate.id)
+                .take(self.max_links(layer))
+                .collect();
+            for &neighbour in &neighbours {
+                let mut links = self.node(neighbour).links[layer].clone();
+                links.push(id);
+                if links.len() > self.max_links(layer) {
+                    self.prune(neighbour, layer, links);
+                } else {
+                    self.node_mut(neighbour).links[layer] = links;
+                }
+            }
+            self.node_mut(id).links[layer] = neighbours;
+            entries = found.iter().map(|candidate| candidate.id).collect();
+        }
+        if level > top {
+            self.entry = Some(id);
+        }
+        !existed
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let Some(id) = self.ids.remove(name) else {
+            return false;
+        };
+        let node = self.nodes[id].take().expect("indexed node exists");
+        self.free.push(id);
+
+        // Unlink the node and patch the hole: each former neighbour considers the
+        // removed node's other neighbours as replacement links
+        for (layer, neighbours) in node.links.iter().enumerate() {
+            for &neighbour in neighbours {
+                let Some(Some(linked)) = self.nodes.get(neighbour) else {
+                    continue;
+                };
+                let links = linked.links[layer]
+                    .iter()
+                    .chain(neighbours)
+                    .copied()
+                    .filter(|&other| other != id && other != neighbour)
+                    .collect();
+                self.prune(neighbour, layer, links);
+            }
+        }
+        // Pruning drops back links, so other nodes may still point at the removed one
+        for slot in self.nodes.iter_mut().flatten() {
+            for links in slot.links.iter_mut() {
+                links.retain(|&other| other != id);
+            }
+        }
+
+        if self.entry == Some(id) {
+            self.entry = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter_map(|(id, node)| Some((id, node.as_ref()?.links.len())))
+                .max_by_key(|&(_, levels)| levels)
+                .map(|(id, _)| id);
+        }
+        true
+    }
+
+    // The `count` nearest elements to `vector` whose attributes pass `filter`, closest
+    // first, through the HNSW graph or, when `exact`, by scanning every element
+    pub fn search(
+        &self,
+        vector: &[f32],
+        count: usize,
+        ef: usize,
+        filter: Option<&JsonFilter>,
+        exact: bool,
+    ) -> Vec<(&str, f64)> {
+        let accept = |node: &Node| match filter {
+            Some(filter) => node
+                .attributes
+                .as_ref()
+                .is_some_and(|attributes| filter.matches(attributes)),
+            None => true,
+        };
+        let norm = norm(vector);
+        let found = if exact {
+            let mut all: Vec<Candidate> = self
+                .ids
+                .values()
+                .filter(|&&id| accept(self.node(id)))
+                .map(|&id| Candidate {
+                    distance: self.distance(vector, norm, id),
+                    id,
+                })
+                .collect();
+            all.sort();
+            all
+        } else {
+            let entries = self.descend(vector, norm, 0);
+            self.search_layer(vector, norm, &entries, ef.max(count), 0, &accept)
+        };
+        found
+            .into_iter()
+            .take(count)
+            .map(|candidate| {
+                let node = self.node(candidate.id);
+                (node.name.as_str(), self.metric.score(candidate.distance))
+            })
+            .collect()
+    }
+}
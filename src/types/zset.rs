@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use crate::types::{scan::ScanIndex, skiplist::SkipList};
+
+pub const INVALID_FLOAT: &str = "value is not a valid float";
+
+// Scores print the way Redis prints doubles, with printf's %.17g: "1", "1.5", "1e+20",
+// "inf", "-inf"
+pub fn format_score(score: f64) -> String {
+    if score.is_infinite() {
+        return if score > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    // Seventeen significant digits, then %g's choice between plain and exponent notation
+    let scientific = format!("{:.16e}", score);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let trim = |digits: &str| {
+        match digits.contains('.') {
+            true => digits.trim_end_matches('0').trim_end_matches('.'),
+            false => digits,
+        }
+        .to_string()
+    };
+    if (-4..17).contains(&exponent) {
+        trim(&format!("{:.*}", (16 - exponent) as usize, score))
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim(mantissa), sign, exponent.abs())
+    }
+}
+
+pub fn parse_score(input: &str) -> Result<f64, &'static str> {
+    match input.to_ascii_lowercase().as_str() {
+        "inf" | "+inf" => Ok(f64::INFINITY),
+        "-inf" => Ok(f64::NEG_INFINITY),
+        other => other
+            .parse::<f64>()
+            .ok()
+            .filter(|score| score.is_finite())
+            .ok_or(INVALID_FLOAT),
+    }
+}
+
+// Score interval such as `(1 5` or `-inf +inf`
+pub struct ScoreRange {
+    pub min: f64,
+    pub max: f64,
+    pub min_exclusive: bool,
+    pub max_exclusive: bool,
+}
+
+impl ScoreRange {
+    pub fn parse(min: &str, max: &str) -> Result<Self, &'static str> {
+        fn bound(input: &str) -> Result<(f64, bool), &'static str> {
+            let (value, exclusive) = match input.strip_prefix('(') {
+                Some(rest) => (rest, true),
+                None => (input, false),
+            };
+            parse_score(value)
+                .map(|value| (value, exclusive))
+                .map_err(|_| "min or max is not a float")
+        }
+
+        let (min, min_exclusive) = bound(min)?;
+        let (max, max_exclusive) = bound(max)?;
+        Ok(ScoreRange {
+            min,
+            max,
+            min_exclusive,
+            max_exclusive,
+        })
+    }
+
+    pub fn above_min(&self, score: f64) -> bool {
+        if self.min_exclusive {
+            score > self.min
+        } else {
+            score >= self.min
+        }
+    }
+
+    pub fn below_max(&self, score: f64) -> bool {
+        if self.max_exclusive {
+            score < self.max
+        } else {
+            score <= self.max
+        }
+    }
+}
+
+pub enum LexBound {
+    NegInf,
+    PosInf,
+    Inclusive(String),
+    Exclusive(String),
+}
+
+// Member interval such as `[a (c` or `- +`, only meaningful when all scores are equal
+pub struct LexRange {
+    pub min: LexBound,
+    pub max: LexBound,
+}
+
+impl LexRange {
+    pub fn parse(min: &str, max: &str) -> Result<Self, &'static str> {
+        fn bound(input: &str) -> Result<LexBound, &'static str> {
+            match input {
+                "-" => Ok(LexBound::NegInf),
+                "+" => Ok(LexBound::PosInf),
+                _ => match input.split_at_checked(1) {
+                    Some(("[", rest)) => Ok(LexBound::Inclusive(rest.to_string())),
+                    Some(("(", rest)) => Ok(LexBound::Exclusive(rest.to_string())),
+                    _ => Err("min or max not valid string range item"),
+                },
+            }
+        }
+
+        Ok(LexRange {
+            min: bound(min)?,
+            max: bound(max)?,
+        })
+    }
+
+    pub fn above_min(&self, member: &str) -> bool {
+        match &self.min {
+            LexBound::NegInf => true,
+            LexBound::PosInf => false,
+            LexBound::Inclusive(min) => member >= min.as_str(),
+            LexBound::Exclusive(min) => member > min.as_str(),
+        }
+    }
+
+    pub fn below_max(&self, member: &str) -> bool {
+        match &self.max {
+            LexBound::NegInf => false,
+            LexBound::PosInf => true,
+            LexBound::Inclusive(max) => member <= max.as_str(),
+            LexBound::Exclusive(max) => member < max.as_str(),
+        }
+    }
+}
+
+// Sorted set: the dict answers score lookups in O(1), the skiplist keeps order
+#[derive(Clone)]
+pub struct SortedSet {
+    dict: HashMap<String, f64>,
+    list: SkipList,
+    // Orders members for ZSCAN
+    order: ScanIndex,
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        SortedSet {
+            dict: HashMap::new(),
+            list: SkipList::new(),
+            order: ScanIndex::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.dict.get(member).copied()
+    }
+
+    // Adds the member or moves it to a new score; returns true if it was new
+    pub fn insert(&mut self, member: String, score: f64) -> bool {
+        match self.dict.insert(member.clone(), score) {
+            Some(old) if old == score => false,
+            Some(old) => {
+                self.list.remove(old, &member);
+                self.list.insert(score, member);
+                false
+            }
+            None => {
+                self.order.insert(&member);
+                self.list.insert(score, member);
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self.dict.remove(member) {
+            Some(score) => {
+                self.order.remove(member);
+                self.list.remove(score, member)
+            }
+            None => false,
+        }
+    }
+
+    // 0-based position, counted from the highest score when `reverse` is set
+    pub fn rank(&self, member: &str, reverse: bool) -> Option<usize> {
+        let score = self.score(member)?;
+        let rank = self.list.rank(score, member)?;
+        Some(if reverse { self.len() - 1 - rank } else { rank })
+    }
+
+    fn collect<F>(
+        &self,
+        start: Option<usize>,
+        reverse: bool,
+        offset: usize,
+        limit: Option<usize>,
+        within: F,
+    ) -> Vec<(String, f64)>
+    where
+        F: Fn(f64, &str) -> bool,
+    {
+        let mut result = Vec::new();
+        let mut current = start;
+        let mut skipped = 0;
+        while let Some(index) = current {
+            if limit.is_some_and(|limit| result.len() >= limit) {
+                break;
+            }
+            let (member, score) = self.list.entry(index);
+            if !within(score, member) {
+                break;
+            }
+            if skipped < offset {
+                skipped += 1;
+            } else {
+                result.push((member.to_string(), score));
+            }
+            current = if reverse {
+                self.list.prev(index)
+            } else {
+                self.list.next(index)
+            };
+        }
+        result
+    }
+
+    // Elements between two inclusive 0-based ranks, already clamped by the caller
+    pub fn range_by_rank(&self, start: usize, stop: usize, reverse: bool) -> Vec<(String, f64)> {
+        if start > stop || start >= self.len() {
+            return Vec::new();
+        }
+        let first = if reverse {
+            self.list.by_rank(self.len() - 1 - start)
+        } else {
+            self.list.by_rank(start)
+        };
+        self.collect(first, reverse, 0, Some(stop - start + 1), |_, _| true)
+    }
+
+    pub fn range_by_score(
+        &self,
+        range: &ScoreRange,
+        reverse: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Vec<(String, f64)> {
+        if reverse {
+            let start = self.list.last_where(|score, _| range.below_max(score));
+            self.collect(start, true, offset, limit, |score, _| {
+                range.above_min(score)
+            })
+        } else {
+            let start = self.list.first_where(|score, _| range.above_min(score));
+            self.collect(start, false, offset, limit, |score, _| {
+                range.below_max(score)
+            })
+        }
+    }
+
+    pub fn range_by_lex(
+        &self,
+        range: &LexRange,
+        reverse: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Vec<(String, f64)> {
+        if reverse {
+            let start = self.list.last_where(|_, member| range.below_max(member));
+            self.collect(start, true, offset, limit, |_, member| {
+                range.above_min(member)
+            })
+        } else {
+            let start = self.list.first_where(|_, member| range.above_min(member));
+            self.collect(start, false, offset, limit, |_, member| {
+                range.below_max(member)
+            })
+        }
+    }
+
+    // Number of elements between two nodes, found via their ranks
+    fn count_between(&self, first: Option<usize>, last: Option<usize>) -> usize {
+        match (first, last) {
+            (Some(first), Some(last)) => {
+                let (first_member, first_score) = self.list.entry(first);
+                let (last_member, last_score) = self.list.entry(last);
+                let first_rank = self.list.rank(first_score, first_member).unwrap_or(0);
+                let last_rank = self.list.rank(last_score, last_member).unwrap_or(0);
+                (last_rank + 1).saturating_sub(first_rank)
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn count_by_score(&self, range: &ScoreRange) -> usize {
+        self.count_between(
+            self.list.first_where(|score, _| range.above_min(score)),
+            self.list.last_where(|score, _| range.below_max(score)),
+        )
+    }
+
+    pub fn count_by_lex(&self, range: &LexRange) -> usize {
+        self.count_between(
+            self.list.first_where(|_, member| range.above_min(member)),
+            self.list.last_where(|_, member| range.below_max(member)),
+        )
+    }
+
+    // Removes and returns up to `count` elements from the low or high end
+    pub fn pop(&mut self, count: usize, highest: bool) -> Vec<(String, f64)> {
+        let popped = if highest {
+            self.collect(self.list.last(), true, 0, Some(count), |_, _| true)
+        } else {
+            self.collect(self.list.first(), false, 0, Some(count), |_, _| true)
+        };
+        for (member, _) in &popped {
+            self.remove(member);
+        }
+        popped
+    }
+
+    pub fn entries(&self) -> Vec<(String, f64)> {
+        self.collect(self.list.first(), false, 0, None, |_, _| true)
+    }
+
+    // One ZSCAN step, with each member's score
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<(String, f64)>, u64) {
+        let (members, cursor) = self.order.scan(cursor, count);
+        let entries = members
+            .into_iter()
+            .map(|member| {
+                let score = self.dict[&member];
+                (member, score)
+            })
+            .collect();
+        (entries, cursor)
+    }
+}